@@ -0,0 +1,94 @@
+//! Cardinality and optionality of relationship ends, inferred from the
+//! foreign key columns and stored in the IR, so the editor and every
+//! exporter draw the same markers.
+//!
+//! The referenced end is one row, or none if the foreign key can be null.
+//! The referencing end is one row if the foreign key is unique, and many
+//! otherwise; declared one-to-one relationships are one at both ends. It is
+//! always optional: a foreign key says every child has a parent, not that
+//! every parent has children.
+
+use crate::graph::table_index;
+use crate::ir::{Cardinality, Diagram, RelationType, Relationship};
+
+/// Fill in `Relationship::cardinality` for every relationship.
+pub fn infer(diagram: &mut Diagram) {
+    let cardinalities: Vec<[Cardinality; 2]> = diagram
+        .relationships
+        .iter()
+        .map(|rel| ends(diagram, rel))
+        .collect();
+    for (rel, cardinality) in diagram.relationships.iter_mut().zip(cardinalities) {
+        rel.cardinality = cardinality;
+    }
+}
+
+fn ends(diagram: &Diagram, rel: &Relationship) -> [Cardinality; 2] {
+    use Cardinality::*;
+
+    let Some((child, _)) = rel.foreign_key() else {
+        return [ManyOptional, ManyOptional];
+    };
+    let table = table_index(diagram, &child.table_id).map(|i| &diagram.tables[i]);
+    // A foreign key with a null column references nothing
+    let optional = match table {
+        Some(t) => {
+            child.column_names.is_empty() || child.column_names.iter().any(|c| t.is_nullable(c))
+        }
+        None => true,
+    };
+    let one = rel.relation_type == RelationType::OneToOne
+        || table.is_some_and(|t| t.is_unique_key(&child.column_names));
+
+    let referencing = if one { OneOptional } else { ManyOptional };
+    let referenced = if optional { OneOptional } else { OneMandatory };
+    if rel.relation_type == RelationType::OneToMany {
+        [referenced, referencing]
+    } else {
+        [referencing, referenced]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::Cardinality::*;
+    use crate::parser::parse_dbml;
+
+    #[test]
+    fn test_infer_cardinality() {
+        let diagram = parse_dbml(
+            "Project p {\n  database_type: 'PostgreSQL'\n}\n\
+             Table users {\n  id int [pk]\n}\n\
+             Table profiles {\n  user_id int [pk, ref: > users.id]\n}\n\
+             Table accounts {\n  id int [pk]\n  user_id int [unique, not null, ref: > users.id]\n}\n\
+             Table posts {\n  id int [pk]\n  author_id int [not null, ref: > users.id]\n\
+               editor_id int\n  indexes {\n    (author_id, id) [unique]\n  }\n}\n\
+             Table shares {\n  user_id int\n  post_id int\n  indexes {\n    (user_id, post_id) [unique]\n  }\n}\n\
+             Ref: shares.(user_id, post_id) > posts.(author_id, id)\n\
+             Ref: users.id < posts.editor_id\n\
+             Ref: users.id <> posts.id\n",
+        )
+        .unwrap();
+        let cardinality: Vec<_> = diagram
+            .relationships
+            .iter()
+            .map(|r| r.cardinality)
+            .collect();
+
+        assert_eq!(
+            cardinality,
+            [
+                // Composite unique key, columns nullable
+                [OneOptional, OneOptional],
+                [OneOptional, ManyOptional],
+                [ManyOptional, ManyOptional],
+                // A single-column primary key is not null and unique, but a
+                // user need not have a profile
+                [OneOptional, OneMandatory],
+                // Unique and not null
+                [OneOptional, OneMandatory],
+                [ManyOptional, OneMandatory],
+            ]
+        );
+    }
+}
@@ -1,8 +1,26 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use std::path::PathBuf;
 
+use crate::diff::Renames;
+use crate::docs::DocsFormat;
+use crate::error::AppError;
+use crate::export::notation::Notation;
+use crate::export::options::ExportOptions;
+use crate::export::sql::Dialect;
+use crate::export::ExportFormat;
+use crate::filter::{HiddenRefs, TableFilter};
+use crate::focus::Focus;
+use crate::graph::Direction;
+use crate::ir::Diagram;
+use crate::parser::InputFormat;
+use crate::stats::StatsFormat;
+
 #[derive(Parser, Debug)]
-#[command(name = "dbml-draw", version, about = "Generate ER diagrams from DBML files")]
+#[command(
+    name = "dbml-draw",
+    version,
+    about = "Generate ER diagrams from DBML files"
+)]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
@@ -10,9 +28,268 @@ pub struct Cli {
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
-    /// Open an interactive viewer for a DBML file
+    /// Open an interactive viewer for a DBML or SQL file
     Open {
+        /// Input file path
+        input: PathBuf,
+        /// Input format (detected from the file extension if omitted)
+        #[arg(long, value_enum)]
+        from: Option<InputFormat>,
+        #[command(flatten)]
+        filter: FilterArgs,
+        #[command(flatten)]
+        focus: FocusArgs,
+    },
+    /// Export a DBML file to another diagram format
+    Export {
+        /// Input file path
+        input: PathBuf,
+        /// Input format (detected from the file extension if omitted)
+        #[arg(long, value_enum)]
+        from: Option<InputFormat>,
+        /// Output format
+        #[arg(short, long, value_enum)]
+        format: ExportFormat,
+        /// Output file path (defaults to the input path with the format's extension)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        image: ImageArgs,
+        #[command(flatten)]
+        filter: FilterArgs,
+        #[command(flatten)]
+        focus: FocusArgs,
+    },
+    /// Reformat a DBML file in place
+    Fmt {
         /// Input DBML file path
         input: PathBuf,
+        /// Exit with an error instead of writing if the file is not formatted
+        #[arg(long)]
+        check: bool,
+    },
+    /// Generate SQL DDL from a DBML file
+    Sql {
+        /// Input file path
+        input: PathBuf,
+        /// Input format (detected from the file extension if omitted)
+        #[arg(long, value_enum)]
+        from: Option<InputFormat>,
+        /// SQL dialect
+        #[arg(long, value_enum)]
+        dialect: Dialect,
+        /// Output file path (prints to stdout if omitted)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Generate per-table documentation pages
+    Docs {
+        /// Input file path
+        input: PathBuf,
+        /// Input format (detected from the file extension if omitted)
+        #[arg(long, value_enum)]
+        from: Option<InputFormat>,
+        /// Output directory
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Page format
+        #[arg(long, value_enum, default_value = "markdown")]
+        format: DocsFormat,
+    },
+    /// List the shortest join paths between two tables
+    Path {
+        /// Input file path
+        input: PathBuf,
+        /// Table to start from, as `schema.table` or just `table`
+        table_a: String,
+        /// Table to reach
+        table_b: String,
+        /// Input format (detected from the file extension if omitted)
+        #[arg(long, value_enum)]
+        from: Option<InputFormat>,
+        /// Most paths to list
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Report table counts, connectivity and foreign key structure
+    Stats {
+        /// Input file path
+        input: PathBuf,
+        /// Input format (detected from the file extension if omitted)
+        #[arg(long, value_enum)]
+        from: Option<InputFormat>,
+        /// Report format
+        #[arg(long, value_enum, default_value = "text")]
+        format: StatsFormat,
+    },
+    /// List the indexes, relationships and columns that depend on a column
+    Impact {
+        /// Input file path
+        input: PathBuf,
+        /// Column as `schema.table.column` or `table.column`
+        column: String,
+        /// Input format (detected from the file extension if omitted)
+        #[arg(long, value_enum)]
+        from: Option<InputFormat>,
+    },
+    /// List tables in foreign key dependency order, parents first
+    Order {
+        /// Input file path
+        input: PathBuf,
+        /// Input format (detected from the file extension if omitted)
+        #[arg(long, value_enum)]
+        from: Option<InputFormat>,
+        /// Children first, for deleting
+        #[arg(long)]
+        reverse: bool,
+    },
+    /// Compare two schema files and report the structural changes
+    Diff {
+        #[command(flatten)]
+        versions: Versions,
+        /// Input format of both files (detected from the file extensions if omitted)
+        #[arg(long, value_enum)]
+        from: Option<InputFormat>,
+        /// Also write an SVG diagram with the changes highlighted
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        image: ImageArgs,
+    },
+    /// Generate a SQL migration from one version of a schema to another
+    Migrate {
+        #[command(flatten)]
+        versions: Versions,
+        /// Input format of both files (detected from the file extensions if omitted)
+        #[arg(long, value_enum)]
+        from: Option<InputFormat>,
+        /// SQL dialect
+        #[arg(long, value_enum)]
+        dialect: Dialect,
+        #[command(flatten)]
+        renames: RenameArgs,
+        /// Output file path (prints to stdout if omitted)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
     },
 }
+
+/// The two versions of a schema that `diff` and `migrate` compare.
+#[derive(Args, Debug)]
+pub struct Versions {
+    /// Older schema file, or with --rev the schema file to compare
+    pub old: PathBuf,
+    /// Newer schema file
+    #[arg(required_unless_present = "rev", conflicts_with = "rev")]
+    pub new: Option<PathBuf>,
+    /// Compare the file with its version at this git revision
+    #[arg(long)]
+    pub rev: Option<String>,
+}
+
+/// Renames to apply instead of dropping and adding.
+#[derive(Args, Debug)]
+pub struct RenameArgs {
+    /// Table renamed from OLD to NEW, e.g. `user=users` (repeatable)
+    #[arg(long = "rename-table", value_name = "OLD=NEW")]
+    pub tables: Vec<String>,
+    /// Column renamed, e.g. `users.mail=email`, with the table's new name (repeatable)
+    #[arg(long = "rename-column", value_name = "TABLE.OLD=NEW")]
+    pub columns: Vec<String>,
+}
+
+impl RenameArgs {
+    pub fn renames(&self) -> Result<Renames, AppError> {
+        let mut renames = Renames::default();
+        for spec in &self.tables {
+            renames.add_table(spec)?;
+        }
+        for spec in &self.columns {
+            renames.add_column(spec)?;
+        }
+        Ok(renames)
+    }
+}
+
+/// Table filters; they add to the project config file.
+#[derive(Args, Debug)]
+pub struct FilterArgs {
+    /// Show only tables matching this glob, e.g. `billing.*` (repeatable)
+    #[arg(long, value_name = "PATTERN")]
+    pub include: Vec<String>,
+    /// Hide tables matching this glob, e.g. `*_history` (repeatable)
+    #[arg(long, value_name = "PATTERN")]
+    pub exclude: Vec<String>,
+    /// What to do with relationships to hidden tables
+    #[arg(long, value_enum)]
+    pub hidden_refs: Option<HiddenRefs>,
+    /// Draw junction tables as many-to-many relationships
+    #[arg(long)]
+    pub collapse_junctions: bool,
+}
+
+impl FilterArgs {
+    pub fn apply(&self, filter: &mut TableFilter) {
+        filter.include.extend(self.include.iter().cloned());
+        filter.exclude.extend(self.exclude.iter().cloned());
+        filter.hidden_refs = self.hidden_refs.unwrap_or(filter.hidden_refs);
+        filter.collapse_junctions |= self.collapse_junctions;
+    }
+}
+
+/// Show only the tables around one table.
+#[derive(Args, Debug)]
+pub struct FocusArgs {
+    /// Table to focus on, as `schema.table` or just `table`
+    #[arg(long, value_name = "TABLE")]
+    pub focus: Option<String>,
+    /// Number of relationships to follow from the focused table
+    #[arg(long, default_value_t = 1, requires = "focus")]
+    pub depth: usize,
+    /// Which relationships to follow
+    #[arg(long, value_enum, default_value = "both", requires = "focus")]
+    pub direction: Direction,
+}
+
+impl FocusArgs {
+    pub fn focus(&self, diagram: &Diagram) -> Result<Option<Focus>, AppError> {
+        self.focus
+            .as_deref()
+            .map(|name| Focus::new(diagram, name, self.depth, self.direction))
+            .transpose()
+    }
+}
+
+/// Image export options; they override the project config file.
+#[derive(Args, Debug)]
+pub struct ImageArgs {
+    /// Image scale factor (SVG)
+    #[arg(long)]
+    pub scale: Option<f64>,
+    /// Space around the diagram (SVG)
+    #[arg(long)]
+    pub padding: Option<f64>,
+    /// Leave out the background (SVG)
+    #[arg(long)]
+    pub transparent: bool,
+    /// Draw a title block above the diagram (SVG)
+    #[arg(long)]
+    pub title: bool,
+    /// How relationship ends are drawn
+    #[arg(long, value_enum)]
+    pub notation: Option<Notation>,
+    /// Draw a legend of the notation below the diagram
+    #[arg(long)]
+    pub legend: bool,
+}
+
+impl ImageArgs {
+    pub fn apply(&self, options: &mut ExportOptions) {
+        options.scale = self.scale.unwrap_or(options.scale);
+        options.padding = self.padding.unwrap_or(options.padding);
+        options.transparent |= self.transparent;
+        options.title |= self.title;
+        options.notation = self.notation.unwrap_or(options.notation);
+        options.legend |= self.legend;
+    }
+}
@@ -0,0 +1,164 @@
+//! Project configuration file.
+//!
+//! `dbml-draw.toml` in the input's directory or the closest parent
+//! directory holding one:
+//! ```toml
+//! [export]
+//! output_dir = "docs/img"
+//! scale = 3
+//! padding = 24
+//! transparent = true
+//! title = true
+//! notation = "uml"
+//! legend = true
+//!
+//! [filter]
+//! include = ["billing.*"]
+//! exclude = ["*_history"]
+//! hidden_refs = "stub"
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::error::AppError;
+use crate::export::notation::Notation;
+use crate::export::options::ExportOptions;
+use crate::filter::TableFilter;
+
+pub const FILE_NAME: &str = "dbml-draw.toml";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    pub export: ExportConfig,
+    /// Tables to draw.
+    pub filter: TableFilter,
+    /// Directory of the config file; relative paths are resolved from it.
+    #[serde(skip)]
+    pub dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExportConfig {
+    /// Directory exports are written to, named after the input file.
+    pub output_dir: Option<PathBuf>,
+    pub scale: Option<f64>,
+    pub padding: Option<f64>,
+    pub transparent: Option<bool>,
+    pub title: Option<bool>,
+    pub notation: Option<Notation>,
+    pub legend: Option<bool>,
+}
+
+impl ExportConfig {
+    /// The configured options, defaults for the rest.
+    pub fn options(&self) -> ExportOptions {
+        let defaults = ExportOptions::default();
+        ExportOptions {
+            scale: self.scale.unwrap_or(defaults.scale),
+            padding: self.padding.unwrap_or(defaults.padding),
+            transparent: self.transparent.unwrap_or(defaults.transparent),
+            title: self.title.unwrap_or(defaults.title),
+            notation: self.notation.unwrap_or(defaults.notation),
+            legend: self.legend.unwrap_or(defaults.legend),
+        }
+    }
+}
+
+impl ProjectConfig {
+    /// The config that applies to `input`, or the defaults if there is none.
+    pub fn for_input(input: &Path) -> Result<Self, AppError> {
+        let start = input
+            .canonicalize()
+            .ok()
+            .and_then(|p| p.parent().map(Path::to_path_buf))
+            .unwrap_or_default();
+        match start
+            .ancestors()
+            .map(|d| d.join(FILE_NAME))
+            .find(|p| p.is_file())
+        {
+            Some(path) => Self::read(&path),
+            None => Ok(Self {
+                dir: start,
+                ..Default::default()
+            }),
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<Self, AppError> {
+        let config_error =
+            |e: &dyn std::fmt::Display| AppError::ConfigError(format!("{}: {}", path.display(), e));
+        let content = std::fs::read_to_string(path).map_err(|e| config_error(&e))?;
+        let mut config: Self = toml::from_str(&content).map_err(|e| config_error(&e))?;
+        config
+            .export
+            .options()
+            .validate()
+            .map_err(|e| config_error(&e))?;
+        config.dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(config)
+    }
+
+    /// Where an export of `input` with `extension` goes by default. The
+    /// configured `output_dir` is only created when an export is written
+    /// (see `options::create_output_dir`).
+    pub fn export_path(&self, input: &Path, extension: &str) -> PathBuf {
+        match &self.export.output_dir {
+            Some(dir) => {
+                let name = input.with_extension(extension);
+                self.dir
+                    .join(dir)
+                    .join(name.file_name().unwrap_or_default())
+            }
+            None => input.with_extension(extension),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::HiddenRefs;
+
+    #[test]
+    fn test_found_in_parent_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(FILE_NAME),
+            "[export]\noutput_dir = \"img\"\nscale = 3\ntitle = true\nnotation = \"idef1x\"\n\n\
+             [filter]\nexclude = [\"audit.*\"]\nhidden_refs = \"stub\"\n",
+        )
+        .unwrap();
+        std::fs::create_dir(dir.path().join("db")).unwrap();
+        let input = dir.path().join("db/schema.dbml");
+        std::fs::write(&input, "").unwrap();
+
+        let config = ProjectConfig::for_input(&input).unwrap();
+        let options = config.export.options();
+        assert_eq!(options.scale, 3.0);
+        assert_eq!(options.padding, 50.0);
+        assert!(options.title);
+        assert_eq!(options.notation, Notation::Idef1x);
+        assert_eq!(config.filter.exclude, ["audit.*"]);
+        assert_eq!(config.filter.hidden_refs, HiddenRefs::Stub);
+        assert_eq!(
+            config.export_path(&input, "svg"),
+            dir.path().canonicalize().unwrap().join("img/schema.svg")
+        );
+        assert!(!dir.path().join("img").exists());
+    }
+
+    #[test]
+    fn test_invalid_config_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(FILE_NAME);
+        std::fs::write(&path, "[export]\nscale = -1\n").unwrap();
+        assert!(ProjectConfig::read(&path).is_err());
+        std::fs::write(&path, "[export]\nscael = 2\n").unwrap();
+        assert!(ProjectConfig::read(&path).is_err());
+    }
+}
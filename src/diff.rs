@@ -0,0 +1,896 @@
+//! Structural comparison of two diagrams.
+//!
+//! Tables are matched by `TableId`. A removed and an added table that share
+//! most of their column names are taken to be a rename, unless the renames
+//! are declared (see `Renames`). Matched tables are compared column by
+//! column (name, type, nullability, primary key, uniqueness and default)
+//! and index by index, relationships by the tables and columns they connect
+//! and their referential actions.
+
+use std::collections::HashSet;
+
+use crate::error::AppError;
+use crate::export::dbml::{relation_symbol, write_default};
+use crate::export::style::{ADDED_COLOR, MODIFIED_COLOR, REMOVED_COLOR};
+use crate::export::svg::Highlights;
+use crate::ir::{
+    Column, Diagram, EndPoint, Index, IndexColumn, Position, ReferentialAction, RelationType,
+    Relationship, Table, TableId,
+};
+use crate::layout::metrics::{diagram_bounds, table_height, table_width};
+
+/// Space between the new diagram and the removed tables drawn below it.
+const REMOVED_GAP: f64 = 80.0;
+
+#[derive(Debug)]
+pub struct SchemaDiff<'a> {
+    pub old: &'a Diagram,
+    pub new: &'a Diagram,
+    pub added_tables: Vec<TableId>,
+    pub removed_tables: Vec<TableId>,
+    /// (old, new) ids.
+    pub renamed_tables: Vec<(TableId, TableId)>,
+    /// Tables in both diagrams whose columns or indexes changed.
+    pub changed_tables: Vec<TableChanges<'a>>,
+    /// Indices into `new.relationships`.
+    pub added_relationships: Vec<usize>,
+    /// Indices into `old.relationships`.
+    pub removed_relationships: Vec<usize>,
+}
+
+#[derive(Debug)]
+pub struct TableChanges<'a> {
+    pub old_id: TableId,
+    pub id: TableId,
+    /// The table before and after.
+    pub old_table: &'a Table,
+    pub new_table: &'a Table,
+    pub columns: Vec<ColumnChange<'a>>,
+    pub indexes: Vec<IndexChange<'a>>,
+}
+
+#[derive(Debug)]
+pub enum ColumnChange<'a> {
+    Added(&'a Column),
+    Removed(&'a Column),
+    /// Renamed, or type, nullability, primary key, uniqueness or default
+    /// changed.
+    Modified {
+        old: &'a Column,
+        new: &'a Column,
+    },
+}
+
+#[derive(Debug)]
+pub enum IndexChange<'a> {
+    Added(&'a Index),
+    Removed(&'a Index),
+}
+
+/// Renames stated up front instead of guessed, e.g. for migrations.
+#[derive(Debug, Clone, Default)]
+pub struct Renames {
+    /// (old, new) ids.
+    pub tables: Vec<(TableId, TableId)>,
+    /// Table id in the new schema, old and new column name.
+    pub columns: Vec<(TableId, String, String)>,
+}
+
+impl Renames {
+    /// Add a table rename written `old=new`, e.g. `user=users` or
+    /// `public.user=auth.users`. Names without a schema are in `public`.
+    pub fn add_table(&mut self, spec: &str) -> Result<(), AppError> {
+        let (old, new) = split_rename(spec)?;
+        self.tables.push((parse_table_id(old), parse_table_id(new)));
+        Ok(())
+    }
+
+    /// Add a column rename written `table.old=new`, with the table named as
+    /// in the new schema.
+    pub fn add_column(&mut self, spec: &str) -> Result<(), AppError> {
+        let (old, new) = split_rename(spec)?;
+        let (table, column) = old.rsplit_once('.').ok_or_else(|| {
+            AppError::InvalidRename(format!("{}: expected table.old_column=new_column", spec))
+        })?;
+        self.columns
+            .push((parse_table_id(table), column.to_string(), new.to_string()));
+        Ok(())
+    }
+
+    /// New name of column `name` in the table that is `table` in the new schema.
+    fn column<'s>(&'s self, table: &TableId, name: &'s str) -> &'s str {
+        self.columns
+            .iter()
+            .find(|(t, old, _)| t == table && old == name)
+            .map_or(name, |(_, _, new)| new.as_str())
+    }
+}
+
+fn split_rename(spec: &str) -> Result<(&str, &str), AppError> {
+    match spec.split_once('=') {
+        Some((old, new)) if !old.trim().is_empty() && !new.trim().is_empty() => {
+            Ok((old.trim(), new.trim()))
+        }
+        _ => Err(AppError::InvalidRename(format!(
+            "{}: expected old=new",
+            spec
+        ))),
+    }
+}
+
+fn parse_table_id(name: &str) -> TableId {
+    match name.split_once('.') {
+        Some((schema, name)) => TableId::new(schema, name),
+        None => TableId::new("public", name),
+    }
+}
+
+/// Compare `old` with `new`, guessing which tables were renamed.
+pub fn diff<'a>(old: &'a Diagram, new: &'a Diagram) -> SchemaDiff<'a> {
+    compare(old, new, None)
+}
+
+/// Compare `old` with `new`. Only the declared renames are renames; every
+/// other name change is a removal and an addition.
+pub fn diff_with_renames<'a>(
+    old: &'a Diagram,
+    new: &'a Diagram,
+    renames: &Renames,
+) -> Result<SchemaDiff<'a>, AppError> {
+    let find = |diagram: &'a Diagram, id: &TableId| diagram.tables.iter().find(|t| t.id == *id);
+    for (old_id, new_id) in &renames.tables {
+        if find(old, old_id).is_none() || find(new, old_id).is_some() {
+            return Err(AppError::InvalidRename(format!(
+                "{} is not a table that was removed",
+                old_id
+            )));
+        }
+        if find(new, new_id).is_none() || find(old, new_id).is_some() {
+            return Err(AppError::InvalidRename(format!(
+                "{} is not a table that was added",
+                new_id
+            )));
+        }
+    }
+    for (table, old_name, new_name) in &renames.columns {
+        let old_id = renames
+            .tables
+            .iter()
+            .find(|(_, n)| n == table)
+            .map_or(table, |(o, _)| o);
+        let has = |diagram: &'a Diagram, id: &TableId, name: &str| {
+            find(diagram, id).is_some_and(|t| t.columns.iter().any(|c| c.name == name))
+        };
+        if !has(old, old_id, old_name) || !has(new, table, new_name) {
+            return Err(AppError::InvalidRename(format!(
+                "{}.{} to {}: no such columns in the old and new schema",
+                table, old_name, new_name
+            )));
+        }
+    }
+    Ok(compare(old, new, Some(renames)))
+}
+
+fn compare<'a>(old: &'a Diagram, new: &'a Diagram, declared: Option<&Renames>) -> SchemaDiff<'a> {
+    let old_ids: HashSet<&TableId> = old.tables.iter().map(|t| &t.id).collect();
+    let new_ids: HashSet<&TableId> = new.tables.iter().map(|t| &t.id).collect();
+    let mut removed: Vec<TableId> = old
+        .tables
+        .iter()
+        .filter(|t| !new_ids.contains(&t.id))
+        .map(|t| t.id.clone())
+        .collect();
+    let mut added: Vec<TableId> = new
+        .tables
+        .iter()
+        .filter(|t| !old_ids.contains(&t.id))
+        .map(|t| t.id.clone())
+        .collect();
+
+    let renames = match declared {
+        Some(renames) => renames.clone(),
+        None => Renames {
+            tables: detect_renames(old, new, &removed, &added),
+            columns: Vec::new(),
+        },
+    };
+    let renamed = renames.tables.clone();
+    removed.retain(|id| !renamed.iter().any(|(o, _)| o == id));
+    added.retain(|id| !renamed.iter().any(|(_, n)| n == id));
+
+    // Old id -> new id of every table in both diagrams
+    let mut matched: Vec<(TableId, TableId)> = old
+        .tables
+        .iter()
+        .filter(|t| new_ids.contains(&t.id))
+        .map(|t| (t.id.clone(), t.id.clone()))
+        .collect();
+    matched.extend(renamed.iter().cloned());
+
+    let changed_tables = matched
+        .iter()
+        .filter_map(|(old_id, new_id)| {
+            let old_table = old.tables.iter().find(|t| t.id == *old_id)?;
+            let new_table = new.tables.iter().find(|t| t.id == *new_id)?;
+            let rename = |name: &str| renames.column(new_id, name).to_string();
+            let columns = column_changes(old_table, new_table, &rename);
+            let indexes = index_changes(old_table, new_table, &rename);
+            (!columns.is_empty() || !indexes.is_empty()).then(|| TableChanges {
+                old_id: old_id.clone(),
+                id: new_id.clone(),
+                old_table,
+                new_table,
+                columns,
+                indexes,
+            })
+        })
+        .collect();
+
+    // Old endpoints are compared under their new names
+    let old_keys: Vec<RelKey> = old
+        .relationships
+        .iter()
+        .map(|r| {
+            rel_key(r, |ep| {
+                let id = renamed
+                    .iter()
+                    .find(|(o, _)| *o == ep.table_id)
+                    .map_or(&ep.table_id, |(_, n)| n);
+                let cols = ep
+                    .column_names
+                    .iter()
+                    .map(|c| renames.column(id, c).to_string())
+                    .collect();
+                (id.clone(), cols)
+            })
+        })
+        .collect();
+    let new_keys: Vec<RelKey> = new
+        .relationships
+        .iter()
+        .map(|r| rel_key(r, |ep| (ep.table_id.clone(), ep.column_names.clone())))
+        .collect();
+    let added_relationships = (0..new_keys.len())
+        .filter(|&i| !old_keys.contains(&new_keys[i]))
+        .collect();
+    let removed_relationships = (0..old_keys.len())
+        .filter(|&i| !new_keys.contains(&old_keys[i]))
+        .collect();
+
+    SchemaDiff {
+        old,
+        new,
+        added_tables: added,
+        removed_tables: removed,
+        renamed_tables: renamed,
+        changed_tables,
+        added_relationships,
+        removed_relationships,
+    }
+}
+
+/// Pair removed with added tables that share more than half of their
+/// column names, best matches first.
+fn detect_renames(
+    old: &Diagram,
+    new: &Diagram,
+    removed: &[TableId],
+    added: &[TableId],
+) -> Vec<(TableId, TableId)> {
+    let columns = |diagram: &Diagram, id: &TableId| -> HashSet<String> {
+        diagram
+            .tables
+            .iter()
+            .find(|t| t.id == *id)
+            .map(|t| t.columns.iter().map(|c| c.name.clone()).collect())
+            .unwrap_or_default()
+    };
+
+    let mut candidates = Vec::new();
+    for old_id in removed {
+        let old_cols = columns(old, old_id);
+        for new_id in added {
+            let new_cols = columns(new, new_id);
+            let common = old_cols.intersection(&new_cols).count();
+            let size = old_cols.len().max(new_cols.len());
+            if common * 2 > size {
+                candidates.push((common as f64 / size as f64, old_id, new_id));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut renamed: Vec<(TableId, TableId)> = Vec::new();
+    for (_, old_id, new_id) in candidates {
+        if !renamed.iter().any(|(o, n)| o == old_id || n == new_id) {
+            renamed.push((old_id.clone(), new_id.clone()));
+        }
+    }
+    renamed
+}
+
+/// `rename` maps an old column name to its new name.
+fn column_changes<'a>(
+    old_table: &'a Table,
+    new_table: &'a Table,
+    rename: &dyn Fn(&str) -> String,
+) -> Vec<ColumnChange<'a>> {
+    let mut changes = Vec::new();
+    for old_col in &old_table.columns {
+        let name = rename(&old_col.name);
+        match new_table.columns.iter().find(|c| c.name == name) {
+            None => changes.push(ColumnChange::Removed(old_col)),
+            Some(new_col) => {
+                if old_col.name != new_col.name
+                    || old_col.type_raw != new_col.type_raw
+                    || old_table.is_nullable(&old_col.name) != new_table.is_nullable(&new_col.name)
+                    || is_key(old_table, old_col) != is_key(new_table, new_col)
+                    || old_col.is_unique != new_col.is_unique
+                    || old_col.default != new_col.default
+                {
+                    changes.push(ColumnChange::Modified {
+                        old: old_col,
+                        new: new_col,
+                    });
+                }
+            }
+        }
+    }
+    for new_col in &new_table.columns {
+        if !old_table
+            .columns
+            .iter()
+            .any(|c| rename(&c.name) == new_col.name)
+        {
+            changes.push(ColumnChange::Added(new_col));
+        }
+    }
+    changes
+}
+
+fn index_changes<'a>(
+    old_table: &'a Table,
+    new_table: &'a Table,
+    rename: &dyn Fn(&str) -> String,
+) -> Vec<IndexChange<'a>> {
+    let key = |index: &Index, rename: &dyn Fn(&str) -> String| {
+        let columns: Vec<IndexColumn> = index
+            .columns
+            .iter()
+            .map(|c| match c {
+                IndexColumn::Column(name) => IndexColumn::Column(rename(name)),
+                IndexColumn::Expression(e) => IndexColumn::Expression(e.clone()),
+            })
+            .collect();
+        (
+            index.name.clone(),
+            columns,
+            index.is_unique,
+            index.is_pk,
+            index.index_type.clone(),
+        )
+    };
+    let old_keys: Vec<_> = old_table.indexes.iter().map(|i| key(i, rename)).collect();
+    let new_keys: Vec<_> = new_table
+        .indexes
+        .iter()
+        .map(|i| key(i, &|name: &str| name.to_string()))
+        .collect();
+
+    let mut changes: Vec<IndexChange> = old_table
+        .indexes
+        .iter()
+        .zip(&old_keys)
+        .filter(|(_, k)| !new_keys.contains(k))
+        .map(|(i, _)| IndexChange::Removed(i))
+        .collect();
+    changes.extend(
+        new_table
+            .indexes
+            .iter()
+            .zip(&new_keys)
+            .filter(|(_, k)| !old_keys.contains(k))
+            .map(|(i, _)| IndexChange::Added(i)),
+    );
+    changes
+}
+
+/// Whether `col` is part of the primary key of `table`.
+pub(crate) fn is_key(table: &Table, col: &Column) -> bool {
+    table.primary_key().contains(&col.name.as_str())
+}
+
+/// What makes two relationships the same: the columns of the foreign key
+/// (referencing side first), whether it is one-to-one and what happens on
+/// delete and update.
+type RelKey = (
+    Option<RelationType>,
+    TableId,
+    Vec<String>,
+    TableId,
+    Vec<String>,
+    [Option<ReferentialAction>; 2],
+);
+
+/// `end` gives the table and columns an endpoint is compared by.
+fn rel_key(rel: &Relationship, end: impl Fn(&EndPoint) -> (TableId, Vec<String>)) -> RelKey {
+    let actions = [rel.on_delete, rel.on_update];
+    match rel.foreign_key() {
+        Some((child, parent)) => {
+            let one =
+                (rel.relation_type == RelationType::OneToOne).then_some(RelationType::OneToOne);
+            let ((c, c_cols), (p, p_cols)) = (end(child), end(parent));
+            (one, c, c_cols, p, p_cols, actions)
+        }
+        None => {
+            let mut ends = [end(&rel.from), end(&rel.to)];
+            ends.sort();
+            let [(a, a_cols), (b, b_cols)] = ends;
+            (
+                Some(RelationType::ManyToMany),
+                a,
+                a_cols,
+                b,
+                b_cols,
+                actions,
+            )
+        }
+    }
+}
+
+impl SchemaDiff<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.added_tables.is_empty()
+            && self.removed_tables.is_empty()
+            && self.renamed_tables.is_empty()
+            && self.changed_tables.is_empty()
+            && self.added_relationships.is_empty()
+            && self.removed_relationships.is_empty()
+    }
+
+    /// The changes as text, one per line: `+` added, `-` removed, `~`
+    /// renamed or modified.
+    pub fn report(&self) -> String {
+        if self.is_empty() {
+            return "No structural changes\n".to_string();
+        }
+
+        let mut out = String::new();
+        for id in &self.added_tables {
+            out.push_str(&format!("+ table {}\n", id));
+        }
+        for id in &self.removed_tables {
+            out.push_str(&format!("- table {}\n", id));
+        }
+        for (old_id, new_id) in &self.renamed_tables {
+            if !self.changed_tables.iter().any(|t| t.id == *new_id) {
+                out.push_str(&format!("~ table {} renamed to {}\n", old_id, new_id));
+            }
+        }
+        for table in &self.changed_tables {
+            if table.old_id == table.id {
+                out.push_str(&format!("~ table {}\n", table.id));
+            } else {
+                out.push_str(&format!(
+                    "~ table {} renamed to {}\n",
+                    table.old_id, table.id
+                ));
+            }
+            for change in &table.columns {
+                out.push_str(&format!(
+                    "    {}\n",
+                    describe_column(change, table.old_table, table.new_table)
+                ));
+            }
+            for change in &table.indexes {
+                out.push_str(&format!("    {}\n", describe_index(change)));
+            }
+        }
+        for &i in &self.added_relationships {
+            out.push_str(&format!(
+                "+ ref {}\n",
+                describe_ref(&self.new.relationships[i])
+            ));
+        }
+        for &i in &self.removed_relationships {
+            out.push_str(&format!(
+                "- ref {}\n",
+                describe_ref(&self.old.relationships[i])
+            ));
+        }
+        out
+    }
+
+    /// The new diagram with the removed tables, columns and relationships
+    /// put back, and colors marking every change.
+    ///
+    /// The new diagram's tables must have positions. Removed tables keep
+    /// their old position if nothing is in the way now, otherwise they are
+    /// placed in a row below.
+    pub fn highlighted_diagram(&self) -> (Diagram, Highlights) {
+        let mut diagram = self.new.clone();
+        let mut highlights = Highlights::default();
+
+        for id in &self.added_tables {
+            highlights.tables.insert(id.clone(), ADDED_COLOR);
+        }
+        for (_, new_id) in &self.renamed_tables {
+            highlights.tables.insert(new_id.clone(), MODIFIED_COLOR);
+        }
+        for changes in &self.changed_tables {
+            highlights.tables.insert(changes.id.clone(), MODIFIED_COLOR);
+            let old_table = self.old.tables.iter().find(|t| t.id == changes.old_id);
+            let Some(table) = diagram.tables.iter_mut().find(|t| t.id == changes.id) else {
+                continue;
+            };
+            for change in &changes.columns {
+                let (col, color) = match change {
+                    ColumnChange::Added(col) => (col, ADDED_COLOR),
+                    ColumnChange::Modified { new, .. } => (new, MODIFIED_COLOR),
+                    ColumnChange::Removed(col) => {
+                        // Back where it was in the old table
+                        let index = old_table
+                            .and_then(|t| t.columns.iter().position(|c| c.name == col.name))
+                            .unwrap_or(table.columns.len())
+                            .min(table.columns.len());
+                        table.columns.insert(index, (*col).clone());
+                        (col, REMOVED_COLOR)
+                    }
+                };
+                highlights
+                    .columns
+                    .insert((changes.id.clone(), col.name.clone()), color);
+            }
+        }
+
+        let (mut x, bottom) = diagram_bounds(&diagram)
+            .map(|(min_x, _, _, max_y)| (min_x, max_y + REMOVED_GAP))
+            .unwrap_or((0.0, 0.0));
+        for id in &self.removed_tables {
+            let Some(table) = self.old.tables.iter().find(|t| t.id == *id) else {
+                continue;
+            };
+            let mut table = table.clone();
+            // Where it used to be if that spot is still free
+            if !table.position.is_some_and(|p| is_free(&diagram, &table, p)) {
+                table.position = Some(Position { x, y: bottom });
+                x += table_width(&table) + REMOVED_GAP / 2.0;
+            }
+            highlights.tables.insert(table.id.clone(), REMOVED_COLOR);
+            diagram.tables.push(table);
+        }
+
+        for &i in &self.added_relationships {
+            highlights.relationships.insert(i, ADDED_COLOR);
+        }
+        for &i in &self.removed_relationships {
+            let mut rel = self.old.relationships[i].clone();
+            for ep in [&mut rel.from, &mut rel.to] {
+                if let Some((_, new_id)) =
+                    self.renamed_tables.iter().find(|(o, _)| *o == ep.table_id)
+                {
+                    ep.table_id = new_id.clone();
+                }
+            }
+            highlights
+                .relationships
+                .insert(diagram.relationships.len(), REMOVED_COLOR);
+            diagram.relationships.push(rel);
+        }
+
+        (diagram, highlights)
+    }
+}
+
+/// Whether `table` placed at `at` stays clear of every table in `diagram`.
+fn is_free(diagram: &Diagram, table: &Table, at: Position) -> bool {
+    let margin = REMOVED_GAP / 2.0;
+    let (w, h) = (table_width(table), table_height(table));
+    diagram.tables.iter().all(|other| {
+        let Some(p) = other.position else {
+            return true;
+        };
+        at.x + w + margin <= p.x
+            || p.x + table_width(other) + margin <= at.x
+            || at.y + h + margin <= p.y
+            || p.y + table_height(other) + margin <= at.y
+    })
+}
+
+/// `old_table` and `new_table` are the table the change is in, before and after.
+fn describe_column(change: &ColumnChange, old_table: &Table, new_table: &Table) -> String {
+    let null = |table: &Table, col: &Column| {
+        if table.is_nullable(&col.name) {
+            "null"
+        } else {
+            "not null"
+        }
+    };
+    match change {
+        ColumnChange::Added(col) => format!("+ column {} {}", col.name, col.type_raw),
+        ColumnChange::Removed(col) => format!("- column {} {}", col.name, col.type_raw),
+        ColumnChange::Modified { old, new } => {
+            let mut parts = Vec::new();
+            if old.name != new.name {
+                parts.push(format!("renamed to {}", new.name));
+            }
+            if old.type_raw != new.type_raw {
+                parts.push(format!("type {} -> {}", old.type_raw, new.type_raw));
+            }
+            if old_table.is_nullable(&old.name) != new_table.is_nullable(&new.name) {
+                parts.push(format!(
+                    "{} -> {}",
+                    null(old_table, old),
+                    null(new_table, new)
+                ));
+            }
+            let flag = |set: bool, name: &str| {
+                if set {
+                    name.to_string()
+                } else {
+                    format!("not {}", name)
+                }
+            };
+            let (old_key, new_key) = (is_key(old_table, old), is_key(new_table, new));
+            if old_key != new_key {
+                parts.push(format!(
+                    "{} -> {}",
+                    flag(old_key, "pk"),
+                    flag(new_key, "pk")
+                ));
+            }
+            if old.is_unique != new.is_unique {
+                parts.push(format!(
+                    "{} -> {}",
+                    flag(old.is_unique, "unique"),
+                    flag(new.is_unique, "unique")
+                ));
+            }
+            if old.default != new.default {
+                let default = |col: &Column| {
+                    col.default
+                        .as_ref()
+                        .map_or("none".to_string(), write_default)
+                };
+                parts.push(format!("default {} -> {}", default(old), default(new)));
+            }
+            format!("~ column {}: {}", old.name, parts.join(", "))
+        }
+    }
+}
+
+fn describe_index(change: &IndexChange) -> String {
+    let (sign, index) = match change {
+        IndexChange::Added(index) => ('+', index),
+        IndexChange::Removed(index) => ('-', index),
+    };
+    format!("{} index {}", sign, index_summary(index))
+}
+
+/// An index as `name (a, b) unique`.
+pub(crate) fn index_summary(index: &Index) -> String {
+    let columns: Vec<String> = index
+        .columns
+        .iter()
+        .map(|c| match c {
+            IndexColumn::Column(name) => name.clone(),
+            IndexColumn::Expression(e) => format!("`{}`", e),
+        })
+        .collect();
+    let mut out = String::new();
+    if let Some(name) = &index.name {
+        out.push_str(name);
+        out.push(' ');
+    }
+    out.push_str(&format!("({})", columns.join(", ")));
+    if index.is_pk {
+        out.push_str(" pk");
+    } else if index.is_unique {
+        out.push_str(" unique");
+    }
+    out
+}
+
+/// A relationship in DBML `Ref` notation, e.g. `public.posts.user_id > public.users.id`.
+pub(crate) fn describe_ref(rel: &Relationship) -> String {
+    let endpoint = |ep: &EndPoint| match ep.column_names.as_slice() {
+        [col] => format!("{}.{}", ep.table_id, col),
+        cols => format!("{}.({})", ep.table_id, cols.join(", ")),
+    };
+    let mut out = format!(
+        "{} {} {}",
+        endpoint(&rel.from),
+        relation_symbol(rel.relation_type),
+        endpoint(&rel.to)
+    );
+    let mut actions = Vec::new();
+    if let Some(action) = rel.on_delete {
+        actions.push(format!("delete: {}", action.keyword()));
+    }
+    if let Some(action) = rel.on_update {
+        actions.push(format!("update: {}", action.keyword()));
+    }
+    if !actions.is_empty() {
+        out.push_str(&format!(" [{}]", actions.join(", ")));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout;
+    use crate::parser::parse_dbml;
+
+    const OLD: &str = r#"Project p {
+  database_type: 'PostgreSQL'
+}
+
+Table users {
+  id int [pk]
+  email varchar
+  name varchar
+}
+
+Table posts {
+  id int [pk]
+  user_id int
+  title varchar
+}
+
+Table legacy {
+  id int [pk]
+}
+
+Table tag {
+  id int [pk]
+  label varchar
+  color varchar
+}
+
+Ref: posts.user_id > users.id
+"#;
+
+    const NEW: &str = r#"Project p {
+  database_type: 'PostgreSQL'
+}
+
+Table users {
+  id int [pk]
+  email varchar [not null]
+  name text
+  created_at timestamp
+}
+
+Table posts {
+  id int [pk]
+  author_id int
+}
+
+Table tags {
+  id int [pk]
+  label varchar
+  color varchar
+}
+
+Table comments {
+  id int [pk]
+  post_id int
+}
+
+Ref: posts.author_id > users.id
+Ref: comments.post_id > posts.id
+"#;
+
+    #[test]
+    fn test_report() {
+        let old = parse_dbml(OLD).unwrap();
+        let new = parse_dbml(NEW).unwrap();
+        let report = diff(&old, &new).report();
+        assert_eq!(
+            report,
+            "+ table public.comments\n\
+             - table public.legacy\n\
+             ~ table public.tag renamed to public.tags\n\
+             ~ table public.users\n    \
+             ~ column email: null -> not null\n    \
+             ~ column name: type varchar -> text\n    \
+             + column created_at timestamp\n\
+             ~ table public.posts\n    \
+             - column user_id int\n    \
+             - column title varchar\n    \
+             + column author_id int\n\
+             + ref public.posts.author_id > public.users.id\n\
+             + ref public.comments.post_id > public.posts.id\n\
+             - ref public.posts.user_id > public.users.id\n"
+        );
+        assert_eq!(diff(&new, &new).report(), "No structural changes\n");
+    }
+
+    #[test]
+    fn test_constraint_changes() {
+        let old = parse_dbml(
+            "Project p {\n  database_type: 'PostgreSQL'\n}\n\
+             Table users {\n  id int [pk]\n  email varchar\n  score int [default: 0]\n}\n\
+             Table posts {\n  id int [pk]\n  user_id int\n}\n\
+             Ref: posts.user_id > users.id\n",
+        )
+        .unwrap();
+        let new = parse_dbml(
+            "Project p {\n  database_type: 'PostgreSQL'\n}\n\
+             Table users {\n  id int [pk]\n  email varchar [unique]\n  score int\n}\n\
+             Table posts {\n  id int [pk]\n  user_id int\n}\n\
+             Ref: posts.user_id > users.id [delete: cascade]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            diff(&old, &new).report(),
+            "~ table public.users\n    \
+             ~ column email: not unique -> unique\n    \
+             ~ column score: default 0 -> none\n\
+             + ref public.posts.user_id > public.users.id [delete: cascade]\n\
+             - ref public.posts.user_id > public.users.id\n"
+        );
+    }
+
+    #[test]
+    fn test_composite_key_columns_are_not_null() {
+        let table = |post_id: &str| {
+            format!(
+                "Project p {{\n  database_type: 'PostgreSQL'\n}}\n\
+                 Table post_tags {{\n  post_id int{}\n  tag_id int\n\n  \
+                 indexes {{\n    (post_id, tag_id) [pk]\n  }}\n}}\n",
+                post_id
+            )
+        };
+        let old = parse_dbml(&table("")).unwrap();
+        let new = parse_dbml(&table(" [not null]")).unwrap();
+        assert!(diff(&old, &new).is_empty());
+    }
+
+    #[test]
+    fn test_highlighted_diagram_keeps_removed_elements() {
+        let old = parse_dbml(OLD).unwrap();
+        let mut new = parse_dbml(NEW).unwrap();
+        layout::ensure_positions(&mut new);
+        let changes = diff(&old, &new);
+        let (diagram, highlights) = changes.highlighted_diagram();
+
+        let legacy = TableId::new("public", "legacy");
+        assert_eq!(highlights.tables[&legacy], REMOVED_COLOR);
+        let (_, _, _, max_y) = diagram_bounds(&new).unwrap();
+        let placed = diagram.tables.iter().find(|t| t.id == legacy).unwrap();
+        assert!(placed.position.unwrap().y > max_y);
+
+        // A free spot from the old layout is kept
+        let mut old = old.clone();
+        let far = Position {
+            x: 5000.0,
+            y: 5000.0,
+        };
+        old.tables
+            .iter_mut()
+            .find(|t| t.id == legacy)
+            .unwrap()
+            .position = Some(far);
+        let (diagram, _) = diff(&old, &new).highlighted_diagram();
+        let placed = diagram.tables.iter().find(|t| t.id == legacy).unwrap();
+        assert_eq!(placed.position.map(|p| (p.x, p.y)), Some((far.x, far.y)));
+
+        let posts = diagram
+            .tables
+            .iter()
+            .find(|t| t.id.name == "posts")
+            .unwrap();
+        let names: Vec<&str> = posts.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["id", "user_id", "title", "author_id"]);
+        assert_eq!(
+            highlights.columns[&(posts.id.clone(), "title".to_string())],
+            REMOVED_COLOR
+        );
+
+        assert_eq!(diagram.relationships.len(), 3);
+        assert_eq!(highlights.relationships[&2], REMOVED_COLOR);
+        assert_eq!(highlights.relationships[&0], ADDED_COLOR);
+    }
+}
@@ -0,0 +1,513 @@
+//! Data dictionary generator for `dbml-draw docs`.
+//!
+//! Writes one page per table plus an index page that embeds the rendered
+//! diagram. Relationships link to the pages of the tables on the other end.
+
+use std::path::Path;
+
+use crate::error::AppError;
+use crate::export::options::ExportOptions;
+use crate::export::style::escape_xml;
+use crate::export::svg::write_svg;
+use crate::ir::{DefaultValue, Diagram, EndPoint, IndexColumn, RelationType, Table, TableId};
+use crate::layout;
+
+/// Page format of the generated docs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum DocsFormat {
+    Markdown,
+    Html,
+}
+
+impl DocsFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            DocsFormat::Markdown => "md",
+            DocsFormat::Html => "html",
+        }
+    }
+}
+
+/// File name of the overview image written next to the pages.
+const DIAGRAM_FILE: &str = "diagram.svg";
+
+/// Write the docs for `diagram` into `out_dir`, creating it if needed.
+pub fn write_docs(
+    mut diagram: Diagram,
+    format: DocsFormat,
+    out_dir: &Path,
+) -> Result<(), AppError> {
+    layout::ensure_positions(&mut diagram);
+    std::fs::create_dir_all(out_dir)?;
+    std::fs::write(
+        out_dir.join(DIAGRAM_FILE),
+        write_svg(&diagram, "", &ExportOptions::default(), None),
+    )?;
+
+    let index = format!("index.{}", format.extension());
+    std::fs::write(out_dir.join(index), render_index(&diagram, format))?;
+    for table in &diagram.tables {
+        let page = TablePage::new(&diagram, table);
+        std::fs::write(
+            out_dir.join(page_file(&table.id, format)),
+            page.render(format),
+        )?;
+    }
+    Ok(())
+}
+
+/// File name of the page of a table. Quoted names can contain anything,
+/// including `/` and `..`, so only letters, digits, `.`, `_` and `-` are
+/// kept; a name that had other characters gets a hash of it appended so
+/// pages stay distinct.
+fn page_file(id: &TableId, format: DocsFormat) -> String {
+    let name = id.full_name();
+    let stem: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let stem = if stem == name && !stem.chars().all(|c| c == '.') {
+        stem
+    } else {
+        format!("{}-{:08x}", stem, crc32fast::hash(name.as_bytes()))
+    };
+    format!("{}.{}", stem, format.extension())
+}
+
+/// Relative link to the page of a table, percent-encoding what is not
+/// ASCII in its file name.
+fn page_link(id: &TableId, format: DocsFormat) -> String {
+    let mut out = String::new();
+    for b in page_file(id, format).bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+fn title(diagram: &Diagram) -> String {
+    diagram
+        .project
+        .as_ref()
+        .map(|p| p.name.clone())
+        .unwrap_or_else(|| "Schema".to_string())
+}
+
+fn render_index(diagram: &Diagram, format: DocsFormat) -> String {
+    let title = title(diagram);
+    let note = diagram.project.as_ref().and_then(|p| p.note.as_deref());
+    match format {
+        DocsFormat::Markdown => {
+            let mut out = format!("# {}\n\n", title);
+            if let Some(note) = note {
+                out.push_str(&format!("{}\n\n", note.trim()));
+            }
+            out.push_str(&format!("![{}]({})\n\n## Tables\n\n", title, DIAGRAM_FILE));
+            out.push_str("| Table | Columns | Note |\n| --- | --- | --- |\n");
+            for t in &diagram.tables {
+                out.push_str(&format!(
+                    "| [{}]({}) | {} | {} |\n",
+                    md_link_text(&t.id.full_name()),
+                    page_link(&t.id, format),
+                    t.columns.len(),
+                    md_cell(t.note.as_deref().unwrap_or("")),
+                ));
+            }
+            out
+        }
+        DocsFormat::Html => {
+            let mut body = String::new();
+            if let Some(note) = note {
+                body.push_str(&format!("<p>{}</p>\n", escape_xml(note.trim())));
+            }
+            body.push_str(&format!(
+                "<img src=\"{}\" alt=\"{}\">\n<h2>Tables</h2>\n<table>\n\
+                 <tr><th>Table</th><th>Columns</th><th>Note</th></tr>\n",
+                DIAGRAM_FILE,
+                escape_xml(&title)
+            ));
+            for t in &diagram.tables {
+                body.push_str(&format!(
+                    "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td></tr>\n",
+                    page_link(&t.id, format),
+                    escape_xml(&t.id.full_name()),
+                    t.columns.len(),
+                    escape_xml(t.note.as_deref().unwrap_or("")),
+                ));
+            }
+            body.push_str("</table>\n");
+            html_page(&title, &body)
+        }
+    }
+}
+
+/// A relationship seen from one table, with the table on the other end.
+struct Link {
+    columns: Vec<String>,
+    other: TableId,
+    other_columns: Vec<String>,
+}
+
+impl Link {
+    fn new(this: &EndPoint, other: &EndPoint) -> Self {
+        Self {
+            columns: this.column_names.clone(),
+            other: other.table_id.clone(),
+            other_columns: other.column_names.clone(),
+        }
+    }
+}
+
+struct TablePage<'a> {
+    table: &'a Table,
+    /// Foreign keys on this table.
+    references: Vec<Link>,
+    /// Foreign keys on other tables pointing here.
+    referenced_by: Vec<Link>,
+    many_to_many: Vec<Link>,
+}
+
+impl<'a> TablePage<'a> {
+    fn new(diagram: &Diagram, table: &'a Table) -> Self {
+        let mut page = Self {
+            table,
+            references: Vec::new(),
+            referenced_by: Vec::new(),
+            many_to_many: Vec::new(),
+        };
+        for rel in &diagram.relationships {
+            if rel.relation_type == RelationType::ManyToMany {
+                if rel.from.table_id == table.id {
+                    page.many_to_many.push(Link::new(&rel.from, &rel.to));
+                } else if rel.to.table_id == table.id {
+                    page.many_to_many.push(Link::new(&rel.to, &rel.from));
+                }
+                continue;
+            }
+            let Some((child, parent)) = rel.foreign_key() else {
+                continue;
+            };
+            if child.table_id == table.id {
+                page.references.push(Link::new(child, parent));
+            }
+            if parent.table_id == table.id {
+                page.referenced_by.push(Link::new(parent, child));
+            }
+        }
+        page
+    }
+
+    fn column_rows(&self) -> Vec<[String; 7]> {
+        let flag = |b: bool| if b { "yes" } else { "" }.to_string();
+        let primary_key = self.table.primary_key();
+        self.table
+            .columns
+            .iter()
+            .map(|c| {
+                [
+                    c.name.clone(),
+                    c.type_raw.clone(),
+                    flag(primary_key.contains(&c.name.as_str())),
+                    flag(self.table.is_nullable(&c.name)),
+                    flag(c.is_unique),
+                    c.default.as_ref().map(default_text).unwrap_or_default(),
+                    c.note.clone().unwrap_or_default(),
+                ]
+            })
+            .collect()
+    }
+
+    fn index_rows(&self) -> Vec<[String; 3]> {
+        self.table
+            .indexes
+            .iter()
+            .map(|idx| {
+                let columns = idx
+                    .columns
+                    .iter()
+                    .map(|c| match c {
+                        IndexColumn::Column(name) => name.clone(),
+                        IndexColumn::Expression(e) => e.clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let kind = if idx.is_pk {
+                    "primary key"
+                } else if idx.is_unique {
+                    "unique"
+                } else {
+                    ""
+                };
+                [
+                    idx.name.clone().unwrap_or_default(),
+                    columns,
+                    kind.to_string(),
+                ]
+            })
+            .collect()
+    }
+
+    fn sections(&self) -> [(&'static str, &[Link]); 3] {
+        [
+            ("References", &self.references),
+            ("Referenced by", &self.referenced_by),
+            ("Many-to-many", &self.many_to_many),
+        ]
+    }
+
+    fn render(&self, format: DocsFormat) -> String {
+        match format {
+            DocsFormat::Markdown => self.render_markdown(),
+            DocsFormat::Html => self.render_html(),
+        }
+    }
+
+    fn render_markdown(&self) -> String {
+        let format = DocsFormat::Markdown;
+        let mut out = format!("# {}\n\n", self.table.id.full_name());
+        if let Some(note) = &self.table.note {
+            out.push_str(&format!("{}\n\n", note.trim()));
+        }
+
+        out.push_str("## Columns\n\n");
+        out.push_str("| Column | Type | PK | Nullable | Unique | Default | Note |\n");
+        out.push_str("| --- | --- | --- | --- | --- | --- | --- |\n");
+        for row in self.column_rows() {
+            let cells: Vec<String> = row.iter().map(|c| md_cell(c)).collect();
+            out.push_str(&format!("| {} |\n", cells.join(" | ")));
+        }
+
+        let indexes = self.index_rows();
+        if !indexes.is_empty() {
+            out.push_str("\n## Indexes\n\n| Name | Columns | Kind |\n| --- | --- | --- |\n");
+            for row in indexes {
+                let cells: Vec<String> = row.iter().map(|c| md_cell(c)).collect();
+                out.push_str(&format!("| {} |\n", cells.join(" | ")));
+            }
+        }
+
+        for (heading, links) in self.sections() {
+            if links.is_empty() {
+                continue;
+            }
+            out.push_str(&format!("\n## {}\n\n", heading));
+            for link in links {
+                out.push_str(&format!(
+                    "- {} → [{}]({}) ({})\n",
+                    md_cell(&link.columns.join(", ")),
+                    md_link_text(&link.other.full_name()),
+                    page_link(&link.other, format),
+                    md_cell(&link.other_columns.join(", ")),
+                ));
+            }
+        }
+
+        out.push_str("\n[Back to index](index.md)\n");
+        out
+    }
+
+    fn render_html(&self) -> String {
+        let format = DocsFormat::Html;
+        let mut body = String::new();
+        if let Some(note) = &self.table.note {
+            body.push_str(&format!("<p>{}</p>\n", escape_xml(note.trim())));
+        }
+
+        body.push_str("<h2>Columns</h2>\n");
+        body.push_str(&html_table(
+            &[
+                "Column", "Type", "PK", "Nullable", "Unique", "Default", "Note",
+            ],
+            self.column_rows().iter().map(|r| r.as_slice()),
+        ));
+
+        let indexes = self.index_rows();
+        if !indexes.is_empty() {
+            body.push_str("<h2>Indexes</h2>\n");
+            body.push_str(&html_table(
+                &["Name", "Columns", "Kind"],
+                indexes.iter().map(|r| r.as_slice()),
+            ));
+        }
+
+        for (heading, links) in self.sections() {
+            if links.is_empty() {
+                continue;
+            }
+            body.push_str(&format!("<h2>{}</h2>\n<ul>\n", heading));
+            for link in links {
+                body.push_str(&format!(
+                    "<li>{} → <a href=\"{}\">{}</a> ({})</li>\n",
+                    escape_xml(&link.columns.join(", ")),
+                    page_link(&link.other, format),
+                    escape_xml(&link.other.full_name()),
+                    escape_xml(&link.other_columns.join(", ")),
+                ));
+            }
+            body.push_str("</ul>\n");
+        }
+
+        body.push_str("<p><a href=\"index.html\">Back to index</a></p>\n");
+        html_page(&self.table.id.full_name(), &body)
+    }
+}
+
+fn default_text(value: &DefaultValue) -> String {
+    match value {
+        DefaultValue::String(s) => format!("'{}'", s),
+        DefaultValue::Number(n) => n.clone(),
+        DefaultValue::Bool(b) => b.to_string(),
+        DefaultValue::Null => "null".to_string(),
+        DefaultValue::Expression(e) | DefaultValue::Identifier(e) => e.clone(),
+    }
+}
+
+/// Escape pipes and line breaks so text stays inside one table cell.
+fn md_cell(text: &str) -> String {
+    text.trim().replace('|', "\\|").replace('\n', "<br>")
+}
+
+/// Escape brackets as well, so a table name cannot end the link text.
+fn md_link_text(text: &str) -> String {
+    md_cell(text).replace('[', "\\[").replace(']', "\\]")
+}
+
+fn html_table<'r>(headers: &[&str], rows: impl Iterator<Item = &'r [String]>) -> String {
+    let mut out = String::from("<table>\n<tr>");
+    for h in headers {
+        out.push_str(&format!("<th>{}</th>", h));
+    }
+    out.push_str("</tr>\n");
+    for row in rows {
+        out.push_str("<tr>");
+        for cell in row {
+            out.push_str(&format!("<td>{}</td>", escape_xml(cell)));
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n");
+    out
+}
+
+fn html_page(title: &str, body: &str) -> String {
+    format!(
+        concat!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n",
+            "<style>\n",
+            "body {{ font-family: -apple-system, BlinkMacSystemFont, \"Segoe UI\", system-ui, sans-serif; ",
+            "color: #334155; margin: 2rem auto; max-width: 960px; padding: 0 1rem; }}\n",
+            "table {{ border-collapse: collapse; margin-bottom: 1rem; }}\n",
+            "th, td {{ border: 1px solid #e2e8f0; padding: 4px 10px; text-align: left; }}\n",
+            "th {{ background: #f1f5f9; }}\n",
+            "img {{ max-width: 100%; }}\n",
+            "a {{ color: #6366f1; }}\n",
+            "</style>\n</head>\n<body>\n<h1>{title}</h1>\n{body}</body>\n</html>\n"
+        ),
+        title = escape_xml(title),
+        body = body,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_dbml;
+
+    fn basic() -> Diagram {
+        parse_dbml(include_str!("../tests/fixtures/basic.dbml")).unwrap()
+    }
+
+    #[test]
+    fn test_markdown_pages_link_both_directions() {
+        let dir = tempfile::tempdir().unwrap();
+        write_docs(basic(), DocsFormat::Markdown, dir.path()).unwrap();
+
+        let index = std::fs::read_to_string(dir.path().join("index.md")).unwrap();
+        assert!(index.contains("![test](diagram.svg)"));
+        assert!(index.contains("[public.users](public.users.md)"));
+        assert!(dir.path().join("diagram.svg").exists());
+
+        let posts = std::fs::read_to_string(dir.path().join("public.posts.md")).unwrap();
+        assert!(posts.contains("| published | bool |  | yes |  | false |  |"));
+        assert!(posts.contains("## References\n\n- user_id → [public.users](public.users.md) (id)"));
+        assert!(posts.contains(
+            "## Referenced by\n\n- id → [public.comments](public.comments.md) (post_id)"
+        ));
+    }
+
+    #[test]
+    fn test_html_pages() {
+        let dir = tempfile::tempdir().unwrap();
+        write_docs(basic(), DocsFormat::Html, dir.path()).unwrap();
+
+        let index = std::fs::read_to_string(dir.path().join("index.html")).unwrap();
+        assert!(index.contains("<img src=\"diagram.svg\""));
+        let users = std::fs::read_to_string(dir.path().join("public.users.html")).unwrap();
+        assert!(users.contains("<a href=\"public.profiles.html\">public.profiles</a>"));
+    }
+
+    #[test]
+    fn test_hostile_table_names_stay_inside_out_dir() {
+        let input = "Project p {\n  database_type: 'PostgreSQL'\n}\n\
+                     Table \"a/../../x\" {\n  id int [pk]\n}\n\
+                     Table \"my table#1\" {\n  id int [pk]\n  x_id int [ref: > \"a/../../x\".id]\n}\n\
+                     Table \"café\" {\n  id int [pk]\n}\n";
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("a").join("b");
+        write_docs(parse_dbml(input).unwrap(), DocsFormat::Markdown, &dir).unwrap();
+
+        let mut files: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        let x = format!(
+            "public.a_.._.._x-{:08x}.md",
+            crc32fast::hash(b"public.a/../../x")
+        );
+        let mine = format!(
+            "public.my_table_1-{:08x}.md",
+            crc32fast::hash(b"public.my table#1")
+        );
+        assert_eq!(
+            files,
+            [
+                "diagram.svg",
+                "index.md",
+                x.as_str(),
+                "public.café.md",
+                mine.as_str(),
+            ]
+        );
+        assert!(!root.path().join("x.md").exists());
+
+        let index = std::fs::read_to_string(dir.join("index.md")).unwrap();
+        assert!(index.contains(&format!("[public.my table#1]({})", mine)));
+        assert!(index.contains("[public.café](public.caf%C3%A9.md)"));
+        let page = std::fs::read_to_string(dir.join(&mine)).unwrap();
+        assert!(page.contains(&format!("[public.a/../../x]({})", x)));
+    }
+
+    #[test]
+    fn test_composite_key_columns_are_pk_and_not_null() {
+        let input = "Project p {\n  database_type: 'PostgreSQL'\n}\n\
+                     Table post_tags {\n  post_id int\n  tag_id int\n  note text\n\n  \
+                     indexes {\n    (post_id, tag_id) [pk]\n  }\n}\n";
+        let dir = tempfile::tempdir().unwrap();
+        write_docs(parse_dbml(input).unwrap(), DocsFormat::Markdown, dir.path()).unwrap();
+
+        let page = std::fs::read_to_string(dir.path().join("public.post_tags.md")).unwrap();
+        assert!(page.contains("| post_id | int | yes |  |  |  |  |"));
+        assert!(page.contains("| tag_id | int | yes |  |  |  |  |"));
+        assert!(page.contains("| note | text |  | yes |  |  |  |"));
+    }
+}
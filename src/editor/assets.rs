@@ -1,3 +1,12 @@
 pub const EDITOR_HTML: &str = include_str!("assets/editor.html");
 pub const EDITOR_JS: &str = include_str!("assets/editor.js");
 pub const EDITOR_CSS: &str = include_str!("assets/editor.css");
+
+/// The editor page with CSS and JS inlined. `init_script` runs before the
+/// editor script.
+pub fn editor_page(init_script: &str) -> String {
+    EDITOR_HTML
+        .replace("/* __EDITOR_CSS__ */", EDITOR_CSS)
+        .replace("/* __INIT_SCRIPT__ */", init_script)
+        .replace("/* __EDITOR_JS__ */", EDITOR_JS)
+}
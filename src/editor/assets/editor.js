@@ -16,6 +16,12 @@ const FONT_SANS = '-apple-system, BlinkMacSystemFont, "Segoe UI", system-ui, san
 // Color palette — Modern slate with indigo accent
 const HEADER_BG = "#1e293b";
 const HEADER_TEXT = "#f1f5f9";
+const STUB_HEADER_BG = "#cbd5e1";
+const STUB_HEADER_TEXT = "#334155";
+const STUB_DASH = [6, 4];
+const PATH_COLOR = "#2563eb";
+const JUNCTION_BADGE = "M:N";
+const JUNCTION_BADGE_WIDTH = 34;
 const TABLE_BG = "#ffffff";
 const TABLE_BORDER = "#e2e8f0";
 const COLUMN_TEXT = "#334155";
@@ -25,6 +31,17 @@ const ROW_SEPARATOR = "#f1f5f9";
 const RELATION_STROKE = "#94a3b8";
 const RELATION_STROKE_WIDTH = 1.5;
 const MARKER_LENGTH = 24;
+const LOOP_SIZE = 40;
+const PARALLEL_GAP = 24;
+const TITLE_HEIGHT = 56;
+// Legend layout, the same as `export/notation.rs`
+const LEGEND_GAP = 24;
+const LEGEND_HEADER = 36;
+const LEGEND_ROW = 30;
+const LEGEND_LINE = 56;
+const LEGEND_PADDING = 16;
+// Largest canvas side rendered at once when exporting (see export/tiles.rs)
+const TILE_SIZE = 4096;
 const CANVAS_BG = "#f8fafc";
 
 // Auto-layout constants
@@ -50,6 +67,42 @@ let panStart = { x: 0, y: 0 };
 // Drag state
 let dragState = null; // { tableIdx, offsetX, offsetY }
 
+// Focus view: `diagram` holds a subset of `fullDiagram`'s tables
+let fullDiagram = null;
+let focus = null; // { table, depth, direction }
+
+// Join path finder: tables clicked so far
+let pathPick = null; // [full names] while picking
+
+// Highlighted join path or column dependents
+let highlight = null; // { tables, columns: Sets of full names; rels: Set of relationships }
+
+// Press position, to tell a click from a drag
+let pressAt = null;
+
+// Junction tables, from `junction::find_junctions` in Rust:
+// [{ table, rels: [relationship, relationship], edge }], `edge` being the
+// many-to-many relationship drawn while the junction is collapsed
+let junctions = [];
+// Labels on collapsed junction edges, clicked to expand: [{ junction, x, y, w, h }]
+let junctionLabels = [];
+
+// Standalone HTML export: no dragging, no layout changes, no IPC
+const READ_ONLY = window.__READ_ONLY === true;
+
+// Relationship notation (see `export/notation.rs`) and whether the legend
+// is shown on screen
+let notation = "ie";
+let showLegend = false;
+
+// Orthogonal paths around the tables from `layout::router`, one waypoint list
+// (or null) per route, and the last request sent for them. The HTML export
+// inlines them, since the viewer has no Rust side to ask.
+let routePaths = window.__ROUTES || [];
+let routeSeq = 0;
+let routeApplied = 0;
+let routeKey = null;
+
 // =========================================================================
 // Helpers
 // =========================================================================
@@ -66,6 +119,12 @@ function computeTableWidths() {
   const PK_BADGE_EXTRA = 36; // badge width + gap
 
   for (const table of diagram.tables) {
+    // The inlined routes go around tables of the widths Rust measured
+    const inlined = window.__TABLE_WIDTHS && window.__TABLE_WIDTHS[tableFullName(table)];
+    if (inlined) {
+      table.width = inlined;
+      continue;
+    }
     let maxRowWidth = 0;
 
     ctx.font = FONT_SIZE + "px " + FONT_MONO;
@@ -77,7 +136,8 @@ function computeTableWidths() {
     }
 
     ctx.font = "600 " + HEADER_FONT_SIZE + "px " + FONT_SANS;
-    const headerWidth = ctx.measureText(table.id.name).width;
+    const headerWidth = ctx.measureText(table.id.name).width +
+      (junctionOf(table) ? JUNCTION_BADGE_WIDTH + 8 : 0);
 
     const contentWidth = Math.max(maxRowWidth, headerWidth);
     table.width = Math.max(MIN_TABLE_WIDTH, contentWidth + 2 * PADDING_X);
@@ -113,6 +173,396 @@ function hitTest(worldX, worldY) {
   return -1;
 }
 
+// =========================================================================
+// Focus view
+// =========================================================================
+
+// Tables at most `depth` of `relationships` away from the table named
+// `start`. Mirrors `graph::neighborhood` in Rust.
+function neighborhood(relationships, start, depth, direction) {
+  const hops = new Map(); // full name -> names one hop away
+  const addHop = (from, to) => {
+    if (!hops.has(from)) hops.set(from, []);
+    hops.get(from).push(to);
+  };
+  for (const rel of relationships) {
+    const from = rel.from.table_id.schema + "." + rel.from.table_id.name;
+    const to = rel.to.table_id.schema + "." + rel.to.table_id.name;
+    if (rel.relation_type === "ManyToMany") {
+      addHop(from, to);
+      addHop(to, from);
+      continue;
+    }
+    const [child, parent] = rel.relation_type === "OneToMany" ? [to, from] : [from, to];
+    if (direction !== "incoming") addHop(child, parent);
+    if (direction !== "outgoing") addHop(parent, child);
+  }
+
+  const distance = new Map([[start, 0]]);
+  const queue = [start];
+  while (queue.length > 0) {
+    const name = queue.shift();
+    const d = distance.get(name);
+    if (d === depth) continue;
+    for (const next of hops.get(name) || []) {
+      if (!distance.has(next)) {
+        distance.set(next, d + 1);
+        queue.push(next);
+      }
+    }
+  }
+  return distance;
+}
+
+function focusOn(table, depth, direction) {
+  focus = { table: table, depth: depth, direction: direction };
+  rebuildDiagram();
+  updateFocusBar();
+  fitToView();
+}
+
+function exitFocus() {
+  if (!focus) return;
+  focus = null;
+  rebuildDiagram();
+  updateFocusBar();
+  fitToView();
+}
+
+// The drawn diagram: the full one with collapsed junctions replaced by
+// their edges, narrowed to the focus if there is one. Tables are shared
+// with the full diagram, so positions carry over.
+function rebuildDiagram() {
+  const name = (id) => id.schema + "." + id.name;
+  const collapsed = junctions.filter((j) => j.table.collapsed);
+  const hidden = new Set(collapsed.map((j) => j.table));
+  const replaced = new Set(collapsed.flatMap((j) => j.rels));
+  let tables = fullDiagram.tables.filter((t) => !hidden.has(t));
+  let relationships = fullDiagram.relationships
+    .filter((r) => !replaced.has(r))
+    .concat(collapsed.map((j) => j.edge));
+
+  if (focus) {
+    const keep = neighborhood(relationships, focus.table, focus.depth, focus.direction);
+    tables = tables.filter((t) => keep.has(tableFullName(t)));
+    relationships = relationships.filter(
+      (r) => keep.has(name(r.from.table_id)) && keep.has(name(r.to.table_id))
+    );
+  }
+  diagram = Object.assign({}, fullDiagram, { tables: tables, relationships: relationships });
+}
+
+function updateFocusBar() {
+  const bar = document.getElementById("focus-bar");
+  bar.classList.toggle("show", focus !== null);
+  if (focus) {
+    document.getElementById("focus-table").textContent = focus.table;
+    document.getElementById("focus-depth").value = focus.depth;
+  }
+}
+
+function onFocusDepthChange(e) {
+  const depth = parseInt(e.target.value, 10);
+  if (focus && depth >= 0) {
+    focusOn(focus.table, depth, focus.direction);
+  }
+}
+
+function onDoubleClick(e) {
+  if (!diagram) return;
+  const world = screenToWorld(e.clientX, e.clientY);
+  const idx = hitTest(world.x, world.y);
+  if (idx < 0) return;
+  const table = tableFullName(diagram.tables[idx]);
+  focusOn(table, focus ? focus.depth : 1, focus ? focus.direction : "both");
+}
+
+function onKeyDown(e) {
+  if (e.key === "Escape" && !document.getElementById("export-dialog").open) {
+    if (!clearHighlight()) exitFocus();
+  }
+}
+
+// =========================================================================
+// Junction tables
+// =========================================================================
+
+// `found` is `window.__JUNCTIONS`, relationships given by index
+function resolveJunctions(found) {
+  const name = (id) => id.schema + "." + id.name;
+  const parent = (rel) => (rel.relation_type === "OneToMany" ? rel.from : rel.to);
+  junctions = [];
+  for (const j of found || []) {
+    const table = fullDiagram.tables.find((t) => tableFullName(t) === name(j.table));
+    const rels = j.rels.map((i) => fullDiagram.relationships[i]);
+    if (!table || rels.some((r) => !r)) continue;
+    junctions.push({
+      table: table,
+      rels: rels,
+      edge: {
+        relation_type: "ManyToMany",
+        from: parent(rels[0]),
+        to: parent(rels[1]),
+        cardinality: ["many-optional", "many-optional"],
+        junction: table,
+      },
+    });
+  }
+  updateJunctionButton();
+}
+
+function junctionOf(table) {
+  return junctions.find((j) => j.table === table) || null;
+}
+
+// Collapse or expand `list` and tell Rust, which keeps it in the layout
+function setCollapsed(list, collapsed) {
+  for (const j of list) {
+    j.table.collapsed = collapsed;
+    if (!collapsed && !j.table.position) placeBetween(j);
+  }
+  rebuildDiagram();
+  updateJunctionButton();
+  render();
+  if (READ_ONLY || list.length === 0) return;
+  window.ipc.postMessage(
+    JSON.stringify({
+      type: "junctions_toggled",
+      tables: list.map((j) => tableFullName(j.table)),
+      collapsed: collapsed,
+    })
+  );
+}
+
+// A junction expanded for the first time goes between the tables it links
+function placeBetween(j) {
+  const name = (id) => id.schema + "." + id.name;
+  const ends = [j.edge.from, j.edge.to].map((ep) =>
+    fullDiagram.tables.find((t) => tableFullName(t) === name(ep.table_id))
+  );
+  const centers = ends
+    .filter((t) => t && t.position)
+    .map((t) => ({
+      x: t.position.x + (t.width || MIN_TABLE_WIDTH) / 2,
+      y: t.position.y + tableHeight(t) / 2,
+    }));
+  if (centers.length === 0) return;
+  const cx = centers.reduce((sum, c) => sum + c.x, 0) / centers.length;
+  const cy = centers.reduce((sum, c) => sum + c.y, 0) / centers.length;
+  j.table.position = {
+    x: cx - (j.table.width || MIN_TABLE_WIDTH) / 2,
+    y: cy - tableHeight(j.table) / 2,
+  };
+  if (READ_ONLY) return;
+  window.ipc.postMessage(
+    JSON.stringify({
+      type: "table_moved",
+      table_id: tableFullName(j.table),
+      x: j.table.position.x,
+      y: j.table.position.y,
+    })
+  );
+}
+
+function onToggleJunctions() {
+  const expanded = junctions.filter((j) => !j.table.collapsed);
+  if (expanded.length > 0) {
+    setCollapsed(expanded, true);
+  } else {
+    setCollapsed(junctions, false);
+  }
+}
+
+function updateJunctionButton() {
+  const button = document.getElementById("btn-junctions");
+  button.hidden = junctions.length === 0;
+  button.textContent = junctions.some((j) => !j.table.collapsed)
+    ? "Collapse Junctions"
+    : "Expand Junctions";
+}
+
+// The "M:N" badge in the header of an expanded junction table
+function junctionBadge(table) {
+  const pos = table.position || { x: 0, y: 0 };
+  const w = table.width || MIN_TABLE_WIDTH;
+  const h = 18;
+  return {
+    x: pos.x + w - PADDING_X - JUNCTION_BADGE_WIDTH,
+    y: pos.y + (HEADER_HEIGHT - h) / 2,
+    w: JUNCTION_BADGE_WIDTH,
+    h: h,
+  };
+}
+
+function inRect(rect, x, y) {
+  return x >= rect.x && x <= rect.x + rect.w && y >= rect.y && y <= rect.y + rect.h;
+}
+
+// =========================================================================
+// Join paths
+// =========================================================================
+
+// Tables and relationships on any shortest path between the tables named
+// `start` and `end`, following relationships either way. Mirrors
+// `path::shortest_paths` in Rust. Null if they are not connected.
+function findPath(start, end) {
+  const name = (id) => id.schema + "." + id.name;
+  const neighbors = new Map(); // full name -> [{ rel, to }]
+  for (const rel of diagram.relationships) {
+    const a = name(rel.from.table_id);
+    const b = name(rel.to.table_id);
+    if (a === b) continue;
+    if (!neighbors.has(a)) neighbors.set(a, []);
+    if (!neighbors.has(b)) neighbors.set(b, []);
+    neighbors.get(a).push({ rel: rel, to: b });
+    neighbors.get(b).push({ rel: rel, to: a });
+  }
+  const distances = (from) => {
+    const distance = new Map([[from, 0]]);
+    const queue = [from];
+    while (queue.length > 0) {
+      const t = queue.shift();
+      for (const n of neighbors.get(t) || []) {
+        if (!distance.has(n.to)) {
+          distance.set(n.to, distance.get(t) + 1);
+          queue.push(n.to);
+        }
+      }
+    }
+    return distance;
+  };
+
+  const fromStart = distances(start);
+  const fromEnd = distances(end);
+  if (!fromStart.has(end)) return null;
+  const length = fromStart.get(end);
+
+  // On a shortest path when the distances through it add up
+  const tables = new Set();
+  const rels = new Set();
+  for (const [t, d] of fromStart) {
+    if (fromEnd.has(t) && d + fromEnd.get(t) === length) tables.add(t);
+  }
+  for (const t of tables) {
+    for (const n of neighbors.get(t) || []) {
+      if (tables.has(n.to) && fromStart.get(t) + 1 + fromEnd.get(n.to) === length) {
+        rels.add(n.rel);
+      }
+    }
+  }
+  return { tables: tables, columns: new Set(), rels: rels, length: length };
+}
+
+function onFindPath() {
+  pathPick = [];
+  highlight = null;
+  showToast("Click the two tables to connect");
+  render();
+}
+
+function pickPathTable(name) {
+  pathPick.push(name);
+  if (pathPick.length < 2) {
+    highlight = { tables: new Set([name]), columns: new Set(), rels: new Set() };
+    render();
+    return;
+  }
+  const [start, end] = pathPick;
+  pathPick = null;
+  const found = findPath(start, end);
+  if (found) {
+    highlight = found;
+    const joins = found.length === 1 ? "1 join" : found.length + " joins";
+    showToast(start + " to " + end + ": " + joins);
+  } else {
+    highlight = null;
+    showToast("No path between " + start + " and " + end);
+  }
+  render();
+}
+
+// =========================================================================
+// Column impact
+// =========================================================================
+
+// The column `table`.`column` and the columns referencing it through
+// foreign keys, recursively, with the relationships using any of them.
+// Mirrors `impact::column_impact` in Rust.
+function columnImpact(table, column) {
+  const name = (id) => id.schema + "." + id.name;
+  const tables = new Set();
+  const columns = new Set([table + "." + column]);
+  const rels = new Set();
+  const queue = [[table, column]];
+  while (queue.length > 0) {
+    const [t, c] = queue.shift();
+    tables.add(t);
+    for (const rel of diagram.relationships) {
+      const uses = (ep) => name(ep.table_id) === t && ep.column_names.includes(c);
+      if (!uses(rel.from) && !uses(rel.to)) continue;
+      rels.add(rel);
+      if (rel.relation_type === "ManyToMany") continue;
+      const [child, parent] =
+        rel.relation_type === "OneToMany" ? [rel.to, rel.from] : [rel.from, rel.to];
+      const position = name(parent.table_id) === t ? parent.column_names.indexOf(c) : -1;
+      const childColumn = child.column_names[position];
+      if (position < 0 || childColumn === undefined) continue;
+      const key = name(child.table_id) + "." + childColumn;
+      if (!columns.has(key)) {
+        columns.add(key);
+        queue.push([name(child.table_id), childColumn]);
+      }
+    }
+  }
+  return { tables: tables, columns: columns, rels: rels };
+}
+
+function selectColumn(table, column) {
+  highlight = columnImpact(tableFullName(table), column.name);
+  const dependents = highlight.columns.size - 1;
+  showToast(
+    tableFullName(table) + "." + column.name + ": " +
+    dependents + (dependents === 1 ? " dependent column, " : " dependent columns, ") +
+    highlight.rels.size + (highlight.rels.size === 1 ? " relationship" : " relationships")
+  );
+  render();
+}
+
+// A click on a column row selects it; anywhere else clears the highlight
+function onCanvasClick(e) {
+  const world = screenToWorld(e.clientX, e.clientY);
+  // Labels of collapsed junctions expand them, badges of expanded ones
+  // collapse them
+  const label = junctionLabels.find((l) => inRect(l, world.x, world.y));
+  if (label) {
+    setCollapsed([label.junction], false);
+    return;
+  }
+  const idx = hitTest(world.x, world.y);
+  if (idx >= 0) {
+    const table = diagram.tables[idx];
+    const junction = junctionOf(table);
+    if (junction && inRect(junctionBadge(table), world.x, world.y)) {
+      setCollapsed([junction], true);
+      return;
+    }
+    const row = Math.floor((world.y - table.position.y - HEADER_HEIGHT) / ROW_HEIGHT);
+    if (row >= 0 && row < table.columns.length) {
+      selectColumn(table, table.columns[row]);
+      return;
+    }
+  }
+  clearHighlight();
+}
+
+function clearHighlight() {
+  if (!pathPick && !highlight) return false;
+  pathPick = null;
+  highlight = null;
+  render();
+  return true;
+}
+
 // =========================================================================
 // Auto-layout (BFS cross layout)
 // =========================================================================
@@ -257,46 +707,6 @@ function findNearestEmpty(cx, cy, occupied, key) {
   return null;
 }
 
-// =========================================================================
-// IE Marker determination
-// =========================================================================
-function findColumn(table, name) {
-  return table.columns.find((c) => c.name === name) || null;
-}
-
-function fkIsNullable(columnNames, table) {
-  const colName = columnNames[0] || "";
-  const col = findColumn(table, colName);
-  return col ? col.is_nullable : true;
-}
-
-function determineIeMarkers(rel, fromTable, toTable) {
-  const rt = rel.relation_type;
-  if (rt === "ManyToOne") {
-    const n = fkIsNullable(rel.from.column_names, fromTable);
-    return [
-      n ? "many-optional" : "many-mandatory",
-      n ? "one-optional" : "one-mandatory",
-    ];
-  }
-  if (rt === "OneToMany") {
-    const n = fkIsNullable(rel.to.column_names, toTable);
-    return [
-      n ? "one-optional" : "one-mandatory",
-      n ? "many-optional" : "many-mandatory",
-    ];
-  }
-  if (rt === "OneToOne") {
-    const n = fkIsNullable(rel.from.column_names, fromTable);
-    return [
-      n ? "one-optional" : "one-mandatory",
-      n ? "one-optional" : "one-mandatory",
-    ];
-  }
-  // ManyToMany
-  return ["many-optional", "many-optional"];
-}
-
 // =========================================================================
 // Relationship routing
 // =========================================================================
@@ -367,7 +777,11 @@ function computeRoutes() {
     const fw = ft.width || MIN_TABLE_WIDTH;
     const tw = tt.width || MIN_TABLE_WIDTH;
 
-    const [fromSide, toSide] = determineSides(fp, fw, tableHeight(ft), tp, tw, tableHeight(tt));
+    // A table referencing itself loops out of its right side, or its left
+    // one (see placeSelfLoops)
+    const [fromSide, toSide] = fi === ti
+      ? ["Right", "Right"]
+      : determineSides(fp, fw, tableHeight(ft), tp, tw, tableHeight(tt));
     const fromCol = rel.from.column_names[0] || "";
     const toCol = rel.to.column_names[0] || "";
     const from = connectionPoint(fp, ft, fromSide, fromCol);
@@ -378,11 +792,114 @@ function computeRoutes() {
       fromSide, toSide,
       fromX: from.x, fromY: from.y,
       toX: to.x, toY: to.y,
+      offset: 0, labelled: false,
     });
   }
+  placeSelfLoops(routes);
+  distributeConnectionPoints(routes);
+  separateParallel(routes);
   return routes;
 }
 
+// Routes with the waypoints from the router attached as `path`, where the
+// last answer still fits their ends
+function routedRoutes() {
+  const routes = computeRoutes();
+  const near = (p, x, y) => Math.abs(p.x - x) < 0.5 && Math.abs(p.y - y) < 0.5;
+  routes.forEach((info, i) => {
+    const path = routePaths[i];
+    if (!info || !path || path.length < 2) return;
+    const last = path[path.length - 1];
+    if (near(path[0], info.fromX, info.fromY) && near(last, info.toX, info.toY)) {
+      info.path = path;
+    }
+  });
+  return routes;
+}
+
+// Ask Rust to route around the tables (`layout::router`) when the tables or
+// route ends changed. While a table is dragged only the paths near it are
+// recomputed; the answer comes back through `__onRoutes()`.
+function requestRoutes(routes) {
+  if (READ_ONLY || !window.ipc) return;
+  const tables = diagram.tables.map((t) => {
+    const p = t.position || { x: 0, y: 0 };
+    return { x: p.x, y: p.y, w: t.width || MIN_TABLE_WIDTH, h: tableHeight(t) };
+  });
+  const ends = routes.map((r) => r && {
+    from_table: r.fromIdx, to_table: r.toIdx,
+    from_side: r.fromSide, to_side: r.toSide,
+    from: { x: r.fromX, y: r.fromY },
+    to: { x: r.toX, y: r.toY },
+    offset: r.offset, labelled: r.labelled,
+  });
+  const key = JSON.stringify([tables, ends]);
+  if (key === routeKey) return;
+  const moved = routeKey !== null && dragState ? [dragState.tableIdx] : null;
+  routeKey = key;
+  window.ipc.postMessage(
+    JSON.stringify({ type: "route", seq: ++routeSeq, tables, routes: ends, moved })
+  );
+}
+
+// Move self-loops to the left side of tables with more relationships on the
+// right, so they stay out of the way
+function placeSelfLoops(routes) {
+  const counts = {}; // "table:side" -> endpoints of other relationships
+  const add = (key) => (counts[key] = (counts[key] || 0) + 1);
+  for (const r of routes) {
+    if (!r || r.fromIdx === r.toIdx) continue;
+    add(r.fromIdx + ":" + r.fromSide);
+    add(r.toIdx + ":" + r.toSide);
+  }
+  routes.forEach((r, i) => {
+    if (!r || r.fromIdx !== r.toIdx) return;
+    if ((counts[r.fromIdx + ":Right"] || 0) <= (counts[r.fromIdx + ":Left"] || 0)) return;
+    const rel = diagram.relationships[i];
+    const table = diagram.tables[r.fromIdx];
+    const pos = table.position || { x: 0, y: 0 };
+    const from = connectionPoint(pos, table, "Left", rel.from.column_names[0] || "");
+    const to = connectionPoint(pos, table, "Left", rel.to.column_names[0] || "");
+    Object.assign(r, {
+      fromSide: "Left", toSide: "Left",
+      fromX: from.x, fromY: from.y,
+      toX: to.x, toY: to.y,
+    });
+  });
+}
+
+// Push apart the curves of relationships between the same two tables and
+// nest the self-loops of a table; these get their columns as a label
+function separateParallel(routes) {
+  const groups = {}; // "a:b" -> route indices
+  for (let i = 0; i < routes.length; i++) {
+    const r = routes[i];
+    if (!r) continue;
+    const key = Math.min(r.fromIdx, r.toIdx) + ":" + Math.max(r.fromIdx, r.toIdx);
+    (groups[key] = groups[key] || []).push(i);
+  }
+
+  for (const indices of Object.values(groups)) {
+    const count = indices.length;
+    indices.forEach((i, k) => {
+      const r = routes[i];
+      if (r.fromIdx === r.toIdx) {
+        r.offset = LOOP_SIZE + k * PARALLEL_GAP;
+        r.labelled = true;
+      } else if (count > 1) {
+        r.offset = (k - (count - 1) / 2) * PARALLEL_GAP;
+        r.labelled = true;
+      }
+    });
+  }
+}
+
+// Foreign key columns of a relationship, its label when it needs one
+function columnLabel(rel) {
+  const child = rel.relation_type === "OneToMany" ? rel.to : rel.from;
+  return child.column_names.join(", ");
+}
+
 function distributeConnectionPoints(routes) {
   // Group route indices by (tableIdx, side) for both from and to endpoints
   const groups = {}; // key -> [{ routeIdx, endpoint: "from"|"to" }]
@@ -442,35 +959,79 @@ function sideAngle(side) {
   }
 }
 
-function drawRelationshipPath(ctx, info) {
+// The bezier between a route's straight end segments, as four points, like
+// `Route::curve()` in `layout/routing.rs`
+function routeCurve(info) {
   const { fromX, fromY, toX, toY, fromSide, toSide } = info;
 
   // Offset points past the markers so bezier doesn't overlap with marker symbols
   const fa = sideAngle(fromSide);
   const ta = sideAngle(toSide);
-  const oFromX = fromX + Math.cos(fa) * MARKER_LENGTH;
-  const oFromY = fromY + Math.sin(fa) * MARKER_LENGTH;
-  const oToX = toX + Math.cos(ta) * MARKER_LENGTH;
-  const oToY = toY + Math.sin(ta) * MARKER_LENGTH;
+  const start = { x: fromX + Math.cos(fa) * MARKER_LENGTH, y: fromY + Math.sin(fa) * MARKER_LENGTH };
+  const end = { x: toX + Math.cos(ta) * MARKER_LENGTH, y: toY + Math.sin(ta) * MARKER_LENGTH };
+
+  if (info.fromIdx === info.toIdx) {
+    const x = info.fromSide === "Left"
+      ? Math.min(start.x, end.x) - info.offset
+      : Math.max(start.x, end.x) + info.offset;
+    return [start, { x: x, y: start.y }, { x: x, y: end.y }, end];
+  } else if (isHorizontal(info)) {
+    const midX = (start.x + end.x) / 2 + info.offset;
+    return [start, { x: midX, y: start.y }, { x: midX, y: end.y }, end];
+  } else if (isVertical(info)) {
+    const midY = (start.y + end.y) / 2 + info.offset;
+    return [start, { x: start.x, y: midY }, { x: end.x, y: midY }, end];
+  }
+  return [start, { x: end.x, y: start.y }, end, end];
+}
 
-  ctx.beginPath();
-  ctx.moveTo(fromX, fromY);
-  ctx.lineTo(oFromX, oFromY);
+// Halfway along a route's curve, where labels go
+function routeMiddle(info) {
+  if (info.path) return pathMiddle(info.path);
+  const [p0, p1, p2, p3] = routeCurve(info);
+  return {
+    x: (p0.x + 3 * p1.x + 3 * p2.x + p3.x) / 8,
+    y: (p0.y + 3 * p1.y + 3 * p2.y + p3.y) / 8,
+  };
+}
 
-  if (isHorizontal(info)) {
-    const midX = (oFromX + oToX) / 2;
-    ctx.bezierCurveTo(midX, oFromY, midX, oToY, oToX, oToY);
-  } else if (isVertical(info)) {
-    const midY = (oFromY + oToY) / 2;
-    ctx.bezierCurveTo(oFromX, midY, oToX, midY, oToX, oToY);
-  } else {
-    ctx.bezierCurveTo(oToX, oFromY, oToX, oToY, oToX, oToY);
+// Halfway along a routed path, like `path_middle()` in `layout/router.rs`
+function pathMiddle(path) {
+  const length = (a, b) => Math.abs(b.x - a.x) + Math.abs(b.y - a.y);
+  let left = 0;
+  for (let i = 1; i < path.length; i++) left += length(path[i - 1], path[i]);
+  left /= 2;
+  for (let i = 1; i < path.length; i++) {
+    const a = path[i - 1];
+    const b = path[i];
+    const l = length(a, b);
+    if (l >= left && l > 0) {
+      const t = left / l;
+      return { x: a.x + (b.x - a.x) * t, y: a.y + (b.y - a.y) * t };
+    }
+    left -= l;
   }
+  return { x: path[0].x, y: path[0].y };
+}
 
-  ctx.lineTo(toX, toY);
+function drawRelationshipPath(ctx, info) {
+  ctx.beginPath();
+  if (info.path) {
+    // Waypoints from the router
+    ctx.moveTo(info.path[0].x, info.path[0].y);
+    for (const p of info.path.slice(1)) ctx.lineTo(p.x, p.y);
+    ctx.lineJoin = "round";
+  } else {
+    // Straight segments past the markers joined by a bezier curve
+    const [start, c1, c2, end] = routeCurve(info);
+    ctx.moveTo(info.fromX, info.fromY);
+    ctx.lineTo(start.x, start.y);
+    ctx.bezierCurveTo(c1.x, c1.y, c2.x, c2.y, end.x, end.y);
+    ctx.lineTo(info.toX, info.toY);
+  }
 
-  ctx.strokeStyle = RELATION_STROKE;
-  ctx.lineWidth = RELATION_STROKE_WIDTH;
+  ctx.strokeStyle = info.highlight ? PATH_COLOR : RELATION_STROKE;
+  ctx.lineWidth = info.highlight ? 2.5 : RELATION_STROKE_WIDTH;
   ctx.stroke();
 }
 
@@ -530,34 +1091,307 @@ function drawMarker(ctx, x, y, angle, markerType) {
   ctx.restore();
 }
 
+// =========================================================================
+// Notations — the same marks as `Notation::end_marks()` and
+// `Notation::legend()` in `export/notation.rs`. A mark's symbol is a
+// cardinality (a crow's foot), "dot", "diamond" or null.
+// =========================================================================
+const NOTATION_TITLES = {
+  ie: "Crow's foot notation",
+  uml: "UML notation",
+  chen: "Chen notation",
+  idef1x: "IDEF1X notation",
+  minmax: "Min/max notation",
+};
+const MEANINGS = [
+  ["one-mandatory", "exactly one"],
+  ["one-optional", "zero or one"],
+  ["many-mandatory", "one or more"],
+  ["many-optional", "zero or more"],
+];
+const UML_LABELS = {
+  "one-mandatory": "1",
+  "one-optional": "0..1",
+  "many-mandatory": "1..*",
+  "many-optional": "0..*",
+};
+const MIN_MAX_LABELS = {
+  "one-mandatory": "(1,1)",
+  "one-optional": "(0,1)",
+  "many-mandatory": "(1,N)",
+  "many-optional": "(0,N)",
+};
+const IDEF1X_LABELS = {
+  "one-mandatory": "1",
+  "one-optional": "Z",
+  "many-mandatory": "P",
+  "many-optional": null,
+};
+
+function mark(symbol, label) {
+  return { symbol: symbol, label: label || null };
+}
+
+function isMany(cardinality) {
+  return cardinality === "many-mandatory" || cardinality === "many-optional";
+}
+
+// Marks at the from and to ends of a relationship
+function endMarks(rel) {
+  const [from, to] = rel.cardinality;
+  switch (notation) {
+    case "uml":
+      return [mark(null, UML_LABELS[from]), mark(null, UML_LABELS[to])];
+    case "minmax":
+      // Each end tells how often a row of its own table takes part
+      return [mark(null, MIN_MAX_LABELS[to]), mark(null, MIN_MAX_LABELS[from])];
+    case "chen":
+      if (isMany(from) && isMany(to)) return [mark(null, "M"), mark(null, "N")];
+      return [mark(null, isMany(from) ? "N" : "1"), mark(null, isMany(to) ? "N" : "1")];
+    case "idef1x": {
+      const referencing =
+        rel.relation_type === "OneToMany" ? 1 : rel.relation_type === "ManyToMany" ? null : 0;
+      return [0, 1].map((end) => {
+        const cardinality = rel.cardinality[end];
+        if (referencing === null || referencing === end) {
+          return mark("dot", IDEF1X_LABELS[cardinality]);
+        }
+        return mark(cardinality === "one-optional" ? "diamond" : null);
+      });
+    }
+    default:
+      return [mark(from), mark(to)];
+  }
+}
+
+// Rows of the legend: a mark and what it means
+function legendRows() {
+  switch (notation) {
+    case "uml":
+      return MEANINGS.map(([c, meaning]) => [mark(null, UML_LABELS[c]), meaning]);
+    case "minmax":
+      return MEANINGS.map(([c, meaning]) => [mark(null, MIN_MAX_LABELS[c]), "each row is in " + meaning]);
+    case "chen":
+      return [[mark(null, "1"), "one"], [mark(null, "N"), "many"], [mark("diamond"), "relationship"]];
+    case "idef1x":
+      return [
+        [mark(null), "exactly one (referenced)"],
+        [mark("diamond"), "zero or one (referenced, nullable key)"],
+        [mark("dot"), "zero or more"],
+        [mark("dot", "P"), "one or more"],
+        [mark("dot", "Z"), "zero or one"],
+        [mark("dot", "1"), "exactly one"],
+      ];
+    default:
+      return MEANINGS.map(([c, meaning]) => [mark(c), meaning]);
+  }
+}
+
+function drawEndMark(ctx, x, y, angle, m) {
+  drawSymbol(ctx, x, y, angle, m.symbol);
+  if (!m.label) return;
+
+  // Above a horizontal line, beside a vertical one
+  const dx = Math.cos(angle);
+  const dy = Math.sin(angle);
+  ctx.font = "11px " + FONT_SANS;
+  ctx.fillStyle = COLUMN_TEXT;
+  ctx.textBaseline = "middle";
+  if (Math.abs(dx) > Math.abs(dy)) {
+    ctx.textAlign = "center";
+    ctx.fillText(m.label, x + dx * 18, y - 10);
+  } else {
+    ctx.textAlign = "left";
+    ctx.fillText(m.label, x + 8, y + dy * 18);
+  }
+  ctx.textAlign = "left";
+}
+
+function drawSymbol(ctx, x, y, angle, symbol) {
+  if (symbol !== "dot" && symbol !== "diamond") {
+    if (symbol) drawMarker(ctx, x, y, angle, symbol);
+    return;
+  }
+  ctx.save();
+  ctx.translate(x, y);
+  ctx.rotate(angle);
+  ctx.strokeStyle = RELATION_STROKE;
+  ctx.lineWidth = RELATION_STROKE_WIDTH;
+  ctx.beginPath();
+  if (symbol === "dot") {
+    ctx.arc(5, 0, 4, 0, Math.PI * 2);
+    ctx.fillStyle = RELATION_STROKE;
+  } else {
+    ctx.moveTo(0, 0); ctx.lineTo(10, -7); ctx.lineTo(20, 0); ctx.lineTo(10, 7);
+    ctx.closePath();
+    ctx.fillStyle = CANVAS_BG;
+  }
+  ctx.fill();
+  ctx.stroke();
+  ctx.restore();
+}
+
+function legendSize(ctx) {
+  const rows = legendRows();
+  ctx.font = "600 13px " + FONT_SANS;
+  let text = ctx.measureText(NOTATION_TITLES[notation]).width;
+  ctx.font = "12px " + FONT_SANS;
+  for (const [, meaning] of rows) {
+    text = Math.max(text, ctx.measureText(meaning).width);
+  }
+  return {
+    w: 2 * LEGEND_PADDING + LEGEND_LINE + 12 + text,
+    h: LEGEND_HEADER + rows.length * LEGEND_ROW + 8,
+  };
+}
+
+// Box at (x, y) explaining the marks of the current notation
+function drawLegend(ctx, x, y) {
+  const { w, h } = legendSize(ctx);
+  ctx.beginPath();
+  ctx.roundRect(x, y, w, h, 6);
+  ctx.fillStyle = TABLE_BG;
+  ctx.fill();
+  ctx.strokeStyle = TABLE_BORDER;
+  ctx.lineWidth = 1;
+  ctx.stroke();
+
+  const bx = x + LEGEND_PADDING;
+  ctx.textBaseline = "middle";
+  ctx.textAlign = "left";
+  ctx.font = "600 13px " + FONT_SANS;
+  ctx.fillStyle = COLUMN_TEXT;
+  ctx.fillText(NOTATION_TITLES[notation], bx, y + LEGEND_HEADER / 2);
+
+  legendRows().forEach(([m, meaning], i) => {
+    const cy = y + LEGEND_HEADER + (i + 0.5) * LEGEND_ROW;
+    // The table edge, then the line leaving it
+    ctx.beginPath();
+    ctx.moveTo(bx, cy - 10); ctx.lineTo(bx, cy + 10);
+    ctx.moveTo(bx, cy); ctx.lineTo(bx + LEGEND_LINE, cy);
+    ctx.strokeStyle = RELATION_STROKE;
+    ctx.lineWidth = RELATION_STROKE_WIDTH;
+    ctx.stroke();
+    drawEndMark(ctx, bx, cy, 0, m);
+    ctx.font = "12px " + FONT_SANS;
+    ctx.fillStyle = COLUMN_TEXT;
+    ctx.textBaseline = "middle";
+    ctx.fillText(meaning, bx + LEGEND_LINE + 12, cy);
+  });
+}
+
+function onNotationChange(e) {
+  notation = e.target.value;
+  render();
+}
+
+function onToggleLegend() {
+  showLegend = !showLegend;
+  document.getElementById("btn-legend").classList.toggle("active", showLegend);
+  render();
+}
+
 // =========================================================================
 // Canvas drawing — Relationships
 // =========================================================================
 function drawRelationships() {
-  const routes = computeRoutes();
-  distributeConnectionPoints(routes);
+  junctionLabels = [];
+  const routes = routedRoutes();
+  requestRoutes(routes);
 
   for (let i = 0; i < diagram.relationships.length; i++) {
     const info = routes[i];
     if (!info) continue;
 
     const rel = diagram.relationships[i];
-    const fromTable = diagram.tables[info.fromIdx];
-    const toTable = diagram.tables[info.toIdx];
-    const [fromMarker, toMarker] = determineIeMarkers(rel, fromTable, toTable);
+    // From the cardinality inferred in Rust by `cardinality::infer`
+    const [fromMark, toMark] = endMarks(rel);
+    info.highlight = highlight !== null && highlight.rels.has(rel);
 
-    // Draw bezier path with straight segments at endpoints
+    // Draw the routed path, or a curve until the router answers
     drawRelationshipPath(ctx, info);
 
     // Markers aligned with the straight segments
     const startAngle = sideAngle(info.fromSide);
-    drawMarker(ctx, info.fromX, info.fromY, startAngle, fromMarker);
+    drawEndMark(ctx, info.fromX, info.fromY, startAngle, fromMark);
 
     const endAngle = sideAngle(info.toSide);
-    drawMarker(ctx, info.toX, info.toY, endAngle, toMarker);
+    drawEndMark(ctx, info.toX, info.toY, endAngle, toMark);
+
+    if (rel.junction) {
+      drawJunctionLabel(rel, info);
+      continue;
+    }
+    if (notation === "chen") {
+      const middle = routeMiddle(info);
+      drawSymbol(ctx, middle.x - 10, middle.y, 0, "diamond");
+    }
+    if (info.labelled) {
+      const text = columnLabel(rel);
+      const at = routeLabelPosition(info, text);
+      if (notation === "chen") at.y += 18;
+      drawRouteLabel(text, at, info);
+    }
   }
 }
 
+// Halfway along the route, or beside a routed self-loop since it runs close
+// to its table. Same as `route_label_position()` in `export/svg.rs`.
+function routeLabelPosition(info, text) {
+  const at = routeMiddle(info);
+  if (info.path && info.fromIdx === info.toIdx) {
+    ctx.font = "11px " + FONT_SANS;
+    const outward = info.fromSide === "Left" ? -1 : 1;
+    at.x += outward * ((ctx.measureText(text).width + 12) / 2 + 4);
+  }
+  return at;
+}
+
+// Foreign key columns of a self-loop or parallel relationship, in a pill
+// on its curve, like `write_route_label()` in `export/svg.rs`
+function drawRouteLabel(text, at, info) {
+  ctx.font = "11px " + FONT_SANS;
+  const w = ctx.measureText(text).width + 12;
+  const h = 18;
+  ctx.beginPath();
+  ctx.roundRect(at.x - w / 2, at.y - h / 2, w, h, h / 2);
+  ctx.fillStyle = CANVAS_BG;
+  ctx.fill();
+  ctx.strokeStyle = info.highlight ? PATH_COLOR : RELATION_STROKE;
+  ctx.lineWidth = 1;
+  ctx.stroke();
+  ctx.fillStyle = TYPE_TEXT;
+  ctx.textBaseline = "middle";
+  ctx.textAlign = "center";
+  ctx.fillText(text, at.x, at.y);
+  ctx.textAlign = "left";
+}
+
+// The collapsed junction's name at the middle of its edge
+function drawJunctionLabel(rel, info) {
+  const { x, y } = routeMiddle(info);
+
+  ctx.font = "600 11px " + FONT_SANS;
+  const text = rel.junction.id.name;
+  const w = ctx.measureText(text).width + 16;
+  const h = 20;
+  const label = { junction: junctionOf(rel.junction), x: x - w / 2, y: y - h / 2, w: w, h: h };
+  junctionLabels.push(label);
+
+  ctx.beginPath();
+  ctx.roundRect(label.x, label.y, w, h, h / 2);
+  ctx.fillStyle = TABLE_BG;
+  ctx.fill();
+  ctx.strokeStyle = info.highlight ? PATH_COLOR : RELATION_STROKE;
+  ctx.lineWidth = 1;
+  ctx.stroke();
+  ctx.fillStyle = COLUMN_TEXT;
+  ctx.textBaseline = "middle";
+  ctx.textAlign = "center";
+  ctx.fillText(text, x, y);
+  ctx.textAlign = "left";
+}
+
 // =========================================================================
 // Canvas drawing — Tables
 // =========================================================================
@@ -585,26 +1419,55 @@ function drawTable(table) {
   ctx.roundRect(pos.x, pos.y, w, h, BORDER_RADIUS);
   ctx.strokeStyle = TABLE_BORDER;
   ctx.lineWidth = 1;
+  // Stub of a table hidden by a filter
+  ctx.setLineDash(table.is_stub ? STUB_DASH : []);
   ctx.stroke();
+  ctx.setLineDash([]);
+  if (highlight && highlight.tables.has(tableFullName(table))) {
+    ctx.strokeStyle = PATH_COLOR;
+    ctx.lineWidth = 2.5;
+    ctx.stroke();
+  }
 
   // Header background (top rounded, bottom square)
   ctx.beginPath();
   ctx.roundRect(pos.x, pos.y, w, HEADER_HEIGHT, [BORDER_RADIUS, BORDER_RADIUS, 0, 0]);
-  ctx.fillStyle = HEADER_BG;
+  ctx.fillStyle = table.is_stub ? STUB_HEADER_BG : HEADER_BG;
   ctx.fill();
 
   // Header text (sans-serif, semibold)
   ctx.font = "600 " + HEADER_FONT_SIZE + "px " + FONT_SANS;
-  ctx.fillStyle = HEADER_TEXT;
+  ctx.fillStyle = table.is_stub ? STUB_HEADER_TEXT : HEADER_TEXT;
   ctx.textBaseline = "middle";
   ctx.textAlign = "left";
   ctx.fillText(table.id.name, pos.x + PADDING_X, pos.y + HEADER_HEIGHT / 2);
 
+  // Expanded junction, collapsed by clicking the badge
+  if (junctionOf(table)) {
+    const badge = junctionBadge(table);
+    ctx.beginPath();
+    ctx.roundRect(badge.x, badge.y, badge.w, badge.h, 4);
+    ctx.fillStyle = "rgba(241, 245, 249, 0.15)";
+    ctx.fill();
+    ctx.font = "600 10px " + FONT_SANS;
+    ctx.fillStyle = HEADER_TEXT;
+    ctx.textAlign = "center";
+    ctx.fillText(JUNCTION_BADGE, badge.x + badge.w / 2, badge.y + badge.h / 2);
+    ctx.textAlign = "left";
+  }
+
   // Columns
   for (let i = 0; i < table.columns.length; i++) {
     const col = table.columns[i];
     const rowY = pos.y + HEADER_HEIGHT + i * ROW_HEIGHT;
 
+    if (highlight && highlight.columns.has(tableFullName(table) + "." + col.name)) {
+      ctx.globalAlpha = 0.12;
+      ctx.fillStyle = PATH_COLOR;
+      ctx.fillRect(pos.x + 1, rowY, w - 2, ROW_HEIGHT);
+      ctx.globalAlpha = 1;
+    }
+
     // Subtle row separator
     if (i > 0) {
       ctx.beginPath();
@@ -686,6 +1549,12 @@ function render() {
   for (const table of diagram.tables) {
     drawTable(table);
   }
+
+  // Legend in the bottom left corner of the view
+  if (showLegend) {
+    ctx.setTransform(dpr, 0, 0, dpr, 0, 0);
+    drawLegend(ctx, 12, h - legendSize(ctx).h - 12);
+  }
 }
 
 // =========================================================================
@@ -734,7 +1603,16 @@ function fitToView() {
 // =========================================================================
 function onPointerDown(e) {
   const world = screenToWorld(e.clientX, e.clientY);
-  const idx = hitTest(world.x, world.y);
+  if (pathPick) {
+    const picked = hitTest(world.x, world.y);
+    if (picked >= 0) {
+      pickPathTable(tableFullName(diagram.tables[picked]));
+      e.preventDefault();
+      return;
+    }
+  }
+  pressAt = { x: e.clientX, y: e.clientY };
+  const idx = READ_ONLY ? -1 : hitTest(world.x, world.y);
 
   if (idx >= 0) {
     // Start dragging a table
@@ -779,6 +1657,9 @@ function onPointerMove(e) {
 }
 
 function onPointerUp(e) {
+  const clicked = pressAt && Math.hypot(e.clientX - pressAt.x, e.clientY - pressAt.y) < 4;
+  pressAt = null;
+
   if (dragState) {
     canvas.style.cursor = "";
     const table = diagram.tables[dragState.tableIdx];
@@ -792,13 +1673,17 @@ function onPointerUp(e) {
       })
     );
     dragState = null;
-    return;
-  }
-
-  if (isPanning) {
+    // Route everything again now the table has settled
+    routeKey = null;
+    render();
+  } else if (isPanning) {
     isPanning = false;
     canvas.style.cursor = "";
   }
+
+  // Last, as a click can collapse or expand a junction, changing the
+  // drawn tables
+  if (clicked) onCanvasClick(e);
 }
 
 // =========================================================================
@@ -823,9 +1708,78 @@ function onWheel(e) {
 // =========================================================================
 // Toolbar handlers
 // =========================================================================
+function exportDefaults() {
+  const defaults = window.__EXPORT_DEFAULTS || {};
+  return {
+    output: defaults.output || "",
+    options: Object.assign(
+      { scale: 2, padding: 50, transparent: false, title: false, notation: "ie", legend: false },
+      defaults.options
+    ),
+  };
+}
+
 function onExportPng() {
-  // Create an offscreen canvas at 1x scale for clean export
-  const offscreen = document.createElement("canvas");
+  const { output, options } = exportDefaults();
+  document.getElementById("export-output").value = output;
+  document.getElementById("export-scale").value = options.scale;
+  document.getElementById("export-padding").value = options.padding;
+  document.getElementById("export-transparent").checked = options.transparent;
+  document.getElementById("export-title").checked = options.title;
+  document.getElementById("export-legend").checked = options.legend;
+  document.getElementById("export-error").textContent = "";
+  document.getElementById("export-submit").disabled = false;
+  document.getElementById("export-dialog").showModal();
+}
+
+function readExportDialog() {
+  const scale = parseFloat(document.getElementById("export-scale").value);
+  const padding = parseFloat(document.getElementById("export-padding").value);
+  if (!(scale > 0 && scale <= 8)) {
+    return { error: "Scale must be greater than 0 and at most 8" };
+  }
+  if (!(padding >= 0)) {
+    return { error: "Padding must not be negative" };
+  }
+  return {
+    output: document.getElementById("export-output").value,
+    options: {
+      scale: scale,
+      padding: padding,
+      transparent: document.getElementById("export-transparent").checked,
+      title: document.getElementById("export-title").checked,
+      legend: document.getElementById("export-legend").checked,
+      // The image is drawn in the notation shown
+      notation: notation,
+    },
+  };
+}
+
+function onExportSubmit(e) {
+  e.preventDefault();
+  const result = readExportDialog();
+  if (result.error) {
+    document.getElementById("export-error").textContent = result.error;
+    return;
+  }
+
+  // Remember the choices for the next export in this session
+  window.__EXPORT_DEFAULTS = { output: result.output, options: result.options };
+  document.getElementById("export-submit").disabled = true;
+  exportPng(result.output, result.options);
+}
+
+function exportTitle() {
+  const title = (diagram.project && diagram.project.name) || window.__DIAGRAM_NAME || "";
+  const count = (n, what) => n + " " + what + (n === 1 ? "" : "s");
+  const subtitle =
+    count(diagram.tables.length, "table") + ", " +
+    count(diagram.relationships.length, "relationship");
+  return { title, subtitle };
+}
+
+// Export area in world units and its size in pixels
+function exportFrame(options) {
   let minX = Infinity, minY = Infinity, maxX = -Infinity, maxY = -Infinity;
   for (const t of diagram.tables) {
     const p = t.position || { x: 0, y: 0 };
@@ -836,45 +1790,172 @@ function onExportPng() {
     maxX = Math.max(maxX, p.x + tw);
     maxY = Math.max(maxY, p.y + h);
   }
+  // Self-loops stick out to the side of their table
+  const routes = routedRoutes();
+  routes.forEach((info, i) => {
+    if (info && info.fromIdx === info.toIdx) {
+      const text = columnLabel(diagram.relationships[i]);
+      const at = routeLabelPosition(info, text);
+      ctx.font = "11px " + FONT_SANS;
+      const half = ctx.measureText(text).width / 2 + 6;
+      minX = Math.min(minX, at.x - half);
+      maxX = Math.max(maxX, at.x + half);
+    }
+  });
+
+  const padding = options.padding;
+  const titleHeight = options.title ? TITLE_HEIGHT : 0;
+  // The legend goes below the diagram
+  const legend = options.legend ? legendSize(ctx) : null;
+  const legendHeight = legend ? legend.h + LEGEND_GAP : 0;
+  const width = Math.max(maxX - minX, legend ? legend.w : 0) + padding * 2;
+  const height = maxY - minY + padding * 2 + titleHeight + legendHeight;
+  return {
+    minX: minX,
+    maxY: maxY,
+    left: minX - padding,
+    top: minY - padding - titleHeight,
+    width: width,
+    height: height,
+    pixelW: Math.round(width * options.scale),
+    pixelH: Math.round(height * options.scale),
+  };
+}
 
-  const padding = 50;
-  const exportW = maxX - minX + padding * 2;
-  const exportH = maxY - minY + padding * 2;
-  const exportScale = 2; // 2x for crisp export
-
-  offscreen.width = exportW * exportScale;
-  offscreen.height = exportH * exportScale;
+// Render the pixel rectangle (px, py, pw, ph) of the export into a new canvas
+function renderExportTile(frame, options, px, py, pw, ph) {
+  const offscreen = document.createElement("canvas");
+  offscreen.width = pw;
+  offscreen.height = ph;
 
   const offCtx = offscreen.getContext("2d");
-  offCtx.scale(exportScale, exportScale);
-  offCtx.translate(-minX + padding, -minY + padding);
+  offCtx.translate(-px, -py);
+  offCtx.scale(options.scale, options.scale);
+  offCtx.translate(-frame.left, -frame.top);
 
   // Background
-  offCtx.fillStyle = CANVAS_BG;
-  offCtx.fillRect(minX - padding, minY - padding, exportW, exportH);
+  if (!options.transparent) {
+    offCtx.fillStyle = CANVAS_BG;
+    offCtx.fillRect(frame.left, frame.top, frame.width, frame.height);
+  }
 
-  // Temporarily swap ctx
+  if (options.title) {
+    const { title, subtitle } = exportTitle();
+    const top = frame.top + options.padding;
+    offCtx.textBaseline = "middle";
+    offCtx.textAlign = "left";
+    offCtx.font = "600 18px " + FONT_SANS;
+    offCtx.fillStyle = HEADER_BG;
+    offCtx.fillText(title, frame.minX, top + 10);
+    offCtx.font = "12px " + FONT_SANS;
+    offCtx.fillStyle = TYPE_TEXT;
+    offCtx.fillText(subtitle, frame.minX, top + 32);
+  }
+
+  // Temporarily swap ctx; the path highlight is left out of exports
   const savedCtx = ctx;
+  const savedHighlight = highlight;
   ctx = offCtx;
+  highlight = null;
   drawRelationships();
   for (const table of diagram.tables) {
     drawTable(table);
   }
+  if (options.legend) {
+    drawLegend(ctx, frame.minX, frame.maxY + LEGEND_GAP);
+  }
   ctx = savedCtx;
+  highlight = savedHighlight;
+
+  return offscreen;
+}
+
+function canvasToDataUrl(canvas) {
+  return new Promise((resolve) => {
+    canvas.toBlob(function (blob) {
+      const reader = new FileReader();
+      reader.onloadend = () => resolve(reader.result);
+      reader.readAsDataURL(blob);
+    }, "image/png");
+  });
+}
+
+function exportPng(output, options) {
+  const frame = exportFrame(options);
+
+  if (frame.pixelW > TILE_SIZE || frame.pixelH > TILE_SIZE) {
+    if (READ_ONLY) {
+      window.__onExportComplete(
+        null,
+        "The image would be " + frame.pixelW + "x" + frame.pixelH +
+          " pixels, too large for the browser; lower the scale"
+      );
+      return;
+    }
+    exportTiles(output, frame, options);
+    return;
+  }
 
+  const offscreen = renderExportTile(frame, options, 0, 0, frame.pixelW, frame.pixelH);
   offscreen.toBlob(function (blob) {
+    if (READ_ONLY) {
+      // No Rust side to save the file; let the browser download it
+      const link = document.createElement("a");
+      link.href = URL.createObjectURL(blob);
+      link.download = (window.__DIAGRAM_NAME || "diagram") + ".png";
+      link.click();
+      setTimeout(() => URL.revokeObjectURL(link.href), 0);
+      document.getElementById("export-dialog").close();
+      return;
+    }
     const reader = new FileReader();
     reader.onloadend = function () {
       window.ipc.postMessage(
-        JSON.stringify({ type: "export_png", data_url: reader.result })
+        JSON.stringify({ type: "export_png", data_url: reader.result, output: output })
       );
     };
     reader.readAsDataURL(blob);
   }, "image/png");
 }
 
+// Canvases fail above a few thousand pixels per side, so large exports are
+// rendered in tiles, row by row, and stitched together by Rust
+async function exportTiles(output, frame, options) {
+  window.ipc.postMessage(
+    JSON.stringify({
+      type: "export_tiles_begin",
+      output: output,
+      width: frame.pixelW,
+      height: frame.pixelH,
+      tile_size: TILE_SIZE,
+    })
+  );
+
+  const cols = Math.ceil(frame.pixelW / TILE_SIZE);
+  const rows = Math.ceil(frame.pixelH / TILE_SIZE);
+  for (let row = 0; row < rows; row++) {
+    for (let col = 0; col < cols; col++) {
+      const px = col * TILE_SIZE;
+      const py = row * TILE_SIZE;
+      const tile = renderExportTile(
+        frame,
+        options,
+        px,
+        py,
+        Math.min(TILE_SIZE, frame.pixelW - px),
+        Math.min(TILE_SIZE, frame.pixelH - py)
+      );
+      const dataUrl = await canvasToDataUrl(tile);
+      showToast("Exporting tile " + (row * cols + col + 1) + " of " + rows * cols);
+      window.ipc.postMessage(
+        JSON.stringify({ type: "export_tile", col: col, row: row, data_url: dataUrl })
+      );
+    }
+  }
+}
+
 function onResetLayout() {
-  if (!diagram) return;
+  if (!diagram || READ_ONLY) return;
 
   // Clear all positions
   for (const t of diagram.tables) {
@@ -912,8 +1993,23 @@ function showToast(message) {
   setTimeout(() => { toast.classList.remove("show"); }, 3000);
 }
 
-window.__onExportComplete = function (path) {
-  showToast(path ? "Exported: " + path : "Export failed");
+window.__onRoutes = function (seq, paths) {
+  // Answers can only replace older ones
+  if (seq < routeApplied) return;
+  routeApplied = seq;
+  routePaths = paths;
+  render();
+};
+
+window.__onExportComplete = function (path, error) {
+  if (error) {
+    // Keep the dialog open so the path can be fixed
+    document.getElementById("export-error").textContent = error;
+    document.getElementById("export-submit").disabled = false;
+    return;
+  }
+  document.getElementById("export-dialog").close();
+  showToast("Exported: " + path);
 };
 
 // =========================================================================
@@ -924,6 +2020,19 @@ function init() {
   document.getElementById("btn-export").addEventListener("click", onExportPng);
   document.getElementById("btn-reset").addEventListener("click", onResetLayout);
   document.getElementById("btn-fit").addEventListener("click", onFitToView);
+  document.getElementById("btn-path").addEventListener("click", onFindPath);
+  document.getElementById("btn-junctions").addEventListener("click", onToggleJunctions);
+  document.getElementById("btn-legend").addEventListener("click", onToggleLegend);
+  document.getElementById("notation").addEventListener("change", onNotationChange);
+  document.getElementById("btn-show-all").addEventListener("click", exitFocus);
+  document.getElementById("focus-depth").addEventListener("change", onFocusDepthChange);
+  document.getElementById("export-form").addEventListener("submit", onExportSubmit);
+  document.getElementById("export-cancel").addEventListener("click", () => {
+    document.getElementById("export-dialog").close();
+  });
+  if (READ_ONLY) {
+    document.body.classList.add("read-only");
+  }
 
   // Canvas setup
   canvas = document.getElementById("canvas");
@@ -937,21 +2046,34 @@ function init() {
   canvas.addEventListener("pointermove", onPointerMove);
   canvas.addEventListener("pointerup", onPointerUp);
   canvas.addEventListener("wheel", onWheel, { passive: false });
+  canvas.addEventListener("dblclick", onDoubleClick);
+  window.addEventListener("keydown", onKeyDown);
+
+  notation = exportDefaults().options.notation;
+  document.getElementById("notation").value = notation;
 
   // Load initial data
   if (window.__INITIAL_DIAGRAM) {
-    diagram = window.__INITIAL_DIAGRAM;
+    diagram = fullDiagram = window.__INITIAL_DIAGRAM;
+    resolveJunctions(window.__JUNCTIONS);
 
     // Compute per-table widths from content
     computeTableWidths();
 
-    // Auto-layout tables without positions
+    // Auto-layout tables without positions; collapsed junctions get placed
+    // when expanded
+    rebuildDiagram();
     const needsLayout = diagram.tables.some((t) => !t.position);
     if (needsLayout) {
       autoLayout();
     }
 
-    fitToView();
+    const initial = window.__INITIAL_FOCUS;
+    if (initial) {
+      focusOn(initial.table, initial.depth, initial.direction);
+    } else {
+      fitToView();
+    }
   }
 }
 
@@ -1,12 +1,17 @@
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use base64::Engine;
 use serde::Deserialize;
 
+use crate::embed;
+use crate::error::AppError;
+use crate::export::options::{create_output_dir, resolve_output_path};
+use crate::export::tiles::TileStitcher;
 use crate::ir::{Diagram, Position};
-use crate::layout::layout_file;
-use crate::layout::types::{LayoutData, LayoutMeta, TableLayout};
+use crate::layout::router::{Path as RoutePath, Rect, Router};
+use crate::layout::routing::Route;
+use crate::layout::{self, layout_file};
 
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type")]
@@ -17,8 +22,46 @@ pub enum IpcMessage {
     SaveLayout {
         tables: HashMap<String, TablePosition>,
     },
+    /// Junction tables collapsed to, or expanded from, a many-to-many
+    /// relationship.
+    #[serde(rename = "junctions_toggled")]
+    JunctionsToggled {
+        tables: Vec<String>,
+        collapsed: bool,
+    },
+    /// Table boxes and relationship ends of the editor's view, to be routed
+    /// around the tables. `moved` lists the tables dragged since the last
+    /// request; without it everything is routed again.
+    #[serde(rename = "route")]
+    Route {
+        seq: u64,
+        tables: Vec<Rect>,
+        routes: Vec<Option<Route>>,
+        #[serde(default)]
+        moved: Option<Vec<usize>>,
+    },
     #[serde(rename = "export_png")]
-    ExportPng { data_url: String },
+    ExportPng {
+        data_url: String,
+        /// Path typed in the export dialog, relative to the input's directory.
+        #[serde(default)]
+        output: Option<String>,
+    },
+    /// Start of an export too large for one canvas; tiles follow row by row.
+    #[serde(rename = "export_tiles_begin")]
+    ExportTilesBegin {
+        #[serde(default)]
+        output: Option<String>,
+        width: u32,
+        height: u32,
+        tile_size: u32,
+    },
+    #[serde(rename = "export_tile")]
+    ExportTile {
+        col: u32,
+        row: u32,
+        data_url: String,
+    },
 }
 
 #[derive(Debug, Deserialize)]
@@ -71,53 +114,105 @@ pub fn handle_save_layout(
     save_all_positions(diagram, layout_path, dbml_path);
 }
 
-/// Handle export_png: decode base64 data URL and write PNG file
-pub fn handle_export_png(dbml_path: &Path, data_url: &str) -> String {
-    let base64_data = match data_url.strip_prefix("data:image/png;base64,") {
-        Some(d) => d,
-        None => {
-            eprintln!("Invalid PNG data URL");
-            return String::new();
+/// Handle junctions_toggled: mark the junction tables and save layout.toml
+pub fn handle_junctions_toggled(
+    diagram: &mut Diagram,
+    layout_path: &Path,
+    dbml_path: &Path,
+    tables: &[String],
+    collapsed: bool,
+) {
+    for table in &mut diagram.tables {
+        if tables.contains(&table.id.full_name()) {
+            table.collapsed = collapsed;
         }
-    };
+    }
 
-    let png_bytes = match base64::engine::general_purpose::STANDARD.decode(base64_data) {
-        Ok(b) => b,
-        Err(e) => {
-            eprintln!("Failed to decode base64: {}", e);
-            return String::new();
-        }
-    };
-
-    let output_path = dbml_path.with_extension("png");
-    match std::fs::write(&output_path, &png_bytes) {
-        Ok(_) => output_path.display().to_string(),
-        Err(e) => {
-            eprintln!("Failed to write PNG: {}", e);
-            String::new()
-        }
+    save_all_positions(diagram, layout_path, dbml_path);
+}
+
+/// Handle route: update the window's router and return the paths, one per
+/// route, for `window.__onRoutes()`
+pub fn handle_route(
+    router: &mut Option<Router>,
+    tables: Vec<Rect>,
+    routes: Vec<Option<Route>>,
+    moved: Option<&[usize]>,
+) -> Vec<Option<RoutePath>> {
+    match router {
+        Some(router) => router.update(tables, routes, moved),
+        None => *router = Some(Router::new(tables, routes)),
     }
+    router
+        .as_ref()
+        .map(|r| r.paths().to_vec())
+        .unwrap_or_default()
 }
 
-fn save_all_positions(diagram: &Diagram, layout_path: &Path, dbml_path: &Path) {
-    let mut tables = HashMap::new();
-    for table in &diagram.tables {
-        if let Some(pos) = &table.position {
-            tables.insert(table.id.full_name(), TableLayout { x: pos.x, y: pos.y });
+/// Handle export_png: decode base64 data URL and write the PNG file, with
+/// the source and layout embedded, to the path chosen in the export dialog
+pub fn handle_export_png(
+    diagram: &Diagram,
+    dbml_path: &Path,
+    data_url: &str,
+    output: Option<&str>,
+) -> Result<PathBuf, AppError> {
+    let output_path = export_path(dbml_path, output)?;
+    let png_bytes = decode_png_data_url(data_url)?;
+
+    let embedded = embed::Embedded::for_diagram(diagram, dbml_path)?;
+    let png_bytes = embed::write_png_chunks(&png_bytes, &embedded)?;
+
+    create_output_dir(&output_path)?;
+    std::fs::write(&output_path, &png_bytes)?;
+    Ok(output_path)
+}
+
+/// Handle export_tiles_begin: validate the path and start the output image
+pub fn handle_export_tiles_begin(
+    diagram: &Diagram,
+    dbml_path: &Path,
+    output: Option<&str>,
+    width: u32,
+    height: u32,
+    tile_size: u32,
+) -> Result<TileStitcher, AppError> {
+    let output_path = export_path(dbml_path, output)?;
+    let embedded = embed::Embedded::for_diagram(diagram, dbml_path)?;
+    TileStitcher::create(&output_path, width, height, tile_size, &embedded)
+}
+
+/// Handle export_tile: add a tile; returns the output path after the last one
+pub fn handle_export_tile(
+    stitcher: &mut TileStitcher,
+    col: u32,
+    row: u32,
+    data_url: &str,
+) -> Result<Option<PathBuf>, AppError> {
+    stitcher.add_tile(col, row, &decode_png_data_url(data_url)?)
+}
+
+fn export_path(dbml_path: &Path, output: Option<&str>) -> Result<PathBuf, AppError> {
+    match output {
+        Some(output) => {
+            let base_dir = dbml_path.parent().unwrap_or(Path::new(""));
+            resolve_output_path(base_dir, output, "png")
         }
+        None => Ok(dbml_path.with_extension("png")),
     }
+}
 
-    let layout_data = LayoutData {
-        meta: LayoutMeta {
-            version: 1,
-            source: dbml_path
-                .file_name()
-                .map(|f| f.to_string_lossy().to_string())
-                .unwrap_or_default(),
-        },
-        tables,
-    };
+fn decode_png_data_url(data_url: &str) -> Result<Vec<u8>, AppError> {
+    let base64_data = data_url
+        .strip_prefix("data:image/png;base64,")
+        .ok_or_else(|| AppError::InvalidImage("not a PNG data URL".into()))?;
+    base64::engine::general_purpose::STANDARD
+        .decode(base64_data)
+        .map_err(|e| AppError::InvalidImage(format!("failed to decode base64: {}", e)))
+}
 
+fn save_all_positions(diagram: &Diagram, layout_path: &Path, dbml_path: &Path) {
+    let layout_data = layout::layout_data(diagram, dbml_path);
     if let Err(e) = layout_file::write_layout(layout_path, &layout_data) {
         eprintln!("Failed to save layout: {}", e);
     }
@@ -155,11 +250,78 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_route() {
+        let json = r#"{"type":"route","seq":3,"moved":[1],
+            "tables":[{"x":0,"y":0,"w":200,"h":70},{"x":400,"y":0,"w":200,"h":70}],
+            "routes":[null,{"from_table":1,"to_table":0,"from_side":"Left","to_side":"Right",
+              "from":{"x":400,"y":54},"to":{"x":200,"y":54},"offset":0,"labelled":false}]}"#;
+        let IpcMessage::Route {
+            seq,
+            tables,
+            routes,
+            moved,
+        } = parse_ipc_message(json).unwrap()
+        else {
+            panic!("Expected Route");
+        };
+        assert_eq!(seq, 3);
+
+        let mut router = None;
+        let paths = handle_route(&mut router, tables, routes, moved.as_deref());
+        assert!(paths[0].is_none());
+        let path = paths[1].as_ref().unwrap();
+        assert_eq!((path[0].x, path[1].x), (400.0, 200.0));
+        assert!(router.is_some());
+    }
+
     #[test]
     fn test_parse_export_png() {
         let json = r#"{"type":"export_png","data_url":"data:image/png;base64,iVBOR"}"#;
         let msg = parse_ipc_message(json).unwrap();
-        assert!(matches!(msg, IpcMessage::ExportPng { .. }));
+        assert!(matches!(msg, IpcMessage::ExportPng { output: None, .. }));
+
+        let json = r#"{"type":"export_png","data_url":"","output":"out/a.png"}"#;
+        match parse_ipc_message(json).unwrap() {
+            IpcMessage::ExportPng { output, .. } => {
+                assert_eq!(output.as_deref(), Some("out/a.png"))
+            }
+            _ => panic!("Expected ExportPng"),
+        }
+    }
+
+    #[test]
+    fn test_export_png_reports_bad_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let dbml_path = dir.path().join("schema.dbml");
+        std::fs::write(&dbml_path, "").unwrap();
+        let diagram = Diagram::default();
+
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, 1, 1);
+        encoder.set_color(png::ColorType::Rgba);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[0, 0, 0, 255]).unwrap();
+        writer.finish().unwrap();
+        let data_url = format!(
+            "data:image/png;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(&png)
+        );
+
+        // A missing directory is created
+        let output =
+            handle_export_png(&diagram, &dbml_path, &data_url, Some("img/schema.png")).unwrap();
+        assert_eq!(output, dir.path().join("img/schema.png"));
+        assert!(output.is_file());
+
+        // One that cannot be is named in the error
+        std::fs::write(dir.path().join("file"), "").unwrap();
+        let err = handle_export_png(&diagram, &dbml_path, &data_url, Some("file/schema.png"))
+            .unwrap_err();
+        assert!(matches!(err, AppError::InvalidExportPath(_)));
+        assert!(err
+            .to_string()
+            .contains(&dir.path().join("file").display().to_string()));
     }
 
     #[test]
@@ -182,8 +344,10 @@ mod tests {
                         type_raw: "int".into(),
                         is_pk: true,
                         is_nullable: false,
+                        ..Default::default()
                     }],
                     position: Some(Position { x: 100.0, y: 200.0 }),
+                    ..Default::default()
                 },
                 Table {
                     id: TableId::new("public", "posts"),
@@ -192,11 +356,14 @@ mod tests {
                         type_raw: "int".into(),
                         is_pk: true,
                         is_nullable: false,
+                        ..Default::default()
                     }],
                     position: Some(Position { x: 400.0, y: 200.0 }),
+                    ..Default::default()
                 },
             ],
             relationships: vec![],
+            ..Default::default()
         };
 
         let tmp = NamedTempFile::new().unwrap();
@@ -220,5 +387,20 @@ mod tests {
         let posts = layout_data.tables.get("public.posts").unwrap();
         assert!((posts.x - 400.0).abs() < f64::EPSILON);
         assert!((posts.y - 200.0).abs() < f64::EPSILON);
+        assert!(layout_data.junctions.collapsed.is_empty());
+
+        handle_junctions_toggled(
+            &mut diagram,
+            &layout_path,
+            &dbml_path,
+            &["public.posts".to_string()],
+            true,
+        );
+        let layout_data = layout_file::read_layout(&layout_path).unwrap();
+        assert_eq!(layout_data.junctions.collapsed, ["public.posts"]);
+
+        diagram.tables[1].collapsed = false;
+        layout::apply_layout_data(&mut diagram, &layout_data);
+        assert!(diagram.tables[1].collapsed);
     }
 }
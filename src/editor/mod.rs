@@ -1,16 +1,23 @@
-mod assets;
+pub(crate) mod assets;
 mod ipc;
 mod webview;
 
 use std::path::PathBuf;
 
+use crate::config::ProjectConfig;
 use crate::error::AppError;
+use crate::focus::Focus;
 use crate::ir::Diagram;
 
+/// Open `view` in the editor. `diagram` is the whole schema `view` was
+/// filtered from; layout changes are saved against it.
 pub fn open_editor(
     diagram: Diagram,
+    view: Diagram,
     dbml_path: PathBuf,
     layout_path: PathBuf,
+    config: ProjectConfig,
+    focus: Option<Focus>,
 ) -> Result<(), AppError> {
-    webview::run(diagram, dbml_path, layout_path)
+    webview::run(diagram, view, dbml_path, layout_path, config, focus)
 }
@@ -2,23 +2,39 @@ use std::cell::RefCell;
 use std::path::PathBuf;
 use std::rc::Rc;
 
+use serde_json::json;
 use tao::event::{Event, WindowEvent};
 use tao::event_loop::{ControlFlow, EventLoopBuilder, EventLoopProxy};
 use tao::window::WindowBuilder;
 use wry::WebViewBuilder;
 
+use crate::config::ProjectConfig;
 use crate::error::AppError;
+use crate::export::tiles::TileStitcher;
+use crate::focus::Focus;
 use crate::ir::Diagram;
+use crate::junction;
+use crate::layout::router::{Path, Router};
 
 use super::assets;
 use super::ipc;
 
 /// Custom events sent from IPC handler to the event loop.
 pub enum UserEvent {
-    ExportComplete(String),
+    /// Written path, or the error to show in the export dialog.
+    ExportComplete(Result<String, String>),
+    /// Paths for the editor's route request `seq`.
+    Routes { seq: u64, paths: Vec<Option<Path>> },
 }
 
-pub fn run(diagram: Diagram, dbml_path: PathBuf, layout_path: PathBuf) -> Result<(), AppError> {
+pub fn run(
+    diagram: Diagram,
+    view: Diagram,
+    dbml_path: PathBuf,
+    layout_path: PathBuf,
+    config: ProjectConfig,
+    focus: Option<Focus>,
+) -> Result<(), AppError> {
     let event_loop = EventLoopBuilder::<UserEvent>::with_user_event().build();
     let proxy: EventLoopProxy<UserEvent> = event_loop.create_proxy();
 
@@ -32,20 +48,37 @@ pub fn run(diagram: Diagram, dbml_path: PathBuf, layout_path: PathBuf) -> Result
         .map_err(|e| AppError::EditorError(e.to_string()))?;
 
     // Build the full HTML with inlined CSS and JS
-    let html = assets::EDITOR_HTML
-        .replace("/* __EDITOR_CSS__ */", assets::EDITOR_CSS)
-        .replace("/* __EDITOR_JS__ */", assets::EDITOR_JS);
+    let html = assets::editor_page("");
 
     // Serialize diagram data for injection
-    let diagram_json = serde_json::to_string(&diagram)
+    let diagram_json = serde_json::to_string(&view)
         .map_err(|e| AppError::EditorError(format!("Failed to serialize diagram: {}", e)))?;
-    let init_script = format!("window.__INITIAL_DIAGRAM = {};", diagram_json);
+    let export_defaults = json!({
+        "output": config.export_path(&dbml_path, "png"),
+        "options": config.export.options(),
+    });
+    let name = dbml_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let init_script = format!(
+        "window.__INITIAL_DIAGRAM = {};\nwindow.__EXPORT_DEFAULTS = {};\nwindow.__DIAGRAM_NAME = {};\nwindow.__INITIAL_FOCUS = {};\nwindow.__JUNCTIONS = {};",
+        diagram_json,
+        export_defaults,
+        json!(name),
+        json!(focus),
+        json!(junction::find_junctions(&view))
+    );
 
     // Wrap diagram in RefCell for interior mutability (IPC handler is Fn, not FnMut)
     let diagram = Rc::new(RefCell::new(diagram));
     let diagram_ipc = Rc::clone(&diagram);
     let dbml_path_ipc = dbml_path.clone();
     let layout_path_ipc = layout_path.clone();
+    // Large PNG export in progress
+    let tiles: RefCell<Option<TileStitcher>> = RefCell::new(None);
+    // Paths of the editor's view, kept to be updated as tables are dragged
+    let router: RefCell<Option<Router>> = RefCell::new(None);
 
     let webview = WebViewBuilder::new()
         .with_html(&html)
@@ -71,9 +104,75 @@ pub fn run(diagram: Diagram, dbml_path: PathBuf, layout_path: PathBuf) -> Result
                         &tables,
                     );
                 }
-                Ok(ipc::IpcMessage::ExportPng { data_url }) => {
-                    let path = ipc::handle_export_png(&dbml_path_ipc, &data_url);
-                    let _ = proxy.send_event(UserEvent::ExportComplete(path));
+                Ok(ipc::IpcMessage::JunctionsToggled { tables, collapsed }) => {
+                    ipc::handle_junctions_toggled(
+                        &mut diagram_ipc.borrow_mut(),
+                        &layout_path_ipc,
+                        &dbml_path_ipc,
+                        &tables,
+                        collapsed,
+                    );
+                }
+                Ok(ipc::IpcMessage::Route {
+                    seq,
+                    tables,
+                    routes,
+                    moved,
+                }) => {
+                    let paths = ipc::handle_route(
+                        &mut router.borrow_mut(),
+                        tables,
+                        routes,
+                        moved.as_deref(),
+                    );
+                    let _ = proxy.send_event(UserEvent::Routes { seq, paths });
+                }
+                Ok(ipc::IpcMessage::ExportPng { data_url, output }) => {
+                    let result = ipc::handle_export_png(
+                        &diagram_ipc.borrow(),
+                        &dbml_path_ipc,
+                        &data_url,
+                        output.as_deref(),
+                    )
+                    .map(|path| path.display().to_string())
+                    .map_err(|e| e.to_string());
+                    let _ = proxy.send_event(UserEvent::ExportComplete(result));
+                }
+                Ok(ipc::IpcMessage::ExportTilesBegin {
+                    output,
+                    width,
+                    height,
+                    tile_size,
+                }) => {
+                    let stitcher = ipc::handle_export_tiles_begin(
+                        &diagram_ipc.borrow(),
+                        &dbml_path_ipc,
+                        output.as_deref(),
+                        width,
+                        height,
+                        tile_size,
+                    );
+                    match stitcher {
+                        Ok(stitcher) => *tiles.borrow_mut() = Some(stitcher),
+                        Err(e) => {
+                            *tiles.borrow_mut() = None;
+                            let _ = proxy.send_event(UserEvent::ExportComplete(Err(e.to_string())));
+                        }
+                    }
+                }
+                Ok(ipc::IpcMessage::ExportTile { col, row, data_url }) => {
+                    let mut tiles = tiles.borrow_mut();
+                    // Tiles of an export that already failed are dropped
+                    let Some(stitcher) = tiles.as_mut() else {
+                        return;
+                    };
+                    let result = match ipc::handle_export_tile(stitcher, col, row, &data_url) {
+                        Ok(None) => return,
+                        Ok(Some(path)) => Ok(path.display().to_string()),
+                        Err(e) => Err(e.to_string()),
+                    };
+                    *tiles = None;
+                    let _ = proxy.send_event(UserEvent::ExportComplete(result));
                 }
                 Err(e) => {
                     eprintln!("IPC error: {}", e);
@@ -93,13 +192,22 @@ pub fn run(diagram: Diagram, dbml_path: PathBuf, layout_path: PathBuf) -> Result
             } => {
                 *control_flow = ControlFlow::Exit;
             }
-            Event::UserEvent(UserEvent::ExportComplete(path)) => {
+            Event::UserEvent(UserEvent::ExportComplete(result)) => {
+                let (path, error) = match result {
+                    Ok(path) => (Some(path), None),
+                    Err(error) => (None, Some(error)),
+                };
                 let js = format!(
-                    "window.__onExportComplete({})",
-                    serde_json::to_string(&path).unwrap_or_default()
+                    "window.__onExportComplete({}, {})",
+                    json!(path),
+                    json!(error)
                 );
                 let _ = webview.evaluate_script(&js);
             }
+            Event::UserEvent(UserEvent::Routes { seq, paths }) => {
+                let js = format!("window.__onRoutes({}, {})", seq, json!(paths));
+                let _ = webview.evaluate_script(&js);
+            }
             _ => {}
         }
     })
@@ -0,0 +1,325 @@
+//! DBML source and layout embedded in exported images.
+//!
+//! PNG exports carry them in two uncompressed `iTXt` chunks, SVG exports in
+//! the `<metadata>` element. `dbml-draw open` on such an image writes them
+//! back out next to it, so the diagram can be edited again.
+
+use std::path::{Path, PathBuf};
+
+use crate::error::AppError;
+use crate::export::dbml::write_dbml;
+use crate::export::style::escape_xml;
+use crate::ir::Diagram;
+use crate::layout::{self, layout_file};
+use crate::parser::InputFormat;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const DBML_KEY: &str = "dbml-draw:dbml";
+const LAYOUT_KEY: &str = "dbml-draw:layout";
+const SVG_NAMESPACE: &str = "urn:dbml-draw";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Embedded {
+    pub dbml: String,
+    /// Contents of the layout TOML file.
+    pub layout: Option<String>,
+}
+
+impl Embedded {
+    /// The source to embed for a diagram read from `source`: the file itself
+    /// when it is DBML, the diagram written as DBML otherwise.
+    pub fn for_diagram(diagram: &Diagram, source: &Path) -> Result<Self, AppError> {
+        let dbml = match InputFormat::detect(source) {
+            Ok(InputFormat::Dbml) => std::fs::read_to_string(source)?,
+            _ => write_dbml(diagram),
+        };
+        let layout_data = layout::layout_data(diagram, &source.with_extension("dbml"));
+        Ok(Self {
+            dbml,
+            layout: Some(layout_file::layout_to_string(&layout_data)?),
+        })
+    }
+}
+
+/// Keywords and texts of the `iTXt` chunks carrying `embedded`.
+pub fn png_text(embedded: &Embedded) -> Vec<(&'static str, &str)> {
+    let mut chunks = vec![(DBML_KEY, embedded.dbml.as_str())];
+    if let Some(layout) = &embedded.layout {
+        chunks.push((LAYOUT_KEY, layout.as_str()));
+    }
+    chunks
+}
+
+/// Add the embedded source to a PNG, replacing any embedded earlier. The
+/// chunks go right before `IEND`.
+pub fn write_png_chunks(png: &[u8], embedded: &Embedded) -> Result<Vec<u8>, AppError> {
+    let chunks = png_chunks(png)?;
+    let mut out = PNG_SIGNATURE.to_vec();
+    for (kind, data) in chunks {
+        if kind == b"iTXt" && itxt_text(data).is_some_and(|(k, _)| is_own_key(k)) {
+            continue;
+        }
+        if kind == b"IEND" {
+            for (key, text) in png_text(embedded) {
+                write_chunk(&mut out, b"iTXt", &itxt(key, text));
+            }
+        }
+        write_chunk(&mut out, kind, data);
+    }
+    Ok(out)
+}
+
+pub fn read_png_chunks(png: &[u8]) -> Result<Option<Embedded>, AppError> {
+    let mut dbml = None;
+    let mut layout = None;
+    for (kind, data) in png_chunks(png)? {
+        if kind != b"iTXt" {
+            continue;
+        }
+        match itxt_text(data) {
+            Some((DBML_KEY, text)) => dbml = Some(text.to_string()),
+            Some((LAYOUT_KEY, text)) => layout = Some(text.to_string()),
+            _ => {}
+        }
+    }
+    Ok(dbml.map(|dbml| Embedded { dbml, layout }))
+}
+
+/// `<metadata>` element carrying the embedded source in an SVG.
+pub fn svg_metadata(embedded: &Embedded) -> String {
+    let mut out = format!("  <metadata xmlns:dbml-draw=\"{}\">\n", SVG_NAMESPACE);
+    out.push_str(&format!(
+        "    <{key}>{}</{key}>\n",
+        escape_xml(&embedded.dbml),
+        key = DBML_KEY
+    ));
+    if let Some(layout) = &embedded.layout {
+        out.push_str(&format!(
+            "    <{key}>{}</{key}>\n",
+            escape_xml(layout),
+            key = LAYOUT_KEY
+        ));
+    }
+    out.push_str("  </metadata>\n");
+    out
+}
+
+pub fn read_svg_metadata(svg: &str) -> Option<Embedded> {
+    let element = |key: &str| {
+        let open = format!("<{}>", key);
+        let start = svg.find(&open)? + open.len();
+        let end = start + svg[start..].find(&format!("</{}>", key))?;
+        Some(unescape_xml(&svg[start..end]))
+    };
+    Some(Embedded {
+        dbml: element(DBML_KEY)?,
+        layout: element(LAYOUT_KEY),
+    })
+}
+
+/// The embedded source of a `.png` or `.svg` file; `None` for other files.
+pub fn read_image(path: &Path) -> Result<Option<Embedded>, AppError> {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let embedded = match ext.as_str() {
+        "png" => read_png_chunks(&std::fs::read(path)?)?,
+        "svg" => read_svg_metadata(&std::fs::read_to_string(path)?),
+        _ => return Ok(None),
+    };
+    embedded
+        .map(Some)
+        .ok_or_else(|| AppError::NoEmbeddedSource(path.display().to_string()))
+}
+
+/// Write the embedded source of `image` to `<stem>.dbml` and
+/// `<stem>.layout.toml` next to it and return the DBML path. Existing files
+/// are kept when they match and are never overwritten when they don't.
+pub fn extract(image: &Path, embedded: &Embedded) -> Result<PathBuf, AppError> {
+    let dbml_path = image.with_extension("dbml");
+    let layout_path = image.with_extension("layout.toml");
+
+    let dbml_conflict = dbml_path.exists() && std::fs::read_to_string(&dbml_path)? != embedded.dbml;
+    let layout_conflict = match &embedded.layout {
+        Some(layout) if layout_path.exists() => {
+            !same_toml(&std::fs::read_to_string(&layout_path)?, layout)
+        }
+        _ => false,
+    };
+    for (path, conflict) in [(&dbml_path, dbml_conflict), (&layout_path, layout_conflict)] {
+        if conflict {
+            return Err(AppError::ExtractConflict(path.display().to_string()));
+        }
+    }
+
+    std::fs::write(&dbml_path, &embedded.dbml)?;
+    if let Some(layout) = &embedded.layout {
+        std::fs::write(&layout_path, layout)?;
+    }
+    Ok(dbml_path)
+}
+
+/// Layout tables are written in hash order, so compare parsed values.
+fn same_toml(a: &str, b: &str) -> bool {
+    match (a.parse::<toml::Table>(), b.parse::<toml::Table>()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn is_own_key(key: &str) -> bool {
+    key == DBML_KEY || key == LAYOUT_KEY
+}
+
+/// Chunk type and data.
+type Chunk<'a> = (&'a [u8], &'a [u8]);
+
+fn png_chunks(png: &[u8]) -> Result<Vec<Chunk<'_>>, AppError> {
+    let invalid = || AppError::InvalidImage("not a PNG file".to_string());
+    let mut rest = png.strip_prefix(PNG_SIGNATURE).ok_or_else(invalid)?;
+    let mut chunks = Vec::new();
+    while !rest.is_empty() {
+        if rest.len() < 12 {
+            return Err(invalid());
+        }
+        let len = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        if rest.len() < 12 + len {
+            return Err(invalid());
+        }
+        chunks.push((&rest[4..8], &rest[8..8 + len]));
+        rest = &rest[12 + len..];
+    }
+    Ok(chunks)
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(kind);
+    crc.update(data);
+    out.extend_from_slice(&crc.finalize().to_be_bytes());
+}
+
+/// Uncompressed `iTXt` data: keyword, compression flag and method, empty
+/// language tag and translated keyword, then the UTF-8 text.
+fn itxt(key: &str, text: &str) -> Vec<u8> {
+    let mut data = Vec::with_capacity(key.len() + text.len() + 5);
+    data.extend_from_slice(key.as_bytes());
+    data.extend_from_slice(&[0, 0, 0, 0, 0]);
+    data.extend_from_slice(text.as_bytes());
+    data
+}
+
+/// Keyword and text of an uncompressed `iTXt` chunk.
+fn itxt_text(data: &[u8]) -> Option<(&str, &str)> {
+    let key_end = data.iter().position(|&b| b == 0)?;
+    let key = std::str::from_utf8(&data[..key_end]).ok()?;
+    let (&compressed, rest) = data[key_end + 1..].split_first()?;
+    let (_method, rest) = rest.split_first()?;
+    if compressed != 0 {
+        return None;
+    }
+    // Skip the language tag and the translated keyword
+    let lang_end = rest.iter().position(|&b| b == 0)?;
+    let rest = &rest[lang_end + 1..];
+    let translated_end = rest.iter().position(|&b| b == 0)?;
+    let text = std::str::from_utf8(&rest[translated_end + 1..]).ok()?;
+    Some((key, text))
+}
+
+fn unescape_xml(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else { break };
+        let entity = &rest[1..semi];
+        let c = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match c {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Smallest valid PNG: a 1x1 grayscale image.
+    const PIXEL: &[u8] = &[
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44,
+        0x52, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x00, 0x00, 0x00, 0x00, 0x3a,
+        0x7e, 0x9b, 0x55, 0x00, 0x00, 0x00, 0x0a, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0x60,
+        0x00, 0x00, 0x00, 0x02, 0x00, 0x01, 0x48, 0xaf, 0xa4, 0x71, 0x00, 0x00, 0x00, 0x00, 0x49,
+        0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+    ];
+
+    fn embedded() -> Embedded {
+        Embedded {
+            dbml: "Table \"ünïcode\" {\n  id int [note: '<&>']\n}\n".to_string(),
+            layout: Some("[tables.\"public.a\"]\nx = 1.0\ny = 2.0\n".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_png_roundtrip_replaces_earlier_chunks() {
+        let once = write_png_chunks(PIXEL, &embedded()).unwrap();
+        let twice = write_png_chunks(&once, &embedded()).unwrap();
+        assert_eq!(once, twice);
+        assert!(once.ends_with(&PIXEL[PIXEL.len() - 12..]));
+        assert_eq!(read_png_chunks(&once).unwrap(), Some(embedded()));
+        assert_eq!(read_png_chunks(PIXEL).unwrap(), None);
+        assert!(write_png_chunks(b"GIF89a", &embedded()).is_err());
+    }
+
+    #[test]
+    fn test_svg_roundtrip() {
+        let svg = format!("<svg>\n{}</svg>\n", svg_metadata(&embedded()));
+        assert_eq!(read_svg_metadata(&svg), Some(embedded()));
+        assert_eq!(
+            unescape_xml("a&#10;b &#x41; &unknown; &"),
+            "a\nb A &unknown; &"
+        );
+    }
+
+    #[test]
+    fn test_extract_refuses_to_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = dir.path().join("schema.png");
+
+        let dbml = extract(&image, &embedded()).unwrap();
+        assert_eq!(dbml, dir.path().join("schema.dbml"));
+        // Matching files are fine
+        extract(&image, &embedded()).unwrap();
+
+        std::fs::write(&dbml, "Table other {}\n").unwrap();
+        assert!(matches!(
+            extract(&image, &embedded()),
+            Err(AppError::ExtractConflict(_))
+        ));
+    }
+}
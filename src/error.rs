@@ -5,6 +5,18 @@ pub enum AppError {
     #[error("Failed to parse DBML: {0}")]
     ParseError(String),
 
+    #[error("Failed to parse SQL: {0}")]
+    SqlParseError(String),
+
+    #[error("Failed to parse Prisma schema: {0}")]
+    PrismaParseError(String),
+
+    #[error("Failed to read SQLite database: {0}")]
+    SqliteError(String),
+
+    #[error("Cannot detect the input format of {0}; pass --from")]
+    UnknownInputFormat(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -13,4 +25,42 @@ pub enum AppError {
 
     #[error("Editor error: {0}")]
     EditorError(String),
+
+    #[error("Config file error: {0}")]
+    ConfigError(String),
+
+    #[error("Invalid export option: {0}")]
+    InvalidExportOption(String),
+
+    #[error("Invalid export path: {0}")]
+    InvalidExportPath(String),
+
+    #[error("Invalid image: {0}")]
+    InvalidImage(String),
+
+    #[error("{0} has no embedded dbml-draw source")]
+    NoEmbeddedSource(String),
+
+    #[error(
+        "{0} already exists with different content; move it away to extract the embedded source"
+    )]
+    ExtractConflict(String),
+
+    #[error("Table not found: {0}")]
+    TableNotFound(String),
+
+    #[error("Ambiguous table name {0}; use schema.table")]
+    AmbiguousTable(String),
+
+    #[error("Column not found: {0}")]
+    ColumnNotFound(String),
+
+    #[error("Invalid rename: {0}")]
+    InvalidRename(String),
+
+    #[error("Git error: {0}")]
+    GitError(String),
+
+    #[error("{0} is not formatted")]
+    NotFormatted(String),
 }
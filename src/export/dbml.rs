@@ -0,0 +1,533 @@
+//! DBML writer.
+//!
+//! Prints a `Diagram` back as canonical DBML: project first, then enums,
+//! tables (with aligned columns), table groups, refs sorted by the order of
+//! their tables and columns, and sticky notes. Comments and notes captured by
+//! the parser are written back next to the node they belong to.
+
+use crate::ir::{
+    Column, DefaultValue, Diagram, EndPoint, Index, IndexColumn, RelationType, Relationship, Table,
+    TableId,
+};
+
+const INDENT: &str = "  ";
+
+pub fn write_dbml(diagram: &Diagram) -> String {
+    let mut blocks: Vec<String> = Vec::new();
+
+    if let Some(project) = &diagram.project {
+        let mut out = comment_lines(&project.comments, "");
+        out.push_str(&format!("Project {} {{\n", quote_ident(&project.name)));
+        for prop in &project.properties {
+            let line = format!("{}{}: {}", INDENT, prop.key, prop.value);
+            push_line(
+                &mut out,
+                INDENT,
+                &prop.comments,
+                line,
+                &prop.trailing_comment,
+            );
+        }
+        if let Some(note) = &project.note {
+            out.push_str(&format!("{}Note: {}\n", INDENT, quote_string(note)));
+        }
+        out.push_str(&comment_lines(&project.end_comments, INDENT));
+        out.push('}');
+        blocks.push(out);
+    }
+
+    for e in &diagram.enums {
+        let mut out = comment_lines(&e.comments, "");
+        out.push_str(&format!("Enum {} {{\n", qualified_name(&e.schema, &e.name)));
+        for v in &e.values {
+            let mut line = format!("{}{}", INDENT, quote_ident(&v.name));
+            if let Some(note) = &v.note {
+                line.push_str(&format!(" [note: {}]", quote_string(note)));
+            }
+            push_line(&mut out, INDENT, &v.comments, line, &v.trailing_comment);
+        }
+        out.push_str(&comment_lines(&e.end_comments, INDENT));
+        out.push('}');
+        blocks.push(out);
+    }
+
+    for table in &diagram.tables {
+        let inline_refs: Vec<&Relationship> = diagram
+            .relationships
+            .iter()
+            .filter(|r| r.is_inline && r.from.table_id == table.id)
+            .collect();
+        blocks.push(write_table(table, &inline_refs));
+    }
+
+    for group in &diagram.table_groups {
+        let mut out = comment_lines(&group.comments, "");
+        out.push_str(&format!("TableGroup {} {{\n", quote_ident(&group.name)));
+        for entry in &group.tables {
+            let line = format!("{}{}", INDENT, table_name(&entry.id));
+            push_line(
+                &mut out,
+                INDENT,
+                &entry.comments,
+                line,
+                &entry.trailing_comment,
+            );
+        }
+        if let Some(note) = &group.note {
+            out.push_str(&format!("{}Note: {}\n", INDENT, quote_string(note)));
+        }
+        out.push_str(&comment_lines(&group.end_comments, INDENT));
+        out.push('}');
+        blocks.push(out);
+    }
+
+    let mut refs: Vec<&Relationship> = diagram
+        .relationships
+        .iter()
+        .filter(|r| !r.is_inline)
+        .collect();
+    refs.sort_by_key(|r| (ref_order(diagram, &r.from), ref_order(diagram, &r.to)));
+    if !refs.is_empty() {
+        let lines: Vec<String> = refs.iter().map(|r| write_ref(r)).collect();
+        blocks.push(lines.join("\n"));
+    }
+
+    for note in &diagram.notes {
+        let mut out = comment_lines(&note.comments, "");
+        out.push_str(&format!("Note: {}", quote_string(&note.value)));
+        blocks.push(out);
+    }
+
+    if !diagram.trailing_comments.is_empty() {
+        blocks.push(
+            comment_lines(&diagram.trailing_comments, "")
+                .trim_end()
+                .to_string(),
+        );
+    }
+
+    let mut out = blocks.join("\n\n");
+    out.push('\n');
+    out
+}
+
+fn write_table(table: &Table, inline_refs: &[&Relationship]) -> String {
+    let mut out = comment_lines(&table.comments, "");
+    out.push_str(&format!("Table {}", table_name(&table.id)));
+    if let Some(alias) = &table.alias {
+        out.push_str(&format!(" as {}", quote_ident(alias)));
+    }
+    if !table.settings.is_empty() {
+        let settings: Vec<String> = table
+            .settings
+            .iter()
+            .map(|s| match &s.value {
+                Some(v) => format!("{}: {}", s.key, v),
+                None => s.key.clone(),
+            })
+            .collect();
+        out.push_str(&format!(" [{}]", settings.join(", ")));
+    }
+    out.push_str(" {\n");
+
+    // Align names, types and settings into columns
+    let names: Vec<String> = table.columns.iter().map(|c| quote_ident(&c.name)).collect();
+    let types: Vec<String> = table
+        .columns
+        .iter()
+        .map(|c| quote_type(&c.type_raw))
+        .collect();
+    let settings: Vec<String> = table
+        .columns
+        .iter()
+        .map(|c| column_settings(c, inline_refs))
+        .collect();
+    let name_w = names.iter().map(|n| n.chars().count()).max().unwrap_or(0);
+    let type_w = types.iter().map(|t| t.chars().count()).max().unwrap_or(0);
+
+    for (i, col) in table.columns.iter().enumerate() {
+        let mut line = format!("{}{:<name_w$} {:<type_w$}", INDENT, names[i], types[i]);
+        if !settings[i].is_empty() {
+            line.push(' ');
+            line.push_str(&settings[i]);
+        }
+        let line = line.trim_end().to_string();
+        push_line(&mut out, INDENT, &col.comments, line, &col.trailing_comment);
+    }
+
+    let index_comments = !table.index_comments.is_empty() || !table.index_end_comments.is_empty();
+    if !table.indexes.is_empty() || index_comments {
+        out.push('\n');
+        out.push_str(&comment_lines(&table.index_comments, INDENT));
+        out.push_str(&format!("{}Indexes {{\n", INDENT));
+        let indent = format!("{}{}", INDENT, INDENT);
+        for index in &table.indexes {
+            let line = format!("{}{}", indent, write_index(index));
+            push_line(
+                &mut out,
+                &indent,
+                &index.comments,
+                line,
+                &index.trailing_comment,
+            );
+        }
+        out.push_str(&comment_lines(&table.index_end_comments, &indent));
+        out.push_str(&format!("{}}}\n", INDENT));
+    }
+
+    if let Some(note) = &table.note {
+        out.push_str(&format!("\n{}Note: {}\n", INDENT, quote_string(note)));
+    }
+
+    if !table.end_comments.is_empty() {
+        out.push_str(&comment_lines(&table.end_comments, INDENT));
+    }
+
+    out.push('}');
+    out
+}
+
+fn column_settings(col: &Column, inline_refs: &[&Relationship]) -> String {
+    let mut settings: Vec<String> = Vec::new();
+    if col.is_pk {
+        settings.push("pk".into());
+    }
+    if col.is_increment {
+        settings.push("increment".into());
+    }
+    if col.is_unique {
+        settings.push("unique".into());
+    }
+    // A primary key is never null, so `not null` would be redundant
+    if !col.is_nullable && !col.is_pk {
+        settings.push("not null".into());
+    }
+    if let Some(default) = &col.default {
+        settings.push(format!("default: {}", write_default(default)));
+    }
+    if let Some(note) = &col.note {
+        settings.push(format!("note: {}", quote_string(note)));
+    }
+    for r in inline_refs {
+        if r.from.column_names.first() == Some(&col.name) {
+            settings.push(format!(
+                "ref: {} {}",
+                relation_symbol(r.relation_type),
+                endpoint(&r.to)
+            ));
+        }
+    }
+
+    if settings.is_empty() {
+        String::new()
+    } else {
+        format!("[{}]", settings.join(", "))
+    }
+}
+
+fn write_index(index: &Index) -> String {
+    let cols: Vec<String> = index
+        .columns
+        .iter()
+        .map(|c| match c {
+            IndexColumn::Column(name) => quote_ident(name),
+            IndexColumn::Expression(expr) => format!("`{}`", expr),
+        })
+        .collect();
+    let mut out = if cols.len() == 1 {
+        cols[0].clone()
+    } else {
+        format!("({})", cols.join(", "))
+    };
+
+    let mut settings: Vec<String> = Vec::new();
+    if index.is_pk {
+        settings.push("pk".into());
+    }
+    if index.is_unique {
+        settings.push("unique".into());
+    }
+    if let Some(name) = &index.name {
+        settings.push(format!("name: {}", quote_string(name)));
+    }
+    if let Some(t) = &index.index_type {
+        settings.push(format!("type: {}", t));
+    }
+    if let Some(note) = &index.note {
+        settings.push(format!("note: {}", quote_string(note)));
+    }
+    if !settings.is_empty() {
+        out.push_str(&format!(" [{}]", settings.join(", ")));
+    }
+    out
+}
+
+fn write_ref(rel: &Relationship) -> String {
+    let mut out = comment_lines(&rel.comments, "");
+    out.push_str("Ref");
+    if let Some(name) = &rel.name {
+        out.push(' ');
+        out.push_str(&quote_ident(name));
+    }
+    out.push_str(&format!(
+        ": {} {} {}",
+        endpoint(&rel.from),
+        relation_symbol(rel.relation_type),
+        endpoint(&rel.to)
+    ));
+
+    let mut settings: Vec<String> = Vec::new();
+    if let Some(action) = rel.on_delete {
+        settings.push(format!("delete: {}", action.keyword()));
+    }
+    if let Some(action) = rel.on_update {
+        settings.push(format!("update: {}", action.keyword()));
+    }
+    if !settings.is_empty() {
+        out.push_str(&format!(" [{}]", settings.join(", ")));
+    }
+    out
+}
+
+pub(crate) fn write_default(value: &DefaultValue) -> String {
+    match value {
+        DefaultValue::String(s) => quote_string(s),
+        DefaultValue::Number(n) => n.clone(),
+        DefaultValue::Bool(b) => b.to_string(),
+        DefaultValue::Null => "null".into(),
+        DefaultValue::Expression(e) => format!("`{}`", e),
+        DefaultValue::Identifier(i) => i.clone(),
+    }
+}
+
+pub(crate) fn relation_symbol(relation_type: RelationType) -> &'static str {
+    match relation_type {
+        RelationType::OneToOne => "-",
+        RelationType::OneToMany => "<",
+        RelationType::ManyToOne => ">",
+        RelationType::ManyToMany => "<>",
+    }
+}
+
+fn endpoint(ep: &EndPoint) -> String {
+    let cols: Vec<String> = ep.column_names.iter().map(|c| quote_ident(c)).collect();
+    let cols = if cols.len() == 1 {
+        cols[0].clone()
+    } else {
+        format!("({})", cols.join(", "))
+    };
+    format!("{}.{}", table_name(&ep.table_id), cols)
+}
+
+/// Position of an endpoint in declaration order, used to sort refs.
+fn ref_order(diagram: &Diagram, ep: &EndPoint) -> (usize, usize) {
+    let Some(ti) = diagram.tables.iter().position(|t| t.id == ep.table_id) else {
+        return (usize::MAX, usize::MAX);
+    };
+    let ci = ep
+        .column_names
+        .first()
+        .and_then(|name| {
+            diagram.tables[ti]
+                .columns
+                .iter()
+                .position(|c| c.name == *name)
+        })
+        .unwrap_or(usize::MAX);
+    (ti, ci)
+}
+
+/// Table name, omitting the default `public` schema.
+fn table_name(id: &TableId) -> String {
+    qualified_name(&id.schema, &id.name)
+}
+
+fn qualified_name(schema: &str, name: &str) -> String {
+    if schema == "public" {
+        quote_ident(name)
+    } else {
+        format!("{}.{}", quote_ident(schema), quote_ident(name))
+    }
+}
+
+/// Identifiers are written bare when possible, otherwise double-quoted.
+fn quote_ident(name: &str) -> String {
+    let mut chars = name.chars();
+    let bare = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if bare {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name)
+    }
+}
+
+/// Types from other front ends may contain spaces, which DBML only accepts quoted.
+fn quote_type(type_raw: &str) -> String {
+    if type_raw.contains(' ') && !type_raw.starts_with('"') {
+        format!("\"{}\"", type_raw)
+    } else {
+        type_raw.to_string()
+    }
+}
+
+/// Strings are single-quoted; multi-line strings and strings containing a
+/// quote use triple quotes.
+fn quote_string(s: &str) -> String {
+    if s.contains('\n') || s.contains('\'') {
+        format!("'''{}'''", s)
+    } else {
+        format!("'{}'", s)
+    }
+}
+
+/// A line of a block body, with the comments above it and the one after it.
+fn push_line(
+    out: &mut String,
+    indent: &str,
+    comments: &[String],
+    mut line: String,
+    trailing: &Option<String>,
+) {
+    out.push_str(&comment_lines(comments, indent));
+    if let Some(comment) = trailing {
+        line.push(' ');
+        line.push_str(comment);
+    }
+    out.push_str(&line);
+    out.push('\n');
+}
+
+fn comment_lines(comments: &[String], indent: &str) -> String {
+    comments
+        .iter()
+        .map(|c| format!("{}{}\n", indent, c))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_dbml;
+
+    const COMMENTED: &str = r#"Project app {
+  database_type: 'PostgreSQL'
+}
+
+// Accounts
+Table users as U [headercolor: #3498DB] {
+  id int [pk, increment] // surrogate key
+  // login name
+  "user name" varchar [not null, unique, note: 'shown in UI']
+  status user_status [default: 'active']
+  created_at timestamp [default: `now()`]
+
+  Indexes {
+    (id, "user name") [unique, name: 'users_idx']
+  }
+
+  Note: 'All users'
+}
+
+Enum user_status {
+  active
+  banned [note: 'no login']
+}
+
+Table posts {
+  id int [pk]
+  user_id int [ref: > users.id]
+  editor_id int
+}
+
+// Editors
+Ref editor: posts.editor_id > users.id [delete: cascade]
+
+// the end
+"#;
+
+    #[test]
+    fn test_basic_roundtrip_is_stable() {
+        let first =
+            write_dbml(&parse_dbml(include_str!("../../tests/fixtures/basic.dbml")).unwrap());
+        let second = write_dbml(&parse_dbml(&first).unwrap());
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_columns_are_aligned() {
+        let out = write_dbml(&parse_dbml(include_str!("../../tests/fixtures/basic.dbml")).unwrap());
+        assert!(out.contains("  id         int       [pk, increment]\n"));
+        assert!(out.contains("  email      varchar   [unique, not null]\n"));
+        assert!(out.contains("  created_at timestamp [default: `now()`]\n"));
+    }
+
+    #[test]
+    fn test_comments_and_notes_are_kept() {
+        let out = write_dbml(&parse_dbml(COMMENTED).unwrap());
+        assert!(out.contains(
+            "}\n\nEnum user_status {\n  active\n  banned [note: 'no login']\n}\n\n// Accounts"
+        ));
+        assert!(out.contains("// Accounts\nTable users as U [headercolor: #3498DB] {\n"));
+        assert!(out.contains("[pk, increment] // surrogate key\n  // login name\n"));
+        assert!(out.contains("[unique, not null, note: 'shown in UI']"));
+        assert!(out.contains("    (id, \"user name\") [unique, name: 'users_idx']\n"));
+        assert!(out.contains("  Note: 'All users'\n"));
+        assert!(
+            out.contains("// Editors\nRef editor: posts.editor_id > users.id [delete: cascade]")
+        );
+        assert!(out.ends_with("// the end\n"));
+    }
+
+    #[test]
+    fn test_comments_inside_blocks_are_kept() {
+        let input = r#"Project app {
+  // db comment
+  database_type: 'PostgreSQL' // verbatim
+  // end of project
+}
+
+Enum status {
+  active // the active state
+  // banned users
+  banned [note: 'no login']
+  // end of enum
+}
+
+Table users {
+  id   int     [pk]
+  name varchar // display name
+
+  // lookups
+  Indexes {
+    // idx comment
+    name [unique] // by name
+    // end of indexes
+  }
+  // end of table
+}
+
+TableGroup accounts {
+  // in group
+  users // the only one
+  // end of group
+}
+"#;
+        assert_eq!(write_dbml(&parse_dbml(input).unwrap()), input);
+    }
+
+    #[test]
+    fn test_inline_refs_stay_inline() {
+        let out = write_dbml(&parse_dbml(COMMENTED).unwrap());
+        assert!(out.contains("  user_id   int [ref: > users.id]\n"));
+        assert_eq!(out.matches("Ref").count(), 1);
+    }
+
+    #[test]
+    fn test_refs_are_ordered() {
+        let input = "Project p {\n database_type: 'PostgreSQL'\n}\nTable a {\n id int [pk]\n}\nTable b {\n id int [pk]\n a_id int\n}\n\
+                     Ref: b.a_id > a.id\nRef: a.id - b.id\n";
+        let out = write_dbml(&parse_dbml(input).unwrap());
+        assert!(out.contains("Ref: a.id - b.id\nRef: b.a_id > a.id"));
+    }
+}
@@ -0,0 +1,452 @@
+//! draw.io (diagrams.net) exporter.
+//!
+//! Each table becomes a `shape=table` container with one row per column, so
+//! the result stays editable in draw.io. Relationships become edges between
+//! the column rows, marked in the chosen notation: draw.io's ER crow's-foot
+//! arrows, or dots, diamonds and edge labels for the others. Self-loops and
+//! parallel relationships carry their foreign key columns as the edge value.
+
+use super::notation::{
+    Notation, Symbol, LEGEND_GAP, LEGEND_HEADER, LEGEND_LINE, LEGEND_PADDING, LEGEND_ROW,
+};
+use super::options::ExportOptions;
+use super::style::{
+    escape_xml, header_colors, CANVAS_BG, COLUMN_TEXT, PK_COLOR, RELATION_STROKE, ROW_SEPARATOR,
+    TABLE_BG, TABLE_BORDER, TYPE_TEXT,
+};
+use crate::ir::{Cardinality, Diagram, Relationship, Table};
+use crate::layout::metrics::{
+    diagram_bounds, mono_text_width, table_height, table_width, HEADER_HEIGHT, PADDING_X,
+    ROW_HEIGHT,
+};
+use crate::layout::routing::{column_label, compute_routes};
+
+/// draw.io resolves fonts by name, not by CSS font stack.
+const FONT_MONO: &str = "Menlo";
+
+fn arrow_style(marker: Cardinality) -> &'static str {
+    match marker {
+        Cardinality::OneMandatory => "ERmandOne",
+        Cardinality::OneOptional => "ERzeroToOne",
+        Cardinality::ManyMandatory => "ERoneToMany",
+        Cardinality::ManyOptional => "ERzeroToMany",
+    }
+}
+
+/// Arrow and fill of an end of an edge.
+fn arrow(symbol: Symbol) -> (&'static str, u8) {
+    match symbol {
+        Symbol::None => ("none", 0),
+        Symbol::Ie(cardinality) => (arrow_style(cardinality), 0),
+        Symbol::Dot => ("oval", 1),
+        Symbol::Diamond => ("diamond", 0),
+    }
+}
+
+/// Render a positioned diagram as a draw.io XML document.
+///
+/// Tables must already have positions (see `layout::ensure_positions`).
+/// Only the notation and legend of `options` apply.
+pub fn write_drawio(diagram: &Diagram, name: &str, options: &ExportOptions) -> String {
+    let mut cells = String::new();
+
+    for (ti, table) in diagram.tables.iter().enumerate() {
+        write_table(&mut cells, ti, table);
+    }
+
+    let routes = compute_routes(diagram);
+    for (ri, rel) in diagram.relationships.iter().enumerate() {
+        let labelled = routes[ri].is_some_and(|r| r.labelled);
+        let fi = diagram
+            .tables
+            .iter()
+            .position(|t| t.id == rel.from.table_id);
+        let ti = diagram.tables.iter().position(|t| t.id == rel.to.table_id);
+        let (Some(fi), Some(ti)) = (fi, ti) else {
+            continue;
+        };
+        write_relationship(
+            &mut cells,
+            ri,
+            rel,
+            labelled,
+            options.notation,
+            (fi, &diagram.tables[fi]),
+            (ti, &diagram.tables[ti]),
+        );
+    }
+
+    if options.legend {
+        let (min_x, _, _, max_y) = diagram_bounds(diagram).unwrap_or((0.0, 0.0, 0.0, 0.0));
+        write_legend(&mut cells, options.notation, min_x, max_y + LEGEND_GAP);
+    }
+
+    format!(
+        concat!(
+            "<mxfile host=\"dbml-draw\">\n",
+            "  <diagram id=\"dbml-draw\" name=\"{name}\">\n",
+            "    <mxGraphModel grid=\"1\" gridSize=\"10\" guides=\"1\" tooltips=\"1\" connect=\"1\" ",
+            "arrows=\"1\" fold=\"1\" page=\"0\" pageScale=\"1\" math=\"0\" shadow=\"0\">\n",
+            "      <root>\n",
+            "        <mxCell id=\"0\"/>\n",
+            "        <mxCell id=\"1\" parent=\"0\"/>\n",
+            "{cells}",
+            "      </root>\n",
+            "    </mxGraphModel>\n",
+            "  </diagram>\n",
+            "</mxfile>\n"
+        ),
+        name = escape_xml(name),
+        cells = cells
+    )
+}
+
+fn write_table(out: &mut String, ti: usize, table: &Table) {
+    let pos = table
+        .position
+        .unwrap_or(crate::ir::Position { x: 0.0, y: 0.0 });
+    let w = table_width(table);
+    let h = table_height(table);
+
+    // Split the row into a name cell and a right-aligned type cell
+    let type_w = table
+        .columns
+        .iter()
+        .map(|c| mono_text_width(&c.type_raw))
+        .fold(0.0, f64::max)
+        + PADDING_X * 1.5;
+    let name_w = w - type_w;
+    let (header_bg, header_text) = header_colors(table);
+
+    out.push_str(&format!(
+        concat!(
+            "        <mxCell id=\"{id}\" value=\"{value}\" style=\"shape=table;startSize={header};",
+            "container=1;collapsible=0;childLayout=tableLayout;fixedRows=1;rowLines=0;",
+            "fontStyle=1;align=left;spacingLeft={pad};fillColor={header_bg};fontColor={header_text};",
+            "strokeColor={border};swimlaneFillColor={bg};rounded=1;arcSize=4;{dashed}\" vertex=\"1\" parent=\"1\">\n",
+            "          <mxGeometry x=\"{x}\" y=\"{y}\" width=\"{w}\" height=\"{h}\" as=\"geometry\"/>\n",
+            "        </mxCell>\n"
+        ),
+        id = table_cell_id(ti),
+        value = escape_xml(&table.id.name),
+        header = HEADER_HEIGHT,
+        pad = PADDING_X,
+        header_bg = header_bg,
+        header_text = header_text,
+        border = TABLE_BORDER,
+        bg = TABLE_BG,
+        dashed = if table.is_stub { "dashed=1;" } else { "" },
+        x = pos.x,
+        y = pos.y,
+        w = w,
+        h = h,
+    ));
+
+    for (ci, col) in table.columns.iter().enumerate() {
+        let row_id = row_cell_id(ti, ci);
+        let row_y = HEADER_HEIGHT + ci as f64 * ROW_HEIGHT;
+        out.push_str(&format!(
+            concat!(
+                "        <mxCell id=\"{id}\" value=\"\" style=\"shape=tableRow;horizontal=0;startSize=0;",
+                "swimlaneHead=0;swimlaneBody=0;fillColor={bg};collapsible=0;dropTarget=0;",
+                "points=[[0,0.5],[1,0.5]];portConstraint=eastwest;top={top};left=0;right=0;bottom=0;",
+                "strokeColor={sep};\" vertex=\"1\" parent=\"{parent}\">\n",
+                "          <mxGeometry y=\"{y}\" width=\"{w}\" height=\"{rh}\" as=\"geometry\"/>\n",
+                "        </mxCell>\n"
+            ),
+            id = row_id,
+            bg = TABLE_BG,
+            top = if ci > 0 { 1 } else { 0 },
+            sep = ROW_SEPARATOR,
+            parent = table_cell_id(ti),
+            y = row_y,
+            w = w,
+            rh = ROW_HEIGHT,
+        ));
+
+        let (name, name_style, name_color) = if col.is_pk {
+            (format!("PK  {}", col.name), 1, PK_COLOR)
+        } else {
+            (col.name.clone(), 0, COLUMN_TEXT)
+        };
+        write_row_cell(
+            out,
+            &format!("{}-name", row_id),
+            &row_id,
+            &name,
+            &format!(
+                "align=left;spacingLeft={};fontStyle={};fontColor={};",
+                PADDING_X, name_style, name_color
+            ),
+            0.0,
+            name_w,
+        );
+        write_row_cell(
+            out,
+            &format!("{}-type", row_id),
+            &row_id,
+            &col.type_raw,
+            &format!(
+                "align=right;spacingRight={};fontColor={};",
+                PADDING_X, TYPE_TEXT
+            ),
+            name_w,
+            type_w,
+        );
+    }
+}
+
+fn write_row_cell(
+    out: &mut String,
+    id: &str,
+    parent: &str,
+    value: &str,
+    style: &str,
+    x: f64,
+    w: f64,
+) {
+    out.push_str(&format!(
+        concat!(
+            "        <mxCell id=\"{id}\" value=\"{value}\" style=\"shape=partialRectangle;connectable=0;",
+            "fillColor=none;top=0;left=0;bottom=0;right=0;overflow=hidden;fontFamily={font};",
+            "fontSize=13;{style}\" vertex=\"1\" parent=\"{parent}\">\n",
+            "          <mxGeometry x=\"{x}\" width=\"{w}\" height=\"{rh}\" as=\"geometry\">\n",
+            "            <mxRectangle width=\"{w}\" height=\"{rh}\" as=\"alternateBounds\"/>\n",
+            "          </mxGeometry>\n",
+            "        </mxCell>\n"
+        ),
+        id = escape_xml(id),
+        value = escape_xml(value),
+        font = FONT_MONO,
+        style = style,
+        parent = escape_xml(parent),
+        x = x,
+        w = w,
+        rh = ROW_HEIGHT,
+    ));
+}
+
+fn write_relationship(
+    out: &mut String,
+    ri: usize,
+    rel: &Relationship,
+    labelled: bool,
+    notation: Notation,
+    (fi, from_table): (usize, &Table),
+    (ti, to_table): (usize, &Table),
+) {
+    let [from_mark, to_mark] = notation.end_marks(rel);
+    let (start, start_fill) = arrow(from_mark.symbol);
+    let (end, end_fill) = arrow(to_mark.symbol);
+    let source = endpoint_cell_id(fi, from_table, &rel.from.column_names);
+    let target = endpoint_cell_id(ti, to_table, &rel.to.column_names);
+    let id = format!("rel-{}", ri);
+
+    out.push_str(&format!(
+        concat!(
+            "        <mxCell id=\"{id}\" value=\"{value}\" style=\"edgeStyle=entityRelationEdgeStyle;",
+            "html=0;fontSize=11;fontColor={label_color};labelBackgroundColor={label_bg};startArrow={start};endArrow={end};startFill={start_fill};endFill={end_fill};",
+            "startSize=12;endSize=12;strokeColor={stroke};strokeWidth=1.5;\" edge=\"1\" parent=\"1\" ",
+            "source=\"{source}\" target=\"{target}\">\n",
+            "          <mxGeometry relative=\"1\" as=\"geometry\"/>\n",
+            "        </mxCell>\n"
+        ),
+        id = id,
+        value = if labelled {
+            escape_xml(&column_label(rel))
+        } else {
+            String::new()
+        },
+        label_color = TYPE_TEXT,
+        label_bg = CANVAS_BG,
+        start = start,
+        end = end,
+        start_fill = start_fill,
+        end_fill = end_fill,
+        stroke = RELATION_STROKE,
+        source = source,
+        target = target,
+    ));
+    if let Some(label) = from_mark.label {
+        write_edge_label(out, &format!("{}-from", id), &id, label, -0.8);
+    }
+    if let Some(label) = to_mark.label {
+        write_edge_label(out, &format!("{}-to", id), &id, label, 0.8);
+    }
+    if notation.middle_diamond() {
+        out.push_str(&format!(
+            concat!(
+                "        <mxCell id=\"{id}-diamond\" value=\"\" style=\"rhombus;fillColor={bg};",
+                "strokeColor={stroke};strokeWidth=1.5;\" vertex=\"1\" connectable=\"0\" parent=\"{id}\">\n",
+                "          <mxGeometry width=\"20\" height=\"14\" relative=\"1\" as=\"geometry\">\n",
+                "            <mxPoint x=\"-10\" y=\"-7\" as=\"offset\"/>\n",
+                "          </mxGeometry>\n",
+                "        </mxCell>\n"
+            ),
+            id = id,
+            bg = CANVAS_BG,
+            stroke = RELATION_STROKE,
+        ));
+    }
+}
+
+/// Text on edge `parent`, `x` along it from -1 (source) to 1 (target).
+fn write_edge_label(out: &mut String, id: &str, parent: &str, value: &str, x: f64) {
+    out.push_str(&format!(
+        concat!(
+            "        <mxCell id=\"{id}\" value=\"{value}\" style=\"edgeLabel;html=0;resizable=0;",
+            "labelBackgroundColor=none;fontSize=11;fontColor={color};\" vertex=\"1\" connectable=\"0\" ",
+            "parent=\"{parent}\">\n",
+            "          <mxGeometry x=\"{x}\" relative=\"1\" as=\"geometry\">\n",
+            "            <mxPoint y=\"-10\" as=\"offset\"/>\n",
+            "          </mxGeometry>\n",
+            "        </mxCell>\n"
+        ),
+        id = escape_xml(id),
+        value = escape_xml(value),
+        color = COLUMN_TEXT,
+        parent = escape_xml(parent),
+        x = x,
+    ));
+}
+
+/// Box at (`x`, `y`) explaining the marks of `notation`, with each sample
+/// line an unconnected edge.
+fn write_legend(out: &mut String, notation: Notation, x: f64, y: f64) {
+    let (w, h) = notation.legend_size();
+    out.push_str(&format!(
+        concat!(
+            "        <mxCell id=\"legend\" value=\"{title}\" style=\"rounded=1;arcSize=6;absoluteArcSize=1;",
+            "html=0;fillColor={bg};strokeColor={border};fontColor={color};fontSize=13;",
+            "fontStyle=1;align=left;verticalAlign=top;spacingLeft={pad};spacingTop=6;\" vertex=\"1\" ",
+            "parent=\"1\">\n",
+            "          <mxGeometry x=\"{x}\" y=\"{y}\" width=\"{w}\" height=\"{h}\" as=\"geometry\"/>\n",
+            "        </mxCell>\n"
+        ),
+        title = escape_xml(notation.title()),
+        bg = TABLE_BG,
+        border = TABLE_BORDER,
+        color = COLUMN_TEXT,
+        pad = LEGEND_PADDING - 2.0,
+        x = x,
+        y = y,
+        w = w,
+        h = h,
+    ));
+
+    let bx = x + LEGEND_PADDING;
+    for (i, (mark, meaning)) in notation.legend().into_iter().enumerate() {
+        let cy = y + LEGEND_HEADER + (i as f64 + 0.5) * LEGEND_ROW;
+        let id = format!("legend-{}", i);
+        let (start, start_fill) = arrow(mark.symbol);
+        out.push_str(&format!(
+            concat!(
+                "        <mxCell id=\"{id}\" value=\"\" style=\"html=0;startArrow={start};startFill={fill};",
+                "endArrow=none;startSize=12;strokeColor={stroke};strokeWidth=1.5;\" edge=\"1\" parent=\"1\">\n",
+                "          <mxGeometry relative=\"1\" as=\"geometry\">\n",
+                "            <mxPoint x=\"{x0}\" y=\"{cy}\" as=\"sourcePoint\"/>\n",
+                "            <mxPoint x=\"{x1}\" y=\"{cy}\" as=\"targetPoint\"/>\n",
+                "          </mxGeometry>\n",
+                "        </mxCell>\n",
+                "        <mxCell id=\"{id}-edge\" value=\"\" style=\"html=0;endArrow=none;",
+                "strokeColor={stroke};strokeWidth=1.5;\" edge=\"1\" parent=\"1\">\n",
+                "          <mxGeometry relative=\"1\" as=\"geometry\">\n",
+                "            <mxPoint x=\"{x0}\" y=\"{top}\" as=\"sourcePoint\"/>\n",
+                "            <mxPoint x=\"{x0}\" y=\"{bottom}\" as=\"targetPoint\"/>\n",
+                "          </mxGeometry>\n",
+                "        </mxCell>\n",
+                "        <mxCell id=\"{id}-text\" value=\"{meaning}\" style=\"text;html=0;align=left;",
+                "verticalAlign=middle;fontSize=12;fontColor={color};\" vertex=\"1\" parent=\"1\">\n",
+                "          <mxGeometry x=\"{tx}\" y=\"{ty}\" width=\"{tw}\" height=\"20\" as=\"geometry\"/>\n",
+                "        </mxCell>\n"
+            ),
+            id = id,
+            start = start,
+            fill = start_fill,
+            stroke = RELATION_STROKE,
+            x0 = bx,
+            x1 = bx + LEGEND_LINE,
+            cy = cy,
+            top = cy - 10.0,
+            bottom = cy + 10.0,
+            meaning = escape_xml(meaning),
+            color = COLUMN_TEXT,
+            tx = bx + LEGEND_LINE + 12.0,
+            ty = cy - 10.0,
+            tw = x + w - (bx + LEGEND_LINE + 12.0),
+        ));
+        if let Some(label) = mark.label {
+            write_edge_label(out, &format!("{}-label", id), &id, label, -0.4);
+        }
+    }
+}
+
+fn table_cell_id(ti: usize) -> String {
+    format!("table-{}", ti)
+}
+
+fn row_cell_id(ti: usize, ci: usize) -> String {
+    format!("table-{}-row-{}", ti, ci)
+}
+
+/// Edges attach to the row of the first endpoint column, or to the table
+/// itself when the column is unknown.
+fn endpoint_cell_id(ti: usize, table: &Table, column_names: &[String]) -> String {
+    column_names
+        .first()
+        .and_then(|name| table.columns.iter().position(|c| c.name == *name))
+        .map(|ci| row_cell_id(ti, ci))
+        .unwrap_or_else(|| table_cell_id(ti))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout;
+    use crate::parser::parse_dbml;
+
+    fn basic_drawio() -> String {
+        let mut diagram = parse_dbml(include_str!("../../tests/fixtures/basic.dbml")).unwrap();
+        layout::ensure_positions(&mut diagram);
+        write_drawio(&diagram, "basic", &ExportOptions::default())
+    }
+
+    #[test]
+    fn test_tables_become_table_shapes() {
+        let xml = basic_drawio();
+        assert!(xml.starts_with("<mxfile"));
+        assert_eq!(xml.matches("shape=table;").count(), 4);
+        assert!(xml.contains("value=\"users\""));
+        assert!(xml.contains("value=\"PK  id\""));
+        assert!(xml.contains("value=\"varchar\""));
+    }
+
+    #[test]
+    fn test_relationships_use_er_arrows() {
+        let xml = basic_drawio();
+        assert_eq!(xml.matches("edge=\"1\"").count(), 4);
+        // posts.user_id (not null) > users.id
+        assert!(xml.contains("startArrow=ERzeroToMany;endArrow=ERmandOne"));
+        // profiles.user_id (nullable) - users.id
+        assert!(xml.contains("startArrow=ERzeroToOne;endArrow=ERzeroToOne"));
+        // Edges attach to the column rows
+        assert!(xml.contains("source=\"table-1-row-1\" target=\"table-0-row-0\""));
+    }
+
+    #[test]
+    fn test_idef1x_with_legend() {
+        let mut diagram = parse_dbml(include_str!("../../tests/fixtures/basic.dbml")).unwrap();
+        layout::ensure_positions(&mut diagram);
+        let options = ExportOptions {
+            notation: Notation::Idef1x,
+            legend: true,
+            ..Default::default()
+        };
+        let xml = write_drawio(&diagram, "basic", &options);
+
+        // posts.user_id (not null) > users.id: a dot at posts, nothing at users
+        assert!(xml.contains("startArrow=oval;endArrow=none;startFill=1;endFill=0"));
+        assert!(xml.contains("id=\"legend\" value=\"IDEF1X notation\""));
+        assert_eq!(xml.matches("id=\"legend-").count(), 6 * 3 + 3);
+    }
+}
@@ -0,0 +1,113 @@
+//! Standalone HTML viewer exporter.
+//!
+//! Reuses the editor page with the diagram (positions included) inlined
+//! and the editor switched to read-only, so the file can be opened in any
+//! browser without dbml-draw or a network connection. The export options
+//! become the viewer's defaults, so it starts in their notation.
+//!
+//! There is no Rust side to route relationships in the browser, so the
+//! paths from `layout::router` are inlined too, with the table widths they
+//! were routed around.
+
+use super::options::ExportOptions;
+use crate::editor::assets;
+use crate::error::AppError;
+use crate::ir::Diagram;
+use crate::junction;
+use crate::layout::metrics::table_width;
+use crate::layout::router::Router;
+
+pub fn write_html(
+    diagram: &Diagram,
+    name: &str,
+    options: &ExportOptions,
+) -> Result<String, AppError> {
+    let diagram_json = script_json(diagram)?;
+    let defaults_json = script_json(&serde_json::json!({ "options": options }))?;
+    let name_json = script_json(name)?;
+    let junctions_json = script_json(&junction::find_junctions(diagram))?;
+
+    // Routed as the viewer first shows it, with the marked junctions collapsed
+    let mut shown = diagram.clone();
+    junction::collapse_marked(&mut shown);
+    let routes_json = script_json(Router::for_diagram(&shown).paths())?;
+    let widths: serde_json::Map<String, serde_json::Value> = shown
+        .tables
+        .iter()
+        .map(|t| (t.id.full_name(), table_width(t).into()))
+        .collect();
+    let widths_json = script_json(&widths)?;
+
+    let init_script = format!(
+        "window.__READ_ONLY = true;\n\
+         window.__DIAGRAM_NAME = {name};\n\
+         window.__INITIAL_DIAGRAM = {diagram};\n\
+         window.__EXPORT_DEFAULTS = {defaults};\n\
+         window.__JUNCTIONS = {junctions};\n\
+         window.__ROUTES = {routes};\n\
+         window.__TABLE_WIDTHS = {widths};\n\
+         document.title = {name} + \" — dbml-draw\";",
+        name = name_json,
+        diagram = diagram_json,
+        defaults = defaults_json,
+        junctions = junctions_json,
+        routes = routes_json,
+        widths = widths_json,
+    );
+    Ok(assets::editor_page(&init_script))
+}
+
+/// JSON that is safe to inline in a `<script>` element: `</script>` or
+/// `<!--` inside a string must not end or change the element.
+fn script_json<T: serde::Serialize + ?Sized>(value: &T) -> Result<String, AppError> {
+    let json = serde_json::to_string(value)
+        .map_err(|e| AppError::EditorError(format!("Failed to serialize diagram: {}", e)))?;
+    Ok(json.replace('<', "\\u003c"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_dbml;
+
+    #[test]
+    fn test_html_is_self_contained_and_read_only() {
+        let diagram = parse_dbml(
+            "Project p {\n  database_type: 'PostgreSQL'\n}\n\
+             Table users {\n  id int [pk]\n  Note: '</script><b>'\n}\n",
+        )
+        .unwrap();
+        let html = write_html(&diagram, "schema", &ExportOptions::default()).unwrap();
+
+        assert!(html.contains("window.__READ_ONLY = true;"));
+        assert!(!html.contains("/* __EDITOR_JS__ */"));
+        assert!(!html.contains("/* __EDITOR_CSS__ */"));
+        assert!(html.contains("\\u003c/script>\\u003cb>"));
+        assert_eq!(html.matches("</script>").count(), 2);
+        // Nothing is loaded from elsewhere
+        assert!(!html.contains("src=\"http"));
+        assert!(!html.contains("href=\"http"));
+    }
+
+    #[test]
+    fn test_html_inlines_routed_paths() {
+        let mut diagram = parse_dbml(include_str!("../../tests/fixtures/basic.dbml")).unwrap();
+        crate::layout::ensure_positions(&mut diagram);
+        let html = write_html(&diagram, "basic", &ExportOptions::default()).unwrap();
+
+        let paths = Router::for_diagram(&diagram).paths().to_vec();
+        assert!(paths
+            .iter()
+            .all(|p| p.as_ref().is_some_and(|p| p.len() >= 2)));
+        assert!(html.contains(&format!(
+            "window.__ROUTES = {};",
+            serde_json::to_string(&paths).unwrap()
+        )));
+        let users = &diagram.tables[0];
+        assert!(html.contains(&format!(
+            "\"{}\":{:?}",
+            users.id.full_name(),
+            table_width(users)
+        )));
+    }
+}
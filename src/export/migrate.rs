@@ -0,0 +1,721 @@
+//! Migration scripts from one version of a schema to the next.
+//!
+//! Turns a `diff::SchemaDiff` into DDL for one dialect, reusing the
+//! statements of the SQL exporter. Steps are ordered so that no foreign key
+//! or index is left pointing at something missing: removed foreign keys and
+//! indexes go first, then tables and columns are renamed, created, changed
+//! and dropped, and new indexes and foreign keys come last.
+//!
+//! SQLite cannot change column types or add and drop constraints on an
+//! existing table; those steps come out as comments saying which table has
+//! to be rebuilt by hand.
+
+use std::collections::HashSet;
+
+use super::sql::{Dialect, SqlWriter};
+use crate::diff::{is_key, ColumnChange, IndexChange, SchemaDiff};
+use crate::graph;
+use crate::ir::{Column, Index, Relationship, Table, TableId};
+
+#[derive(Debug, Clone)]
+pub struct Step {
+    pub sql: String,
+    /// What data the step destroys, if any.
+    pub destructive: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Migration {
+    pub steps: Vec<Step>,
+}
+
+impl Migration {
+    pub fn destructive_count(&self) -> usize {
+        self.steps
+            .iter()
+            .filter(|s| s.destructive.is_some())
+            .count()
+    }
+
+    /// The script, destructive steps preceded by a `-- DESTRUCTIVE:` comment.
+    pub fn to_sql(&self) -> String {
+        if self.steps.is_empty() {
+            return "-- No changes\n".to_string();
+        }
+        let statements: Vec<String> = self
+            .steps
+            .iter()
+            .map(|step| match &step.destructive {
+                Some(reason) => format!("-- DESTRUCTIVE: {}\n{}", reason, step.sql),
+                None => step.sql.clone(),
+            })
+            .collect();
+        let mut out = statements.join("\n\n");
+        out.push('\n');
+        out
+    }
+}
+
+pub fn write_migration(changes: &SchemaDiff, dialect: Dialect) -> Migration {
+    MigrationWriter {
+        changes,
+        old: SqlWriter {
+            diagram: changes.old,
+            dialect,
+        },
+        new: SqlWriter {
+            diagram: changes.new,
+            dialect,
+        },
+        steps: Vec::new(),
+    }
+    .write()
+}
+
+struct MigrationWriter<'a> {
+    changes: &'a SchemaDiff<'a>,
+    /// Names as they are before the migration.
+    old: SqlWriter<'a>,
+    /// Names as they are after it.
+    new: SqlWriter<'a>,
+    steps: Vec<Step>,
+}
+
+impl MigrationWriter<'_> {
+    fn write(mut self) -> Migration {
+        let changes = self.changes;
+        let removed: HashSet<&TableId> = changes.removed_tables.iter().collect();
+        let added: HashSet<&TableId> = changes.added_tables.iter().collect();
+
+        // Constraints and indexes that go away, under their old names
+        for &i in &changes.removed_relationships {
+            let rel = &changes.old.relationships[i];
+            // Many-to-many refs have nothing in the database to drop
+            match rel.foreign_key() {
+                Some((child, _)) if removed.contains(&child.table_id) => {}
+                Some((child, _)) => self.drop_foreign_key(rel, &child.table_id),
+                None => {}
+            }
+        }
+        for table in &changes.changed_tables {
+            for change in &table.indexes {
+                if let IndexChange::Removed(index) = change {
+                    self.drop_index(table.old_table, index);
+                }
+            }
+        }
+
+        for (old_id, new_id) in &changes.renamed_tables {
+            self.rename_table(old_id, new_id);
+        }
+        for table in &changes.changed_tables {
+            for change in &table.columns {
+                if let ColumnChange::Modified { old, new } = change {
+                    if old.name != new.name {
+                        self.step(format!(
+                            "ALTER TABLE {} RENAME COLUMN {} TO {};",
+                            self.new.table_ref(&table.id),
+                            self.new.ident(&old.name),
+                            self.new.ident(&new.name)
+                        ));
+                    }
+                }
+            }
+        }
+
+        self.create_types();
+        let order = graph::dependency_order(changes.new);
+        let edges = graph::fk_edges(changes.new);
+        for &ti in &order.tables {
+            let table = &changes.new.tables[ti];
+            if !added.contains(&table.id) {
+                continue;
+            }
+            // SQLite cannot add foreign keys later, so new tables get theirs inline
+            let inline: Vec<_> = edges
+                .iter()
+                .filter(|e| self.new.dialect == Dialect::Sqlite && e.child == ti)
+                .filter(|e| changes.added_relationships.contains(&e.rel))
+                .copied()
+                .collect();
+            self.step(self.new.create_table(table, &inline, &[]));
+        }
+
+        for table in &changes.changed_tables {
+            let (old_table, new_table) = (table.old_table, table.new_table);
+            let key_changed = table.columns.iter().any(|change| {
+                matches!(change, ColumnChange::Modified { old, new }
+                    if is_key(old_table, old) != is_key(new_table, new))
+            });
+            if key_changed {
+                match self.new.dialect {
+                    Dialect::Sqlite => self.rebuild("change the primary key".into(), &table.id),
+                    _ => self.step(format!("-- Change the primary key of {} by hand", table.id)),
+                }
+            }
+            for change in &table.columns {
+                match change {
+                    ColumnChange::Added(col) => self.step(format!(
+                        "ALTER TABLE {} ADD COLUMN {};",
+                        self.new.table_ref(&table.id),
+                        self.new.column_def(new_table, col, false)
+                    )),
+                    ColumnChange::Modified { old, new } => {
+                        self.alter_column(old_table, new_table, old, new)
+                    }
+                    ColumnChange::Removed(_) => {}
+                }
+            }
+        }
+        for table in &changes.changed_tables {
+            for change in &table.columns {
+                if let ColumnChange::Removed(col) = change {
+                    self.destructive(
+                        format!(
+                            "ALTER TABLE {} DROP COLUMN {};",
+                            self.new.table_ref(&table.id),
+                            self.new.ident(&col.name)
+                        ),
+                        format!("drops column {}.{} and its data", table.id, col.name),
+                    );
+                }
+            }
+        }
+
+        // Referencing tables before the tables they reference
+        let old_order = graph::dependency_order(changes.old);
+        for &ti in old_order.tables.iter().rev() {
+            let id = &changes.old.tables[ti].id;
+            if removed.contains(id) {
+                self.destructive(
+                    format!("DROP TABLE {};", self.old.table_ref(id)),
+                    format!("drops table {} and its rows", id),
+                );
+            }
+        }
+
+        for table in &changes.new.tables {
+            if added.contains(&table.id) {
+                for index in &table.indexes {
+                    self.create_index(table, index);
+                }
+            }
+        }
+        for table in &changes.changed_tables {
+            for change in &table.indexes {
+                if let IndexChange::Added(index) = change {
+                    self.create_index(table.new_table, index);
+                }
+            }
+        }
+
+        for &i in &changes.added_relationships {
+            let rel = &changes.new.relationships[i];
+            match rel.foreign_key() {
+                Some((child, _)) => self.add_foreign_key(i, &child.table_id),
+                None => self.step(self.new.many_to_many(rel)),
+            }
+        }
+
+        self.drop_types();
+        Migration { steps: self.steps }
+    }
+
+    fn step(&mut self, sql: String) {
+        self.steps.push(Step {
+            sql,
+            destructive: None,
+        });
+    }
+
+    fn destructive(&mut self, sql: String, reason: String) {
+        self.steps.push(Step {
+            sql,
+            destructive: Some(reason),
+        });
+    }
+
+    /// A step SQLite cannot do in place.
+    fn rebuild(&mut self, what: String, table: &TableId) {
+        self.step(format!(
+            "-- SQLite cannot {}; rebuild table {} to apply it",
+            what, table
+        ));
+    }
+
+    fn drop_foreign_key(&mut self, rel: &Relationship, child: &TableId) {
+        let name = self.old.foreign_key_name(rel);
+        let table = self.old.table_ref(child);
+        match self.old.dialect {
+            Dialect::Postgres => self.step(format!(
+                "ALTER TABLE {} DROP CONSTRAINT {};",
+                table,
+                self.old.ident(&name)
+            )),
+            Dialect::Mysql => self.step(format!(
+                "ALTER TABLE {} DROP FOREIGN KEY {};",
+                table,
+                self.old.ident(&name)
+            )),
+            Dialect::Sqlite => self.rebuild(format!("drop foreign key {}", name), child),
+        }
+    }
+
+    fn add_foreign_key(&mut self, rel: usize, child: &TableId) {
+        let added_table = self.changes.added_tables.contains(child);
+        if self.new.dialect == Dialect::Sqlite {
+            if !added_table {
+                let name = self
+                    .new
+                    .foreign_key_name(&self.changes.new.relationships[rel]);
+                self.rebuild(format!("add foreign key {}", name), child);
+            }
+            return;
+        }
+        if let Some(edge) = graph::fk_edges(self.changes.new)
+            .into_iter()
+            .find(|e| e.rel == rel)
+        {
+            self.step(format!(
+                "ALTER TABLE {} ADD {};",
+                self.new.table_ref(child),
+                self.new.foreign_key(&edge)
+            ));
+        }
+    }
+
+    fn drop_index(&mut self, table: &Table, index: &Index) {
+        if index.is_pk {
+            self.step(format!("-- Drop the primary key of {} by hand", table.id));
+            return;
+        }
+        let name = self.old.index_name(table, index);
+        let sql = match self.old.dialect {
+            Dialect::Postgres => format!(
+                "DROP INDEX {};",
+                self.old.qualified(&table.id.schema, &name)
+            ),
+            Dialect::Mysql => format!(
+                "DROP INDEX {} ON {};",
+                self.old.ident(&name),
+                self.old.table_ref(&table.id)
+            ),
+            Dialect::Sqlite => format!("DROP INDEX {};", self.old.ident(&name)),
+        };
+        self.step(sql);
+    }
+
+    fn create_index(&mut self, table: &Table, index: &Index) {
+        if index.is_pk {
+            // Part of CREATE TABLE for new tables
+            if !self.changes.added_tables.contains(&table.id) {
+                self.step(format!(
+                    "-- Add the new primary key of {} by hand",
+                    table.id
+                ));
+            }
+            return;
+        }
+        self.step(self.new.create_index(table, index));
+    }
+
+    fn rename_table(&mut self, old_id: &TableId, new_id: &TableId) {
+        let old_ref = self.old.table_ref(old_id);
+        match self.new.dialect {
+            Dialect::Postgres => {
+                let mut current = old_ref;
+                if old_id.schema != new_id.schema {
+                    self.step(format!(
+                        "ALTER TABLE {} SET SCHEMA {};",
+                        current,
+                        self.new.ident(&new_id.schema)
+                    ));
+                    current = self.new.qualified(&new_id.schema, &old_id.name);
+                }
+                if old_id.name != new_id.name {
+                    self.step(format!(
+                        "ALTER TABLE {} RENAME TO {};",
+                        current,
+                        self.new.ident(&new_id.name)
+                    ));
+                }
+            }
+            Dialect::Mysql => self.step(format!(
+                "RENAME TABLE {} TO {};",
+                old_ref,
+                self.new.table_ref(new_id)
+            )),
+            Dialect::Sqlite => self.step(format!(
+                "ALTER TABLE {} RENAME TO {};",
+                old_ref,
+                self.new.table_ref(new_id)
+            )),
+        }
+    }
+
+    /// Type, nullability, uniqueness and default changes; renames are done
+    /// earlier. `table` is the new version of `old_table`.
+    fn alter_column(&mut self, old_table: &Table, table: &Table, old: &Column, new: &Column) {
+        let type_changed = old.type_raw != new.type_raw;
+        let null_changed = old_table.is_nullable(&old.name) != table.is_nullable(&new.name);
+        let unique_changed = old.is_unique != new.is_unique;
+        let default_changed = old.default != new.default;
+        if !type_changed && !null_changed && !unique_changed && !default_changed {
+            return;
+        }
+        let table_ref = self.new.table_ref(&table.id);
+        let column = self.new.ident(&new.name);
+        let lossy = type_changed.then(|| {
+            format!(
+                "converts {}.{} from {} to {}, which can fail or lose data",
+                table.id, new.name, old.type_raw, new.type_raw
+            )
+        });
+
+        match self.new.dialect {
+            Dialect::Postgres => {
+                if type_changed {
+                    let sql = format!(
+                        "ALTER TABLE {} ALTER COLUMN {} TYPE {};",
+                        table_ref,
+                        column,
+                        self.new.column_type(table, new)
+                    );
+                    self.steps.push(Step {
+                        sql,
+                        destructive: lossy,
+                    });
+                }
+                if null_changed {
+                    let action = if table.is_nullable(&new.name) {
+                        "DROP NOT NULL"
+                    } else {
+                        "SET NOT NULL"
+                    };
+                    self.step(format!(
+                        "ALTER TABLE {} ALTER COLUMN {} {};",
+                        table_ref, column, action
+                    ));
+                }
+                if default_changed {
+                    self.set_default(&table_ref, &column, new);
+                }
+            }
+            Dialect::Mysql if type_changed || null_changed => {
+                // The column is restated with its default; the primary key
+                // and UNIQUE constraint stay as they are
+                let restated = Column {
+                    is_unique: false,
+                    ..new.clone()
+                };
+                let sql = format!(
+                    "ALTER TABLE {} MODIFY COLUMN {};",
+                    table_ref,
+                    self.new.column_def(table, &restated, false)
+                );
+                self.steps.push(Step {
+                    sql,
+                    destructive: lossy,
+                });
+            }
+            Dialect::Mysql => {
+                if default_changed {
+                    self.set_default(&table_ref, &column, new);
+                }
+            }
+            Dialect::Sqlite => {
+                self.rebuild(format!("alter column {}", new.name), &table.id);
+                return;
+            }
+        }
+
+        if unique_changed && !old.is_unique {
+            self.step(format!(
+                "ALTER TABLE {} ADD CONSTRAINT {} UNIQUE ({});",
+                table_ref,
+                self.new.ident(&self.new.unique_name(table, new)),
+                column
+            ));
+        } else if unique_changed {
+            // Named when the table had its old name, and not renamed since
+            let name = self.old.ident(&self.old.unique_name(old_table, old));
+            let drop = match self.new.dialect {
+                Dialect::Mysql => "DROP INDEX",
+                _ => "DROP CONSTRAINT",
+            };
+            self.step(format!("ALTER TABLE {} {} {};", table_ref, drop, name));
+        }
+    }
+
+    fn set_default(&mut self, table_ref: &str, column: &str, col: &Column) {
+        let action = match &col.default {
+            Some(value) => format!("SET DEFAULT {}", self.new.default_value(value)),
+            None => "DROP DEFAULT".to_string(),
+        };
+        self.step(format!(
+            "ALTER TABLE {} ALTER COLUMN {} {};",
+            table_ref, column, action
+        ));
+    }
+
+    /// Schemas and enum types the new tables need (Postgres).
+    fn create_types(&mut self) {
+        if self.new.dialect == Dialect::Sqlite {
+            return;
+        }
+        let old_schemas: HashSet<&str> = self
+            .changes
+            .old
+            .tables
+            .iter()
+            .map(|t| t.id.schema.as_str())
+            .collect();
+        let mut schemas: Vec<&str> = self
+            .changes
+            .new
+            .tables
+            .iter()
+            .map(|t| t.id.schema.as_str())
+            .filter(|s| *s != "public" && !old_schemas.contains(s))
+            .collect();
+        schemas.sort();
+        schemas.dedup();
+        for schema in schemas {
+            self.step(self.new.create_schema(schema));
+        }
+
+        if self.new.dialect == Dialect::Postgres {
+            for e in &self.changes.new.enums {
+                let exists = self
+                    .changes
+                    .old
+                    .enums
+                    .iter()
+                    .any(|o| o.schema == e.schema && o.name == e.name);
+                if !exists {
+                    self.step(self.new.create_enum(e));
+                }
+            }
+        }
+    }
+
+    /// Enum types no longer in the schema (Postgres).
+    fn drop_types(&mut self) {
+        if self.old.dialect != Dialect::Postgres {
+            return;
+        }
+        for e in &self.changes.old.enums {
+            let kept = self
+                .changes
+                .new
+                .enums
+                .iter()
+                .any(|n| n.schema == e.schema && n.name == e.name);
+            if !kept {
+                self.step(format!(
+                    "DROP TYPE {};",
+                    self.old.qualified(&e.schema, &e.name)
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::{diff_with_renames, Renames};
+    use crate::export::sql::write_sql;
+    use crate::parser::parse_dbml;
+
+    const OLD: &str = r#"Project p {
+  database_type: 'PostgreSQL'
+}
+
+Table users {
+  id int [pk]
+  mail varchar
+  legacy_flag bool
+}
+
+Table posts {
+  id int [pk]
+  user_id int
+  title varchar
+
+  Indexes {
+    title
+  }
+}
+
+Table audit {
+  id int [pk]
+}
+
+Ref: posts.user_id > users.id
+"#;
+
+    const NEW: &str = r#"Project p {
+  database_type: 'PostgreSQL'
+}
+
+Table users {
+  id int [pk]
+  email varchar
+  created_at timestamp
+}
+
+Table articles {
+  id int [pk]
+  user_id int
+  title varchar
+  body text
+
+  Indexes {
+    (user_id, title)
+  }
+}
+
+Table tags {
+  id int [pk]
+  article_id int
+}
+
+Ref: articles.user_id > users.id
+Ref: tags.article_id > articles.id
+"#;
+
+    fn renames() -> Renames {
+        let mut renames = Renames::default();
+        renames.add_table("posts=articles").unwrap();
+        renames.add_column("users.mail=email").unwrap();
+        renames
+    }
+
+    #[test]
+    fn test_sqlite_migration_runs() {
+        let (old, new) = (parse_dbml(OLD).unwrap(), parse_dbml(NEW).unwrap());
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(&write_sql(&old, Dialect::Sqlite))
+            .unwrap();
+
+        let changes = diff_with_renames(&old, &new, &renames()).unwrap();
+        let migration = write_migration(&changes, Dialect::Sqlite);
+        conn.execute_batch(&migration.to_sql()).unwrap();
+        assert_eq!(migration.destructive_count(), 2);
+
+        let names = |sql: &str| -> Vec<String> {
+            conn.prepare(sql)
+                .unwrap()
+                .query_map([], |r| r.get(0))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap()
+        };
+        assert_eq!(
+            names("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name"),
+            ["articles", "tags", "users"]
+        );
+        assert_eq!(
+            names("SELECT name FROM pragma_table_info('users')"),
+            ["id", "email", "created_at"]
+        );
+        assert_eq!(
+            names("SELECT name FROM sqlite_master WHERE type = 'index' AND sql IS NOT NULL"),
+            ["articles_user_id_title_idx"]
+        );
+        assert_eq!(
+            names("SELECT \"table\" FROM pragma_foreign_key_list('tags')"),
+            ["articles"]
+        );
+    }
+
+    #[test]
+    fn test_postgres_steps_and_destructive_flags() {
+        let (old, new) = (parse_dbml(OLD).unwrap(), parse_dbml(NEW).unwrap());
+        let changes = diff_with_renames(&old, &new, &renames()).unwrap();
+        let sql = write_migration(&changes, Dialect::Postgres).to_sql();
+
+        let position = |needle: &str| {
+            sql.find(needle)
+                .unwrap_or_else(|| panic!("{} not in\n{}", needle, sql))
+        };
+        assert!(
+            position("DROP INDEX \"posts_title_idx\";")
+                < position("ALTER TABLE \"posts\" RENAME TO \"articles\";")
+        );
+        position("ALTER TABLE \"users\" RENAME COLUMN \"mail\" TO \"email\";");
+        position("-- DESTRUCTIVE: drops column public.users.legacy_flag and its data\n");
+        position("-- DESTRUCTIVE: drops table public.audit and its rows\nDROP TABLE \"audit\";");
+        assert!(
+            position("CREATE TABLE \"tags\"")
+                < position(
+                    "ALTER TABLE \"tags\" ADD CONSTRAINT \"fk_tags_article_id\" FOREIGN KEY"
+                )
+        );
+        // The foreign key follows the renames and is left alone
+        assert!(!sql.contains("fk_posts_user_id"));
+    }
+
+    #[test]
+    fn test_constraint_changes() {
+        let schema = |email: &str, score: &str, actions: &str| {
+            parse_dbml(&format!(
+                "Project p {{\n  database_type: 'PostgreSQL'\n}}\n\
+                 Table users {{\n  id int [pk]\n  email varchar{}\n  score int{}\n}}\n\
+                 Table posts {{\n  id int [pk]\n  user_id int\n}}\n\
+                 Ref: posts.user_id > users.id{}\n",
+                email, score, actions
+            ))
+            .unwrap()
+        };
+        let old = schema("", " [default: 0]", "");
+        let new = schema(" [unique]", "", " [delete: cascade]");
+        let migrate = |old, new, dialect| {
+            write_migration(
+                &diff_with_renames(old, new, &Renames::default()).unwrap(),
+                dialect,
+            )
+            .to_sql()
+        };
+
+        let sql = migrate(&old, &new, Dialect::Postgres);
+        let position = |needle: &str| {
+            sql.find(needle)
+                .unwrap_or_else(|| panic!("{} not in\n{}", needle, sql))
+        };
+        position("ALTER TABLE \"users\" ADD CONSTRAINT \"users_email_key\" UNIQUE (\"email\");");
+        position("ALTER TABLE \"users\" ALTER COLUMN \"score\" DROP DEFAULT;");
+        assert!(
+            position("ALTER TABLE \"posts\" DROP CONSTRAINT \"fk_posts_user_id\";")
+                < position(
+                    "ALTER TABLE \"posts\" ADD CONSTRAINT \"fk_posts_user_id\" FOREIGN KEY \
+                     (\"user_id\") REFERENCES \"users\" (\"id\") ON DELETE CASCADE;"
+                )
+        );
+
+        // And back again
+        let sql = migrate(&new, &old, Dialect::Mysql);
+        assert!(sql.contains("ALTER TABLE `users` DROP INDEX `email`;"));
+        assert!(sql.contains("ALTER TABLE `users` ALTER COLUMN `score` SET DEFAULT 0;"));
+        assert!(sql.contains("ALTER TABLE `posts` DROP FOREIGN KEY `fk_posts_user_id`;"));
+        assert!(sql.contains(
+            "ALTER TABLE `posts` ADD CONSTRAINT `fk_posts_user_id` FOREIGN KEY (`user_id`) \
+             REFERENCES `users` (`id`);"
+        ));
+    }
+
+    #[test]
+    fn test_undeclared_renames_drop_and_add() {
+        let (old, new) = (parse_dbml(OLD).unwrap(), parse_dbml(NEW).unwrap());
+        let changes = diff_with_renames(&old, &new, &Renames::default()).unwrap();
+        let sql = write_migration(&changes, Dialect::Postgres).to_sql();
+        assert!(sql.contains("DROP TABLE \"posts\";"));
+        assert!(sql.contains("DROP COLUMN \"mail\";"));
+
+        let mut bad = Renames::default();
+        bad.add_table("users=articles").unwrap();
+        assert!(diff_with_renames(&old, &new, &bad).is_err());
+        assert!(bad.add_column("mail=email").is_err());
+    }
+}
@@ -0,0 +1,74 @@
+pub(crate) mod dbml;
+pub(crate) mod drawio;
+pub(crate) mod html;
+pub(crate) mod migrate;
+pub(crate) mod notation;
+pub(crate) mod options;
+pub(crate) mod sql;
+pub(crate) mod style;
+pub(crate) mod svg;
+pub(crate) mod tiles;
+
+use std::path::Path;
+
+use crate::embed::Embedded;
+use crate::error::AppError;
+use crate::ir::Diagram;
+use crate::junction;
+use crate::layout;
+use options::ExportOptions;
+
+/// Output formats supported by `dbml-draw export`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// draw.io (diagrams.net) XML
+    Drawio,
+    /// Standalone SVG image
+    Svg,
+    /// Self-contained read-only HTML viewer
+    Html,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Drawio => "drawio",
+            ExportFormat::Svg => "svg",
+            ExportFormat::Html => "html",
+        }
+    }
+}
+
+/// Export a diagram to `output` in the given format. `options` apply to
+/// the image formats. Collapsed junction tables are drawn as many-to-many
+/// relationships, except in HTML, where the viewer can expand them.
+pub fn export(
+    mut diagram: Diagram,
+    format: ExportFormat,
+    input: &Path,
+    output: &Path,
+    options: &ExportOptions,
+) -> Result<(), AppError> {
+    layout::ensure_positions(&mut diagram);
+
+    let name = input
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let content = match format {
+        ExportFormat::Drawio => {
+            junction::collapse_marked(&mut diagram);
+            drawio::write_drawio(&diagram, &name, options)
+        }
+        ExportFormat::Svg => {
+            // The embedded layout keeps the collapsed junction tables
+            let source = Embedded::for_diagram(&diagram, input)?;
+            junction::collapse_marked(&mut diagram);
+            svg::write_svg(&diagram, &name, options, Some(&source))
+        }
+        ExportFormat::Html => html::write_html(&diagram, &name, options)?,
+    };
+
+    std::fs::write(output, content)?;
+    Ok(())
+}
@@ -0,0 +1,281 @@
+//! Relationship notations: what is drawn where a relationship meets a
+//! table, given the cardinality of that end (see `cardinality::infer`).
+//!
+//! Mirrors `endMarks()` and `legendRows()` in `editor.js`; the SVG and
+//! draw.io exporters and the editor all draw from these marks.
+
+use serde::{Deserialize, Serialize};
+
+use crate::ir::{Cardinality, Position, RelationType, Relationship};
+use crate::layout::metrics::sans_text_width;
+
+/// Legend layout, the same as `drawLegend()` in `editor.js`: a box below
+/// the diagram with a title and one row per mark, each a short line ending
+/// in the mark at a table edge, then its meaning.
+pub const LEGEND_GAP: f64 = 24.0;
+pub const LEGEND_HEADER: f64 = 36.0;
+pub const LEGEND_ROW: f64 = 30.0;
+/// Length of the sample line in each row.
+pub const LEGEND_LINE: f64 = 56.0;
+pub const LEGEND_PADDING: f64 = 16.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Notation {
+    /// Crow's foot (Information Engineering)
+    #[default]
+    Ie,
+    /// UML multiplicities: 1, 0..1, 1..*, 0..*
+    Uml,
+    /// Chen: 1, N or M at the ends and a diamond in the middle
+    Chen,
+    /// IDEF1X: a dot at the referencing end, a diamond at an optional
+    /// referenced end
+    Idef1x,
+    /// (min,max): how many relationships each row takes part in
+    Minmax,
+}
+
+/// Symbol drawn where a relationship meets a table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symbol {
+    None,
+    /// Crow's foot marker
+    Ie(Cardinality),
+    /// Filled dot
+    Dot,
+    /// Hollow diamond
+    Diamond,
+}
+
+/// A symbol and the text next to it at one end of a relationship.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EndMark {
+    pub symbol: Symbol,
+    pub label: Option<&'static str>,
+}
+
+impl EndMark {
+    fn symbol(symbol: Symbol) -> Self {
+        Self {
+            symbol,
+            label: None,
+        }
+    }
+
+    fn label(label: &'static str) -> Self {
+        Self {
+            symbol: Symbol::None,
+            label: Some(label),
+        }
+    }
+}
+
+impl Notation {
+    pub fn title(self) -> &'static str {
+        match self {
+            Notation::Ie => "Crow's foot notation",
+            Notation::Uml => "UML notation",
+            Notation::Chen => "Chen notation",
+            Notation::Idef1x => "IDEF1X notation",
+            Notation::Minmax => "Min/max notation",
+        }
+    }
+
+    /// Marks at the `from` and `to` ends of `rel`.
+    pub fn end_marks(self, rel: &Relationship) -> [EndMark; 2] {
+        use Cardinality::*;
+
+        let [from, to] = rel.cardinality;
+        let many = |c: Cardinality| matches!(c, ManyMandatory | ManyOptional);
+        match self {
+            Notation::Ie => [
+                EndMark::symbol(Symbol::Ie(from)),
+                EndMark::symbol(Symbol::Ie(to)),
+            ],
+            Notation::Uml => [EndMark::label(uml(from)), EndMark::label(uml(to))],
+            // Each end tells how often a row of its own table takes part,
+            // which is the cardinality seen from the other end
+            Notation::Minmax => [EndMark::label(min_max(to)), EndMark::label(min_max(from))],
+            Notation::Chen => match (many(from), many(to)) {
+                (true, true) => [EndMark::label("M"), EndMark::label("N")],
+                (from, to) => [
+                    EndMark::label(if from { "N" } else { "1" }),
+                    EndMark::label(if to { "N" } else { "1" }),
+                ],
+            },
+            Notation::Idef1x => {
+                let referencing = match rel.relation_type {
+                    RelationType::OneToMany => Some(1),
+                    RelationType::ManyToMany => None,
+                    _ => Some(0),
+                };
+                [0, 1].map(|end| {
+                    let cardinality = rel.cardinality[end];
+                    if !matches!(referencing, Some(r) if r != end) {
+                        idef1x_child(cardinality)
+                    } else if cardinality == OneOptional {
+                        EndMark::symbol(Symbol::Diamond)
+                    } else {
+                        EndMark::symbol(Symbol::None)
+                    }
+                })
+            }
+        }
+    }
+
+    /// Width and height of the legend box.
+    pub fn legend_size(self) -> (f64, f64) {
+        let rows = self.legend();
+        let text = rows
+            .iter()
+            .map(|(_, meaning)| sans_text_width(meaning))
+            .fold(sans_text_width(self.title()), f64::max);
+        (
+            2.0 * LEGEND_PADDING + LEGEND_LINE + 12.0 + text,
+            LEGEND_HEADER + rows.len() as f64 * LEGEND_ROW + 8.0,
+        )
+    }
+
+    /// Whether a diamond is drawn halfway along each relationship.
+    pub fn middle_diamond(self) -> bool {
+        self == Notation::Chen
+    }
+
+    /// Rows of the legend: a mark and what it means.
+    pub fn legend(self) -> Vec<(EndMark, &'static str)> {
+        use Cardinality::*;
+
+        const MEANINGS: [(Cardinality, &str); 4] = [
+            (OneMandatory, "exactly one"),
+            (OneOptional, "zero or one"),
+            (ManyMandatory, "one or more"),
+            (ManyOptional, "zero or more"),
+        ];
+        match self {
+            Notation::Ie => MEANINGS
+                .iter()
+                .map(|&(c, meaning)| (EndMark::symbol(Symbol::Ie(c)), meaning))
+                .collect(),
+            Notation::Uml => MEANINGS
+                .iter()
+                .map(|&(c, meaning)| (EndMark::label(uml(c)), meaning))
+                .collect(),
+            Notation::Minmax => vec![
+                (EndMark::label("(1,1)"), "each row is in exactly one"),
+                (EndMark::label("(0,1)"), "each row is in zero or one"),
+                (EndMark::label("(1,N)"), "each row is in one or more"),
+                (EndMark::label("(0,N)"), "each row is in zero or more"),
+            ],
+            Notation::Chen => vec![
+                (EndMark::label("1"), "one"),
+                (EndMark::label("N"), "many"),
+                (EndMark::symbol(Symbol::Diamond), "relationship"),
+            ],
+            Notation::Idef1x => vec![
+                (EndMark::symbol(Symbol::None), "exactly one (referenced)"),
+                (
+                    EndMark::symbol(Symbol::Diamond),
+                    "zero or one (referenced, nullable key)",
+                ),
+                (idef1x_child(ManyOptional), "zero or more"),
+                (idef1x_child(ManyMandatory), "one or more"),
+                (idef1x_child(OneOptional), "zero or one"),
+                (idef1x_child(OneMandatory), "exactly one"),
+            ],
+        }
+    }
+}
+
+/// Where the label of a mark at `at` goes, for a line leaving the table at
+/// `angle`: above a horizontal line or beside a vertical one. Also returns
+/// whether the text is centered on that point rather than starting there.
+pub fn label_position(at: Position, angle: f64) -> (Position, bool) {
+    let (dx, dy) = (angle.cos(), angle.sin());
+    if dx.abs() > dy.abs() {
+        (
+            Position {
+                x: at.x + dx * 18.0,
+                y: at.y - 10.0,
+            },
+            true,
+        )
+    } else {
+        (
+            Position {
+                x: at.x + 8.0,
+                y: at.y + dy * 18.0,
+            },
+            false,
+        )
+    }
+}
+
+fn uml(cardinality: Cardinality) -> &'static str {
+    match cardinality {
+        Cardinality::OneMandatory => "1",
+        Cardinality::OneOptional => "0..1",
+        Cardinality::ManyMandatory => "1..*",
+        Cardinality::ManyOptional => "0..*",
+    }
+}
+
+fn min_max(cardinality: Cardinality) -> &'static str {
+    match cardinality {
+        Cardinality::OneMandatory => "(1,1)",
+        Cardinality::OneOptional => "(0,1)",
+        Cardinality::ManyMandatory => "(1,N)",
+        Cardinality::ManyOptional => "(0,N)",
+    }
+}
+
+/// The dot at the referencing end, lettered unless it means zero or more.
+fn idef1x_child(cardinality: Cardinality) -> EndMark {
+    EndMark {
+        symbol: Symbol::Dot,
+        label: match cardinality {
+            Cardinality::OneMandatory => Some("1"),
+            Cardinality::OneOptional => Some("Z"),
+            Cardinality::ManyMandatory => Some("P"),
+            Cardinality::ManyOptional => None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_dbml;
+
+    #[test]
+    fn test_end_marks() {
+        let diagram = parse_dbml(
+            "Project p {\n  database_type: 'PostgreSQL'\n}\n\
+             Table users {\n  id int [pk]\n}\n\
+             Table posts {\n  id int [pk]\n  author_id int [not null, ref: > users.id]\n\
+               editor_id int [ref: > users.id]\n}\n",
+        )
+        .unwrap();
+        let [author, editor] = [&diagram.relationships[0], &diagram.relationships[1]];
+        let labels = |notation: Notation, rel| notation.end_marks(rel).map(|m| m.label);
+
+        assert_eq!(labels(Notation::Uml, author), [Some("0..*"), Some("1")]);
+        assert_eq!(labels(Notation::Uml, editor), [Some("0..*"), Some("0..1")]);
+        // Each post has exactly one author, a user any number of posts
+        assert_eq!(
+            labels(Notation::Minmax, author),
+            [Some("(1,1)"), Some("(0,N)")]
+        );
+        assert_eq!(labels(Notation::Chen, author), [Some("N"), Some("1")]);
+        assert_eq!(
+            Notation::Idef1x.end_marks(editor),
+            [
+                EndMark::symbol(Symbol::Dot),
+                EndMark::symbol(Symbol::Diamond)
+            ]
+        );
+        assert_eq!(Notation::Idef1x.end_marks(author)[1].symbol, Symbol::None);
+        assert!(Notation::Chen.middle_diamond());
+        assert_eq!(Notation::Ie.legend().len(), 4);
+    }
+}
@@ -0,0 +1,150 @@
+//! Options shared by the image exports: the SVG exporter and the editor's
+//! PNG export.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::notation::Notation;
+use crate::error::AppError;
+
+pub const MAX_SCALE: f64 = 8.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExportOptions {
+    /// Pixels per diagram unit (2 is 192 DPI on a 96 DPI display).
+    pub scale: f64,
+    /// Empty space around the diagram.
+    pub padding: f64,
+    /// Leave out the background fill.
+    pub transparent: bool,
+    /// Draw the diagram name and table count above the diagram.
+    pub title: bool,
+    /// How relationship ends are drawn.
+    pub notation: Notation,
+    /// Draw a legend of the notation's symbols below the diagram.
+    pub legend: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            scale: 2.0,
+            padding: 50.0,
+            transparent: false,
+            title: false,
+            notation: Notation::default(),
+            legend: false,
+        }
+    }
+}
+
+impl ExportOptions {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if !(self.scale > 0.0 && self.scale <= MAX_SCALE) {
+            return Err(AppError::InvalidExportOption(format!(
+                "scale must be greater than 0 and at most {}, got {}",
+                MAX_SCALE, self.scale
+            )));
+        }
+        if !(self.padding >= 0.0 && self.padding.is_finite()) {
+            return Err(AppError::InvalidExportOption(format!(
+                "padding must not be negative, got {}",
+                self.padding
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Resolve an export path given in the editor: relative paths are taken
+/// from `base_dir` and the extension must be `extension`. The directory is
+/// created when the export is written.
+pub fn resolve_output_path(
+    base_dir: &Path,
+    path: &str,
+    extension: &str,
+) -> Result<PathBuf, AppError> {
+    let invalid = |reason: &str| AppError::InvalidExportPath(format!("{}: {}", path, reason));
+
+    let path_buf = PathBuf::from(path.trim());
+    if path_buf.as_os_str().is_empty() {
+        return Err(AppError::InvalidExportPath("no path given".into()));
+    }
+    let resolved = if path_buf.is_absolute() {
+        path_buf
+    } else {
+        base_dir.join(path_buf)
+    };
+
+    if !resolved
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case(extension))
+    {
+        return Err(invalid(&format!("file name must end in .{}", extension)));
+    }
+    if resolved.is_dir() {
+        return Err(invalid("is a directory"));
+    }
+    Ok(resolved)
+}
+
+/// Create the directory an export goes in, such as a configured
+/// `output_dir` on its first export.
+pub fn create_output_dir(output: &Path) -> Result<(), AppError> {
+    match output.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => std::fs::create_dir_all(dir).map_err(|e| {
+            AppError::InvalidExportPath(format!("directory {}: {}", dir.display(), e))
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(ExportOptions::default().validate().is_ok());
+        let bad_scale = ExportOptions {
+            scale: 0.0,
+            ..Default::default()
+        };
+        assert!(bad_scale.validate().is_err());
+        let bad_padding = ExportOptions {
+            padding: -1.0,
+            ..Default::default()
+        };
+        assert!(bad_padding.validate().is_err());
+    }
+
+    #[test]
+    fn test_resolve_output_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path();
+        std::fs::create_dir(base.join("out")).unwrap();
+
+        assert_eq!(
+            resolve_output_path(base, "out/a.PNG", "png").unwrap(),
+            base.join("out/a.PNG")
+        );
+        assert!(resolve_output_path(base, "", "png").is_err());
+        assert!(resolve_output_path(base, "out/a.jpg", "png").is_err());
+        std::fs::create_dir(base.join("dir.png")).unwrap();
+        assert!(resolve_output_path(base, "dir.png", "png").is_err());
+
+        // A missing directory is created on export, and named if it cannot be
+        let missing = resolve_output_path(base, "missing/a.png", "png").unwrap();
+        create_output_dir(&missing).unwrap();
+        assert!(base.join("missing").is_dir());
+        std::fs::write(base.join("file"), "").unwrap();
+        let blocked = resolve_output_path(base, "file/a.png", "png").unwrap();
+        let err = create_output_dir(&blocked).unwrap_err().to_string();
+        assert!(err.starts_with(&format!(
+            "Invalid export path: directory {}: ",
+            base.join("file").display()
+        )));
+    }
+}
@@ -0,0 +1,598 @@
+//! SQL DDL generation for PostgreSQL, MySQL and SQLite.
+//!
+//! Tables are created in foreign key dependency order (see
+//! `graph::dependency_order`). Foreign keys that close a cycle are added
+//! afterwards with `ALTER TABLE`, except on SQLite, which cannot add
+//! constraints later but also does not check the referenced table exists at
+//! `CREATE TABLE` time, so every foreign key stays inline there.
+//!
+//! Those foreign keys are also `DEFERRABLE INITIALLY DEFERRED` on PostgreSQL
+//! and SQLite, so the rows of a cycle can be inserted in one transaction.
+//! MySQL cannot defer constraint checks; rows in a cycle have to be inserted
+//! with `SET FOREIGN_KEY_CHECKS = 0`.
+
+use crate::graph::{self, FkEdge};
+use crate::ir::{
+    Column, DefaultValue, Diagram, EndPoint, Enum, Index, IndexColumn, RelationType, Relationship,
+    Table, TableId,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Dialect {
+    Postgres,
+    Mysql,
+    Sqlite,
+}
+
+pub fn write_sql(diagram: &Diagram, dialect: Dialect) -> String {
+    SqlWriter { diagram, dialect }.write()
+}
+
+/// Statements for the tables of one diagram; also used by `migrate`.
+pub(super) struct SqlWriter<'a> {
+    pub diagram: &'a Diagram,
+    pub dialect: Dialect,
+}
+
+impl SqlWriter<'_> {
+    fn write(&self) -> String {
+        let mut statements: Vec<String> = Vec::new();
+        let order = graph::dependency_order(self.diagram);
+        let edges = graph::fk_edges(self.diagram);
+        let inline_fks = |ti: usize| -> Vec<FkEdge> {
+            edges
+                .iter()
+                .filter(|e| e.child == ti)
+                .filter(|e| self.dialect == Dialect::Sqlite || !order.deferred.contains(e))
+                .copied()
+                .collect()
+        };
+
+        if self.dialect != Dialect::Sqlite {
+            let mut schemas: Vec<&str> = self
+                .diagram
+                .tables
+                .iter()
+                .map(|t| t.id.schema.as_str())
+                .chain(self.diagram.enums.iter().map(|e| e.schema.as_str()))
+                .filter(|s| *s != "public")
+                .collect();
+            schemas.sort();
+            schemas.dedup();
+            for schema in schemas {
+                statements.push(self.create_schema(schema));
+            }
+        }
+
+        if self.dialect == Dialect::Postgres {
+            for e in &self.diagram.enums {
+                statements.push(self.create_enum(e));
+            }
+        }
+
+        for &ti in &order.tables {
+            statements.push(self.create_table(
+                &self.diagram.tables[ti],
+                &inline_fks(ti),
+                &order.deferred,
+            ));
+        }
+
+        for rel in &self.diagram.relationships {
+            if rel.relation_type == RelationType::ManyToMany {
+                statements.push(self.many_to_many(rel));
+            }
+        }
+
+        if self.dialect != Dialect::Sqlite {
+            for e in &order.deferred {
+                statements.push(format!(
+                    "ALTER TABLE {} ADD {}{};",
+                    self.table_ref(&self.diagram.tables[e.child].id),
+                    self.foreign_key(e),
+                    self.deferrable()
+                ));
+            }
+        }
+
+        for table in &self.diagram.tables {
+            for index in table.indexes.iter().filter(|index| !index.is_pk) {
+                statements.push(self.create_index(table, index));
+            }
+        }
+
+        if self.dialect == Dialect::Postgres {
+            statements.extend(self.comments());
+        }
+
+        let mut out = statements.join("\n\n");
+        out.push('\n');
+        out
+    }
+
+    pub fn create_schema(&self, schema: &str) -> String {
+        format!("CREATE SCHEMA IF NOT EXISTS {};", self.ident(schema))
+    }
+
+    pub fn create_enum(&self, e: &Enum) -> String {
+        let values: Vec<String> = e.values.iter().map(|v| literal(&v.name)).collect();
+        format!(
+            "CREATE TYPE {} AS ENUM ({});",
+            self.qualified(&e.schema, &e.name),
+            values.join(", ")
+        )
+    }
+
+    /// `fks` are the foreign keys declared inside the table, those among
+    /// `deferred` checked at the end of the transaction.
+    pub fn create_table(&self, table: &Table, fks: &[FkEdge], deferred: &[FkEdge]) -> String {
+        let pk_columns = table.primary_key();
+        let mut lines: Vec<String> = table
+            .columns
+            .iter()
+            .map(|c| {
+                format!(
+                    "  {}",
+                    self.column_def(table, c, pk_columns == [c.name.as_str()])
+                )
+            })
+            .collect();
+
+        if pk_columns.len() > 1 {
+            lines.push(format!("  PRIMARY KEY ({})", self.ident_list(&pk_columns)));
+        }
+        for e in fks {
+            let deferrable = if deferred.contains(e) {
+                self.deferrable()
+            } else {
+                ""
+            };
+            lines.push(format!("  {}{}", self.foreign_key(e), deferrable));
+        }
+
+        let mut out = format!(
+            "CREATE TABLE {} (\n{}\n)",
+            self.table_ref(&table.id),
+            lines.join(",\n")
+        );
+        if let (Dialect::Mysql, Some(note)) = (self.dialect, &table.note) {
+            out.push_str(&format!(" COMMENT={}", literal(note)));
+        }
+        out.push(';');
+        out
+    }
+
+    pub fn column_def(&self, table: &Table, col: &Column, inline_pk: bool) -> String {
+        let mut def = format!("{} {}", self.ident(&col.name), self.column_type(table, col));
+
+        if col.is_increment {
+            match self.dialect {
+                Dialect::Postgres => def.push_str(" GENERATED BY DEFAULT AS IDENTITY"),
+                Dialect::Mysql => def.push_str(" AUTO_INCREMENT"),
+                // Only an INTEGER PRIMARY KEY can autoincrement
+                Dialect::Sqlite if inline_pk => {
+                    def = format!("{} INTEGER", self.ident(&col.name));
+                }
+                Dialect::Sqlite => {}
+            }
+        }
+        if inline_pk {
+            def.push_str(" PRIMARY KEY");
+            if self.dialect == Dialect::Sqlite && col.is_increment {
+                def.push_str(" AUTOINCREMENT");
+            }
+        } else if !table.is_nullable(&col.name) {
+            def.push_str(" NOT NULL");
+        }
+        if col.is_unique {
+            def.push_str(" UNIQUE");
+        }
+        if let Some(default) = &col.default {
+            def.push_str(&format!(" DEFAULT {}", self.default_value(default)));
+        }
+        if self.dialect == Dialect::Sqlite {
+            if let Some(e) = self.find_enum(table, &col.type_raw) {
+                let values: Vec<String> = e.values.iter().map(|v| literal(&v.name)).collect();
+                def.push_str(&format!(
+                    " CHECK ({} IN ({}))",
+                    self.ident(&col.name),
+                    values.join(", ")
+                ));
+            }
+        }
+        if let (Dialect::Mysql, Some(note)) = (self.dialect, &col.note) {
+            def.push_str(&format!(" COMMENT {}", literal(note)));
+        }
+        def
+    }
+
+    pub fn column_type(&self, table: &Table, col: &Column) -> String {
+        if let Some(e) = self.find_enum(table, &col.type_raw) {
+            return match self.dialect {
+                Dialect::Postgres => self.qualified(&e.schema, &e.name),
+                Dialect::Mysql => {
+                    let values: Vec<String> = e.values.iter().map(|v| literal(&v.name)).collect();
+                    format!("ENUM({})", values.join(", "))
+                }
+                Dialect::Sqlite => "TEXT".to_string(),
+            };
+        }
+        // DBML quotes types that contain spaces
+        let type_raw = col.type_raw.trim_matches('"');
+        // MySQL has no VARCHAR without a length
+        if self.dialect == Dialect::Mysql && type_raw.eq_ignore_ascii_case("varchar") {
+            return "VARCHAR(255)".to_string();
+        }
+        type_raw.to_string()
+    }
+
+    /// The enum a column type names. A name without a schema is looked up
+    /// in the schema of the table, then in `public`.
+    fn find_enum(&self, table: &Table, type_raw: &str) -> Option<&Enum> {
+        let find = |schema: &str, name: &str| {
+            self.diagram
+                .enums
+                .iter()
+                .find(|e| e.schema == schema && e.name == name)
+        };
+        match type_raw.split_once('.') {
+            Some((schema, name)) => find(schema.trim_matches('"'), name.trim_matches('"')),
+            None => {
+                let name = type_raw.trim_matches('"');
+                find(&table.id.schema, name).or_else(|| find("public", name))
+            }
+        }
+    }
+
+    pub fn default_value(&self, value: &DefaultValue) -> String {
+        match value {
+            DefaultValue::String(s) | DefaultValue::Identifier(s) => literal(s),
+            DefaultValue::Number(n) => n.clone(),
+            DefaultValue::Bool(true) => "TRUE".to_string(),
+            DefaultValue::Bool(false) => "FALSE".to_string(),
+            DefaultValue::Null => "NULL".to_string(),
+            // Expressions need parentheses in MySQL and SQLite defaults
+            DefaultValue::Expression(e) => match self.dialect {
+                Dialect::Postgres => e.clone(),
+                Dialect::Mysql | Dialect::Sqlite => format!("({})", e),
+            },
+        }
+    }
+
+    /// Name the database gives the UNIQUE constraint declared on a column.
+    pub fn unique_name(&self, table: &Table, col: &Column) -> String {
+        match self.dialect {
+            Dialect::Postgres => format!("{}_{}_key", table.id.name, col.name),
+            // MySQL names the index after the column; SQLite cannot drop it
+            Dialect::Mysql | Dialect::Sqlite => col.name.clone(),
+        }
+    }
+
+    /// Constraint name of a foreign key: its `Ref` name or one made up
+    /// from the referencing table and columns.
+    pub fn foreign_key_name(&self, rel: &Relationship) -> String {
+        let (child, _) = rel
+            .foreign_key()
+            .expect("only foreign keys have constraint names");
+        rel.name.clone().unwrap_or_else(|| {
+            format!(
+                "fk_{}_{}",
+                child.table_id.name,
+                child.column_names.join("_")
+            )
+        })
+    }
+
+    pub fn foreign_key(&self, e: &FkEdge) -> String {
+        let rel = &self.diagram.relationships[e.rel];
+        let (child, parent) = rel
+            .foreign_key()
+            .expect("FkEdge always refers to a foreign key");
+
+        let mut out = format!(
+            "CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({})",
+            self.ident(&self.foreign_key_name(rel)),
+            self.ident_list(&child.column_names),
+            self.table_ref(&parent.table_id),
+            self.ident_list(&parent.column_names)
+        );
+        if let Some(action) = rel.on_delete {
+            out.push_str(&format!(" ON DELETE {}", action.keyword().to_uppercase()));
+        }
+        if let Some(action) = rel.on_update {
+            out.push_str(&format!(" ON UPDATE {}", action.keyword().to_uppercase()));
+        }
+        out
+    }
+
+    /// Clause that checks a foreign key at commit instead of per statement,
+    /// where the dialect has one.
+    fn deferrable(&self) -> &'static str {
+        match self.dialect {
+            Dialect::Postgres | Dialect::Sqlite => " DEFERRABLE INITIALLY DEFERRED",
+            Dialect::Mysql => "",
+        }
+    }
+
+    /// A many-to-many relationship has no columns of its own to hold it,
+    /// so it is only noted; the junction table is left to the schema.
+    pub fn many_to_many(&self, rel: &Relationship) -> String {
+        let end = |ep: &EndPoint| format!("{}.({})", ep.table_id, ep.column_names.join(", "));
+        format!(
+            "-- {} <> {} is many-to-many; it needs a junction table, which is not generated",
+            end(&rel.from),
+            end(&rel.to)
+        )
+    }
+
+    /// Index name: its own or one made up from the table and columns.
+    pub fn index_name(&self, table: &Table, index: &Index) -> String {
+        index.name.clone().unwrap_or_else(|| {
+            let parts: Vec<&str> = index
+                .columns
+                .iter()
+                .map(|c| match c {
+                    IndexColumn::Column(name) => name.as_str(),
+                    IndexColumn::Expression(_) => "expr",
+                })
+                .collect();
+            format!("{}_{}_idx", table.id.name, parts.join("_"))
+        })
+    }
+
+    pub fn create_index(&self, table: &Table, index: &Index) -> String {
+        let cols: Vec<String> = index
+            .columns
+            .iter()
+            .map(|c| match c {
+                IndexColumn::Column(name) => self.ident(name),
+                IndexColumn::Expression(expr) => format!("({})", expr),
+            })
+            .collect();
+
+        let method = index.index_type.as_deref();
+        format!(
+            "CREATE {}INDEX {} ON {}{} ({}){};",
+            if index.is_unique { "UNIQUE " } else { "" },
+            self.ident(&self.index_name(table, index)),
+            self.table_ref(&table.id),
+            match (self.dialect, method) {
+                (Dialect::Postgres, Some(m)) => format!(" USING {}", m),
+                _ => String::new(),
+            },
+            cols.join(", "),
+            match (self.dialect, method) {
+                (Dialect::Mysql, Some(m @ ("btree" | "hash"))) => {
+                    format!(" USING {}", m.to_uppercase())
+                }
+                _ => String::new(),
+            },
+        )
+    }
+
+    fn comments(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        for table in &self.diagram.tables {
+            if let Some(note) = &table.note {
+                out.push(format!(
+                    "COMMENT ON TABLE {} IS {};",
+                    self.table_ref(&table.id),
+                    literal(note)
+                ));
+            }
+            for col in &table.columns {
+                if let Some(note) = &col.note {
+                    out.push(format!(
+                        "COMMENT ON COLUMN {}.{} IS {};",
+                        self.table_ref(&table.id),
+                        self.ident(&col.name),
+                        literal(note)
+                    ));
+                }
+            }
+        }
+        out
+    }
+
+    pub fn ident(&self, name: &str) -> String {
+        match self.dialect {
+            Dialect::Mysql => format!("`{}`", name.replace('`', "``")),
+            Dialect::Postgres | Dialect::Sqlite => format!("\"{}\"", name.replace('"', "\"\"")),
+        }
+    }
+
+    fn ident_list(&self, names: &[impl AsRef<str>]) -> String {
+        names
+            .iter()
+            .map(|n| self.ident(n.as_ref()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Schema-qualified name; SQLite has no schemas, so the schema becomes a prefix.
+    pub fn qualified(&self, schema: &str, name: &str) -> String {
+        match (self.dialect, schema) {
+            (_, "public") => self.ident(name),
+            (Dialect::Sqlite, _) => self.ident(&format!("{}_{}", schema, name)),
+            _ => format!("{}.{}", self.ident(schema), self.ident(name)),
+        }
+    }
+
+    pub fn table_ref(&self, id: &TableId) -> String {
+        self.qualified(&id.schema, &id.name)
+    }
+}
+
+fn literal(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_dbml;
+
+    const SHOP: &str = r#"Project shop {
+  database_type: 'PostgreSQL'
+}
+
+Enum order_status {
+  pending
+  shipped
+}
+
+Table customers {
+  id int [pk, increment]
+  email varchar [unique, not null, note: 'login']
+  best_order_id int
+}
+
+Table orders {
+  id int [pk, increment]
+  customer_id int [not null]
+  status order_status [not null, default: 'pending']
+  placed_at timestamp [default: `CURRENT_TIMESTAMP`]
+
+  Indexes {
+    (customer_id, placed_at) [name: 'orders_by_customer']
+  }
+}
+
+Table tags {
+  id int [pk]
+}
+
+Ref: orders.customer_id > customers.id [delete: cascade]
+Ref: customers.best_order_id > orders.id [delete: set null]
+Ref: orders.id <> tags.id
+"#;
+
+    #[test]
+    fn test_sqlite_output_runs() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        let sql = write_sql(&parse_dbml(SHOP).unwrap(), Dialect::Sqlite);
+        conn.execute_batch(&sql).unwrap();
+
+        let mut stmt = conn
+            .prepare("SELECT \"table\", \"from\", \"to\", on_delete FROM pragma_foreign_key_list('orders')")
+            .unwrap();
+        let fks: Vec<(String, String, String, String)> = stmt
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            fks,
+            vec![(
+                "customers".to_string(),
+                "customer_id".to_string(),
+                "id".to_string(),
+                "CASCADE".to_string()
+            )]
+        );
+
+        // Enum values are enforced with a CHECK constraint
+        conn.execute("INSERT INTO customers (email) VALUES ('a@example.com')", [])
+            .unwrap();
+        assert!(conn
+            .execute(
+                "INSERT INTO orders (customer_id, status) VALUES (1, 'lost')",
+                []
+            )
+            .is_err());
+        conn.execute("INSERT INTO orders (customer_id) VALUES (1)", [])
+            .unwrap();
+
+        // The index exists; the many-to-many ref gets no made-up table
+        let names: Vec<String> = conn
+            .prepare("SELECT name FROM sqlite_master WHERE name IN ('orders_tags', 'orders_by_customer')")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(names, ["orders_by_customer"]);
+        assert!(sql.contains(
+            "-- public.orders.(id) <> public.tags.(id) is many-to-many; \
+             it needs a junction table, which is not generated"
+        ));
+    }
+
+    #[test]
+    fn test_sqlite_defers_cyclic_foreign_keys() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        let sql = write_sql(&parse_dbml(SHOP).unwrap(), Dialect::Sqlite);
+        conn.execute_batch(&sql).unwrap();
+
+        // A customer and their best order reference each other
+        conn.execute_batch(
+            "BEGIN;\n\
+             INSERT INTO customers (id, email, best_order_id) VALUES (1, 'a@example.com', 7);\n\
+             INSERT INTO orders (id, customer_id) VALUES (7, 1);\n\
+             COMMIT;",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_basic_sqlite_output_runs() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        let diagram = parse_dbml(include_str!("../../tests/fixtures/basic.dbml")).unwrap();
+        conn.execute_batch(&write_sql(&diagram, Dialect::Sqlite))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_postgres_defers_cyclic_foreign_keys() {
+        let sql = write_sql(&parse_dbml(SHOP).unwrap(), Dialect::Postgres);
+        assert!(sql.starts_with("CREATE TYPE \"order_status\" AS ENUM ('pending', 'shipped');"));
+        assert!(sql.contains("\"id\" int GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY"));
+
+        // customers is declared first, so its FK to orders is added afterwards
+        let customers = sql.find("CREATE TABLE \"customers\"").unwrap();
+        let orders = sql.find("CREATE TABLE \"orders\"").unwrap();
+        assert!(customers < orders);
+        assert!(sql.contains(
+            "ALTER TABLE \"customers\" ADD CONSTRAINT \"fk_customers_best_order_id\" \
+             FOREIGN KEY (\"best_order_id\") REFERENCES \"orders\" (\"id\") ON DELETE SET NULL \
+             DEFERRABLE INITIALLY DEFERRED;"
+        ));
+        assert!(sql.contains("ON DELETE CASCADE\n);"));
+        assert!(sql.contains("\"email\" varchar NOT NULL UNIQUE"));
+        assert!(sql.contains("COMMENT ON COLUMN \"customers\".\"email\" IS 'login';"));
+    }
+
+    #[test]
+    fn test_enums_resolve_in_the_table_schema() {
+        let input = r#"Project p {
+  database_type: 'PostgreSQL'
+}
+Enum status {
+  active
+}
+Enum billing.status {
+  paid
+}
+Table billing.invoices {
+  state status
+}
+Table accounts {
+  state status
+  invoice_state billing.status
+}
+"#;
+        let sql = write_sql(&parse_dbml(input).unwrap(), Dialect::Mysql);
+        assert!(sql.contains("CREATE TABLE `billing`.`invoices` (\n  `state` ENUM('paid')\n)"));
+        assert!(sql.contains("  `state` ENUM('active'),\n  `invoice_state` ENUM('paid')\n"));
+    }
+
+    #[test]
+    fn test_mysql_inlines_enums() {
+        let sql = write_sql(&parse_dbml(SHOP).unwrap(), Dialect::Mysql);
+        assert!(sql.contains("`status` ENUM('pending', 'shipped') NOT NULL DEFAULT 'pending'"));
+        assert!(sql.contains("`id` int AUTO_INCREMENT PRIMARY KEY"));
+        assert!(sql.contains("`email` VARCHAR(255) NOT NULL UNIQUE COMMENT 'login'"));
+        assert!(sql.contains("DEFAULT (CURRENT_TIMESTAMP)"));
+    }
+}
@@ -0,0 +1,84 @@
+//! Palette shared by the exporters.
+//!
+//! Mirrors the constants in `editor.js` so exported diagrams look like the
+//! editor.
+
+use crate::ir::{Diagram, Table};
+
+pub const HEADER_BG: &str = "#1e293b";
+pub const HEADER_TEXT: &str = "#f1f5f9";
+pub const TABLE_BG: &str = "#ffffff";
+pub const TABLE_BORDER: &str = "#e2e8f0";
+pub const COLUMN_TEXT: &str = "#334155";
+pub const TYPE_TEXT: &str = "#94a3b8";
+pub const PK_COLOR: &str = "#6366f1";
+pub const ROW_SEPARATOR: &str = "#f1f5f9";
+pub const RELATION_STROKE: &str = "#94a3b8";
+pub const RELATION_STROKE_WIDTH: f64 = 1.5;
+pub const CANVAS_BG: &str = "#f8fafc";
+/// Header of a stub for a table hidden by a filter; its outline is dashed.
+pub const STUB_HEADER_BG: &str = "#cbd5e1";
+pub const STUB_HEADER_TEXT: &str = "#334155";
+pub const STUB_DASH: &str = "6 4";
+pub const ADDED_COLOR: &str = "#16a34a";
+pub const REMOVED_COLOR: &str = "#dc2626";
+pub const MODIFIED_COLOR: &str = "#d97706";
+
+pub const FONT_MONO: &str = "\"SF Mono\", Menlo, Monaco, \"Cascadia Code\", monospace";
+pub const FONT_SANS: &str =
+    "-apple-system, BlinkMacSystemFont, \"Segoe UI\", system-ui, sans-serif";
+
+/// Header background and text color of a table.
+pub fn header_colors(table: &Table) -> (&'static str, &'static str) {
+    if table.is_stub {
+        (STUB_HEADER_BG, STUB_HEADER_TEXT)
+    } else {
+        (HEADER_BG, HEADER_TEXT)
+    }
+}
+
+/// Height of the title block above an exported diagram.
+pub const TITLE_HEIGHT: f64 = 56.0;
+
+/// Title and subtitle of the title block: the project name (or `name`)
+/// and the table and relationship counts.
+pub fn title_text(diagram: &Diagram, name: &str) -> (String, String) {
+    let title = diagram
+        .project
+        .as_ref()
+        .map(|p| p.name.clone())
+        .unwrap_or_else(|| name.to_string());
+    let count = |n: usize, what: &str| format!("{} {}{}", n, what, if n == 1 { "" } else { "s" });
+    let subtitle = format!(
+        "{}, {}",
+        count(diagram.tables.len(), "table"),
+        count(diagram.relationships.len(), "relationship")
+    );
+    (title, subtitle)
+}
+
+pub fn escape_xml(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\n' => out.push_str("&#10;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(escape_xml("a<b & \"c\""), "a&lt;b &amp; &quot;c&quot;");
+    }
+}
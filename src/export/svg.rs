@@ -0,0 +1,610 @@
+//! SVG exporter.
+//!
+//! Draws the diagram the way the editor's canvas does, with the paths of
+//! `layout::router` (`drawTable()`,
+//! `drawRelationships()`, `drawEndMark()` and `drawLegend()` in
+//! `editor.js`), so the result can be embedded in documentation without a
+//! browser.
+
+use super::notation::{
+    label_position, EndMark, Notation, Symbol, LEGEND_GAP, LEGEND_HEADER, LEGEND_LINE,
+    LEGEND_PADDING, LEGEND_ROW,
+};
+use super::options::ExportOptions;
+use super::style::{
+    escape_xml, CANVAS_BG, COLUMN_TEXT, FONT_MONO, FONT_SANS, HEADER_BG, PK_COLOR, RELATION_STROKE,
+    RELATION_STROKE_WIDTH, ROW_SEPARATOR, TABLE_BG, TABLE_BORDER, TYPE_TEXT,
+};
+use super::style::{header_colors, title_text, STUB_DASH, TITLE_HEIGHT};
+use crate::embed::{self, Embedded};
+use crate::ir::{Cardinality, Diagram, Position, Table, TableId};
+use crate::layout::metrics::{
+    diagram_bounds, sans_text_width, table_height, table_width, HEADER_HEIGHT, PADDING_X,
+    ROW_HEIGHT,
+};
+use crate::layout::router::{path_middle, Path, Router};
+use crate::layout::routing::{column_label, Route, Side};
+use std::collections::HashMap;
+
+const BORDER_RADIUS: f64 = 8.0;
+const FONT_SIZE: f64 = 13.0;
+const PK_BADGE_WIDTH: f64 = 26.0;
+const PK_BADGE_HEIGHT: f64 = 18.0;
+
+/// Colors that single out tables, columns and relationships, e.g. the
+/// changes in a diff.
+#[derive(Debug, Clone, Default)]
+pub struct Highlights {
+    /// Outline color of a table.
+    pub tables: HashMap<TableId, &'static str>,
+    /// Row tint of a column, by table and column name.
+    pub columns: HashMap<(TableId, String), &'static str>,
+    /// Stroke color of a relationship, by index into `Diagram::relationships`.
+    pub relationships: HashMap<usize, &'static str>,
+}
+
+/// Render a positioned diagram as a standalone SVG document.
+///
+/// Tables must already have positions (see `layout::ensure_positions`).
+/// `name` is the title when the diagram has no project name. `source` is
+/// embedded in the `<metadata>` element so the image can be opened again.
+pub fn write_svg(
+    diagram: &Diagram,
+    name: &str,
+    options: &ExportOptions,
+    source: Option<&Embedded>,
+) -> String {
+    write_highlighted_svg(diagram, name, options, source, &Highlights::default())
+}
+
+/// [`write_svg`] with some elements drawn in the colors of `highlights`.
+pub fn write_highlighted_svg(
+    diagram: &Diagram,
+    name: &str,
+    options: &ExportOptions,
+    source: Option<&Embedded>,
+    highlights: &Highlights,
+) -> String {
+    let (mut min_x, min_y, mut max_x, max_y) =
+        diagram_bounds(diagram).unwrap_or((0.0, 0.0, 0.0, 0.0));
+    let router = Router::for_diagram(diagram);
+    // Self-loops and their labels stick out to the side of their table
+    for ((rel, route), path) in diagram
+        .relationships
+        .iter()
+        .zip(router.routes())
+        .zip(router.paths())
+    {
+        if let Some(route) = route.filter(Route::is_self_loop) {
+            let text = column_label(rel);
+            let at = route_label_position(&route, path.as_ref(), &text);
+            let half = label_width(&text) / 2.0;
+            min_x = min_x.min(at.x - half);
+            max_x = max_x.max(at.x + half);
+        }
+    }
+    let padding = options.padding;
+    let title_height = if options.title { TITLE_HEIGHT } else { 0.0 };
+    // The legend goes below the diagram
+    let legend = options.legend.then(|| options.notation.legend_size());
+    let (legend_w, legend_h) = legend.map_or((0.0, 0.0), |(w, h)| (w, h + LEGEND_GAP));
+    let (x, y) = (min_x - padding, min_y - padding - title_height);
+    let (w, h) = (
+        (max_x - min_x).max(legend_w) + 2.0 * padding,
+        max_y - min_y + 2.0 * padding + title_height + legend_h,
+    );
+
+    let mut out = format!(
+        concat!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{sw}\" height=\"{sh}\" ",
+            "viewBox=\"{x} {y} {w} {h}\">\n",
+            "  <defs>\n",
+            "    <filter id=\"shadow\" x=\"-20%\" y=\"-20%\" width=\"140%\" height=\"140%\">\n",
+            "      <feDropShadow dx=\"0\" dy=\"6\" stdDeviation=\"10\" flood-color=\"#0f172a\" ",
+            "flood-opacity=\"0.08\"/>\n",
+            "    </filter>\n",
+            "  </defs>\n",
+        ),
+        sw = num(w * options.scale),
+        sh = num(h * options.scale),
+        x = num(x),
+        y = num(y),
+        w = num(w),
+        h = num(h),
+    );
+    if !options.transparent {
+        out.push_str(&format!(
+            "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+            num(x),
+            num(y),
+            num(w),
+            num(h),
+            CANVAS_BG
+        ));
+    }
+    if options.title {
+        let (title, subtitle) = title_text(diagram, name);
+        let top = y + padding;
+        out.push_str(&format!(
+            "  <text x=\"{}\" y=\"{}\" dominant-baseline=\"central\" font-family=\"{}\" font-size=\"18\" \
+             font-weight=\"600\" fill=\"{}\">{}</text>\n",
+            num(min_x),
+            num(top + 10.0),
+            escape_xml(FONT_SANS),
+            HEADER_BG,
+            escape_xml(&title)
+        ));
+        out.push_str(&format!(
+            "  <text x=\"{}\" y=\"{}\" dominant-baseline=\"central\" font-family=\"{}\" font-size=\"12\" \
+             fill=\"{}\">{}</text>\n",
+            num(min_x),
+            num(top + 32.0),
+            escape_xml(FONT_SANS),
+            TYPE_TEXT,
+            escape_xml(&subtitle)
+        ));
+    }
+    if let Some(source) = source {
+        out.push_str(&embed::svg_metadata(source));
+    }
+
+    // Relationships below tables
+    out.push_str(&format!(
+        "  <g fill=\"none\" stroke=\"{}\" stroke-width=\"{}\">\n",
+        RELATION_STROKE, RELATION_STROKE_WIDTH
+    ));
+    let ends = router.routes().iter().zip(router.paths());
+    for (i, (rel, (route, path))) in diagram.relationships.iter().zip(ends).enumerate() {
+        let Some(route) = route else { continue };
+        let path = path.as_ref();
+        let [from_mark, to_mark] = options.notation.end_marks(rel);
+        let color = highlights.relationships.get(&i);
+        if let Some(color) = color {
+            out.push_str(&format!(
+                "    <g stroke=\"{}\" stroke-width=\"2\">\n",
+                color
+            ));
+        }
+        match path {
+            Some(path) => write_waypoints(&mut out, path),
+            None => write_path(&mut out, route),
+        }
+        write_end_mark(&mut out, route.from, route.from_side.angle(), from_mark);
+        write_end_mark(&mut out, route.to, route.to_side.angle(), to_mark);
+        if options.notation.middle_diamond() {
+            let middle = path.map_or_else(|| route.middle(), |p| path_middle(p));
+            let left = Position {
+                x: middle.x - 10.0,
+                ..middle
+            };
+            write_symbol(&mut out, left, 0.0, Symbol::Diamond);
+        }
+        if route.labelled {
+            let text = column_label(rel);
+            let mut at = route_label_position(route, path, &text);
+            if options.notation.middle_diamond() {
+                at.y += 18.0;
+            }
+            write_route_label(&mut out, at, &text);
+        }
+        if color.is_some() {
+            out.push_str("    </g>\n");
+        }
+    }
+    out.push_str("  </g>\n");
+
+    for table in &diagram.tables {
+        write_table(&mut out, table, highlights);
+    }
+    if let Some(size) = legend {
+        write_legend(&mut out, options.notation, min_x, max_y + LEGEND_GAP, size);
+    }
+
+    out.push_str("</svg>\n");
+    out
+}
+
+/// A routed path, through its waypoints.
+fn write_waypoints(out: &mut String, path: &Path) {
+    let points: Vec<String> = path
+        .iter()
+        .map(|p| format!("{} {}", num(p.x), num(p.y)))
+        .collect();
+    out.push_str(&format!(
+        "    <path d=\"M{}\" stroke-linejoin=\"round\"/>\n",
+        points.join(" L")
+    ));
+}
+
+/// Where the label of a route goes: halfway along it, or for a routed
+/// self-loop beside it, since the loop runs close to its table. Same as
+/// `routeLabelPosition()` in `editor.js`.
+fn route_label_position(route: &Route, path: Option<&Path>, text: &str) -> Position {
+    let Some(path) = path else {
+        return route.middle();
+    };
+    let mut at = path_middle(path);
+    if route.is_self_loop() {
+        let outward = if route.from_side == Side::Left {
+            -1.0
+        } else {
+            1.0
+        };
+        at.x += outward * (label_width(text) / 2.0 + 4.0);
+    }
+    at
+}
+
+/// Straight segments past the markers joined by a bezier curve, where the
+/// router found no way around the tables.
+fn write_path(out: &mut String, route: &Route) {
+    let (from, to) = (route.from, route.to);
+    let [o_from, c1, c2, o_to] = route.curve();
+    out.push_str(&format!(
+        "    <path d=\"M{} {} L{} {} C{} {} {} {} {} {} L{} {}\"/>\n",
+        num(from.x),
+        num(from.y),
+        num(o_from.x),
+        num(o_from.y),
+        num(c1.x),
+        num(c1.y),
+        num(c2.x),
+        num(c2.y),
+        num(o_to.x),
+        num(o_to.y),
+        num(to.x),
+        num(to.y),
+    ));
+}
+
+fn write_end_mark(out: &mut String, at: Position, angle: f64, mark: EndMark) {
+    write_symbol(out, at, angle, mark.symbol);
+    if let Some(label) = mark.label {
+        let (p, centered) = label_position(at, angle);
+        out.push_str(&format!(
+            "    <text x=\"{}\" y=\"{}\" text-anchor=\"{}\" dominant-baseline=\"central\" \
+             font-family=\"{}\" font-size=\"11\" fill=\"{}\" stroke=\"none\">{}</text>\n",
+            num(p.x),
+            num(p.y),
+            if centered { "middle" } else { "start" },
+            escape_xml(FONT_SANS),
+            COLUMN_TEXT,
+            escape_xml(label)
+        ));
+    }
+}
+
+/// Foreign key columns of a self-loop or parallel relationship, in a pill
+/// centered on `at`, like `drawRouteLabel()` in `editor.js`.
+fn write_route_label(out: &mut String, at: Position, text: &str) {
+    let (w, h) = (label_width(text), 18.0);
+    out.push_str(&format!(
+        "    <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\" fill=\"{}\" stroke-width=\"1\"/>\n",
+        num(at.x - w / 2.0),
+        num(at.y - h / 2.0),
+        num(w),
+        num(h),
+        num(h / 2.0),
+        CANVAS_BG
+    ));
+    out.push_str(&format!(
+        "    <text x=\"{}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\" \
+         font-family=\"{}\" font-size=\"11\" fill=\"{}\" stroke=\"none\">{}</text>\n",
+        num(at.x),
+        num(at.y),
+        escape_xml(FONT_SANS),
+        TYPE_TEXT,
+        escape_xml(text)
+    ));
+}
+
+fn label_width(text: &str) -> f64 {
+    sans_text_width(text) * 11.0 / FONT_SIZE + 12.0
+}
+
+/// `symbol` at `at`, rotated to point away from the table along `angle`.
+fn write_symbol(out: &mut String, at: Position, angle: f64, symbol: Symbol) {
+    let shapes = match symbol {
+        Symbol::None => return,
+        Symbol::Ie(cardinality) => ie_shapes(cardinality),
+        Symbol::Dot => format!(
+            "<circle cx=\"5\" cy=\"0\" r=\"4\" fill=\"{}\"/>",
+            RELATION_STROKE
+        ),
+        Symbol::Diamond => format!(
+            "<path d=\"M0 0 L10 -7 L20 0 L10 7 Z\" fill=\"{}\"/>",
+            CANVAS_BG
+        ),
+    };
+    out.push_str(&format!(
+        "    <g transform=\"translate({} {}) rotate({})\">{}</g>\n",
+        num(at.x),
+        num(at.y),
+        num(angle.to_degrees()),
+        shapes
+    ));
+}
+
+/// Crow's foot marker, like `drawMarker()` in `editor.js`.
+fn ie_shapes(cardinality: Cardinality) -> String {
+    match cardinality {
+        // || two vertical lines
+        Cardinality::OneMandatory => "<path d=\"M6 -8 L6 8 M12 -8 L12 8\"/>".to_string(),
+        // |O vertical line + circle
+        Cardinality::OneOptional => format!(
+            "<path d=\"M6 -8 L6 8\"/><circle cx=\"14\" cy=\"0\" r=\"5\" fill=\"{}\"/>",
+            CANVAS_BG
+        ),
+        // |< vertical line + crow's foot
+        Cardinality::ManyMandatory => {
+            "<path d=\"M16 -8 L16 8 M12 0 L0 -8 M12 0 L0 8\"/>".to_string()
+        }
+        // O< circle + crow's foot
+        Cardinality::ManyOptional => format!(
+            "<circle cx=\"18\" cy=\"0\" r=\"5\" fill=\"{}\"/><path d=\"M12 0 L0 -8 M12 0 L0 8\"/>",
+            CANVAS_BG
+        ),
+    }
+}
+
+/// Box at (`x`, `y`) explaining the marks of `notation`.
+fn write_legend(out: &mut String, notation: Notation, x: f64, y: f64, (w, h): (f64, f64)) {
+    out.push_str(&format!(
+        "  <g data-legend=\"{}\">\n    <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"6\" \
+         fill=\"{}\" stroke=\"{}\"/>\n",
+        escape_xml(notation.title()),
+        num(x),
+        num(y),
+        num(w),
+        num(h),
+        TABLE_BG,
+        TABLE_BORDER
+    ));
+    let text = |out: &mut String, tx: f64, ty: f64, size: u32, weight: u32, s: &str| {
+        out.push_str(&format!(
+            "    <text x=\"{}\" y=\"{}\" dominant-baseline=\"central\" font-family=\"{}\" \
+             font-size=\"{}\" font-weight=\"{}\" fill=\"{}\">{}</text>\n",
+            num(tx),
+            num(ty),
+            escape_xml(FONT_SANS),
+            size,
+            weight,
+            COLUMN_TEXT,
+            escape_xml(s)
+        ));
+    };
+    let bx = x + LEGEND_PADDING;
+    text(out, bx, y + LEGEND_HEADER / 2.0, 13, 600, notation.title());
+
+    out.push_str(&format!(
+        "    <g fill=\"none\" stroke=\"{}\" stroke-width=\"{}\">\n",
+        RELATION_STROKE, RELATION_STROKE_WIDTH
+    ));
+    let rows = notation.legend();
+    for (i, (mark, _)) in rows.iter().enumerate() {
+        let cy = y + LEGEND_HEADER + (i as f64 + 0.5) * LEGEND_ROW;
+        // The table edge, then the line leaving it
+        out.push_str(&format!(
+            "    <path d=\"M{bx} {} L{bx} {} M{bx} {cy} L{} {cy}\"/>\n",
+            num(cy - 10.0),
+            num(cy + 10.0),
+            num(bx + LEGEND_LINE),
+            bx = num(bx),
+            cy = num(cy),
+        ));
+        write_end_mark(out, Position { x: bx, y: cy }, 0.0, *mark);
+    }
+    out.push_str("    </g>\n");
+    for (i, (_, meaning)) in rows.iter().enumerate() {
+        let cy = y + LEGEND_HEADER + (i as f64 + 0.5) * LEGEND_ROW;
+        text(out, bx + LEGEND_LINE + 12.0, cy, 12, 400, meaning);
+    }
+    out.push_str("  </g>\n");
+}
+
+fn write_table(out: &mut String, table: &Table, highlights: &Highlights) {
+    let p = table.position.unwrap_or(Position { x: 0.0, y: 0.0 });
+    let w = table_width(table);
+    let h = table_height(table);
+    let r = BORDER_RADIUS;
+
+    out.push_str(&format!(
+        "  <g data-table=\"{}\">\n",
+        escape_xml(&table.id.full_name())
+    ));
+    let outline = highlights.tables.get(&table.id);
+    out.push_str(&format!(
+        "    <rect x=\"{x}\" y=\"{y}\" width=\"{w}\" height=\"{h}\" rx=\"{r}\" fill=\"{bg}\" \
+         stroke=\"{border}\"{width} filter=\"url(#shadow)\"/>\n",
+        x = num(p.x),
+        y = num(p.y),
+        w = num(w),
+        h = num(h),
+        r = r,
+        bg = TABLE_BG,
+        border = outline.copied().unwrap_or(TABLE_BORDER),
+        width = if outline.is_some() {
+            " stroke-width=\"3\"".to_string()
+        } else if table.is_stub {
+            format!(" stroke-dasharray=\"{}\"", STUB_DASH)
+        } else {
+            String::new()
+        },
+    ));
+    let (header_bg, header_text) = header_colors(table);
+
+    // Header: rounded top corners, square bottom
+    out.push_str(&format!(
+        "    <path d=\"M{x0} {yh} V{yr} A{r} {r} 0 0 1 {xr} {y0} H{xwr} A{r} {r} 0 0 1 {xw} {yr} V{yh} Z\" fill=\"{bg}\"/>\n",
+        x0 = num(p.x),
+        y0 = num(p.y),
+        yr = num(p.y + r),
+        yh = num(p.y + HEADER_HEIGHT),
+        xr = num(p.x + r),
+        xwr = num(p.x + w - r),
+        xw = num(p.x + w),
+        r = r,
+        bg = header_bg,
+    ));
+    out.push_str(&format!(
+        "    <text x=\"{}\" y=\"{}\" dominant-baseline=\"central\" font-family=\"{}\" font-size=\"{}\" \
+         font-weight=\"600\" fill=\"{}\">{}</text>\n",
+        num(p.x + PADDING_X),
+        num(p.y + HEADER_HEIGHT / 2.0),
+        escape_xml(FONT_SANS),
+        FONT_SIZE,
+        header_text,
+        escape_xml(&table.id.name)
+    ));
+
+    for (i, col) in table.columns.iter().enumerate() {
+        let row_y = p.y + HEADER_HEIGHT + i as f64 * ROW_HEIGHT;
+        let mid_y = num(row_y + ROW_HEIGHT / 2.0);
+
+        if let Some(tint) = highlights
+            .columns
+            .get(&(table.id.clone(), col.name.clone()))
+        {
+            out.push_str(&format!(
+                "    <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" fill-opacity=\"0.15\"/>\n",
+                num(p.x + 1.0),
+                num(row_y),
+                num(w - 2.0),
+                ROW_HEIGHT,
+                tint,
+            ));
+        }
+        if i > 0 {
+            out.push_str(&format!(
+                "    <line x1=\"{}\" y1=\"{y}\" x2=\"{}\" y2=\"{y}\" stroke=\"{}\"/>\n",
+                num(p.x + 1.0),
+                num(p.x + w - 1.0),
+                ROW_SEPARATOR,
+                y = num(row_y),
+            ));
+        }
+
+        let mut name_x = p.x + PADDING_X;
+        if col.is_pk {
+            out.push_str(&format!(
+                "    <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"4\" fill=\"{}\" fill-opacity=\"0.1\"/>\n",
+                num(name_x),
+                num(row_y + (ROW_HEIGHT - PK_BADGE_HEIGHT) / 2.0),
+                PK_BADGE_WIDTH,
+                PK_BADGE_HEIGHT,
+                PK_COLOR,
+            ));
+            out.push_str(&format!(
+                "    <text x=\"{}\" y=\"{}\" dominant-baseline=\"central\" font-family=\"{}\" \
+                 font-size=\"10\" font-weight=\"600\" fill=\"{}\">PK</text>\n",
+                num(name_x + 5.0),
+                mid_y,
+                escape_xml(FONT_SANS),
+                PK_COLOR,
+            ));
+            name_x += PK_BADGE_WIDTH + 8.0;
+        }
+        out.push_str(&format!(
+            "    <text x=\"{}\" y=\"{}\" dominant-baseline=\"central\" font-family=\"{}\" font-size=\"{}\"{} \
+             fill=\"{}\">{}</text>\n",
+            num(name_x),
+            mid_y,
+            escape_xml(FONT_MONO),
+            FONT_SIZE,
+            if col.is_pk { " font-weight=\"600\"" } else { "" },
+            COLUMN_TEXT,
+            escape_xml(&col.name)
+        ));
+        out.push_str(&format!(
+            "    <text x=\"{}\" y=\"{}\" dominant-baseline=\"central\" text-anchor=\"end\" font-family=\"{}\" \
+             font-size=\"{}\" fill=\"{}\">{}</text>\n",
+            num(p.x + w - PADDING_X),
+            mid_y,
+            escape_xml(FONT_MONO),
+            FONT_SIZE,
+            TYPE_TEXT,
+            escape_xml(&col.type_raw)
+        ));
+    }
+
+    out.push_str("  </g>\n");
+}
+
+/// Coordinates rounded to two decimals.
+fn num(v: f64) -> String {
+    let rounded = (v * 100.0).round() / 100.0;
+    // Avoid "-0"
+    format!("{}", rounded + 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout;
+    use crate::parser::parse_dbml;
+
+    #[test]
+    fn test_basic_svg() {
+        let mut diagram = parse_dbml(include_str!("../../tests/fixtures/basic.dbml")).unwrap();
+        layout::ensure_positions(&mut diagram);
+        let svg = write_svg(&diagram, "basic", &ExportOptions::default(), None);
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert_eq!(svg.matches("<g data-table=").count(), 4);
+        // One marker at each end of the four relationships
+        assert_eq!(svg.matches("<g transform=").count(), 8);
+        assert!(svg.contains(">users</text>"));
+        assert!(svg.contains(">PK</text>"));
+    }
+
+    #[test]
+    fn test_title_and_transparent_background() {
+        let mut diagram = parse_dbml(include_str!("../../tests/fixtures/basic.dbml")).unwrap();
+        layout::ensure_positions(&mut diagram);
+        let options = ExportOptions {
+            scale: 1.0,
+            padding: 10.0,
+            transparent: true,
+            title: true,
+            ..Default::default()
+        };
+        let svg = write_svg(&diagram, "basic", &options, None);
+
+        assert!(!svg
+            .lines()
+            .any(|l| l.trim_start().starts_with("<rect") && l.contains(CANVAS_BG)));
+        assert!(svg.contains(">test</text>"));
+        assert!(svg.contains(">4 tables, 4 relationships</text>"));
+        let (min_x, min_y, _, _) = diagram_bounds(&diagram).unwrap();
+        assert!(svg.contains(&format!(
+            "viewBox=\"{} {} ",
+            num(min_x - 10.0),
+            num(min_y - 10.0 - TITLE_HEIGHT)
+        )));
+    }
+
+    #[test]
+    fn test_notation_and_legend() {
+        let mut diagram = parse_dbml(include_str!("../../tests/fixtures/basic.dbml")).unwrap();
+        layout::ensure_positions(&mut diagram);
+        let options = ExportOptions {
+            notation: Notation::Uml,
+            legend: true,
+            ..Default::default()
+        };
+        let svg = write_svg(&diagram, "basic", &options, None);
+
+        // Labels instead of crow's feet, so the only symbols are none
+        assert_eq!(svg.matches("<g transform=").count(), 0);
+        assert!(svg.contains(">0..*</text>"));
+        assert!(svg.contains("<g data-legend=\"UML notation\">"));
+        assert!(svg.contains(">zero or more</text>"));
+    }
+
+    #[test]
+    fn test_num() {
+        assert_eq!(num(1.0), "1");
+        assert_eq!(num(1.23456), "1.23");
+        assert_eq!(num(-0.001), "0");
+    }
+}
@@ -0,0 +1,293 @@
+//! Stitches PNG tiles into one image.
+//!
+//! Browser canvases have a maximum size, so the editor renders large
+//! exports in tiles of at most `TILE_SIZE` pixels and sends them one by
+//! one. Tiles are kept until their row is complete and then streamed into
+//! the output PNG, so at most one row of tiles is held in memory.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Cursor, Write};
+use std::path::{Path, PathBuf};
+
+use crate::embed::{self, Embedded};
+use crate::error::AppError;
+use crate::export::options::create_output_dir;
+
+/// Largest tile the editor renders; mirrors `TILE_SIZE` in `editor.js`.
+pub const TILE_SIZE: u32 = 4096;
+
+pub struct TileStitcher {
+    output: PathBuf,
+    /// Written here first and renamed to `output` once complete.
+    partial: PathBuf,
+    width: u32,
+    height: u32,
+    tile_size: u32,
+    cols: u32,
+    rows: u32,
+    next_row: u32,
+    /// Decoded RGBA tiles by (row, col).
+    pending: BTreeMap<(u32, u32), Vec<u8>>,
+    writer: Option<png::StreamWriter<'static, BufWriter<File>>>,
+}
+
+impl TileStitcher {
+    pub fn create(
+        output: &Path,
+        width: u32,
+        height: u32,
+        tile_size: u32,
+        source: &Embedded,
+    ) -> Result<Self, AppError> {
+        if width == 0 || height == 0 || tile_size == 0 || tile_size > TILE_SIZE {
+            return Err(AppError::InvalidImage(format!(
+                "cannot tile a {}x{} image into {}px tiles",
+                width, height, tile_size
+            )));
+        }
+
+        let mut partial = output.as_os_str().to_owned();
+        partial.push(".part");
+        let partial = PathBuf::from(partial);
+
+        create_output_dir(output)?;
+        let file = BufWriter::new(File::create(&partial)?);
+        // `Self` does not exist yet, so `Drop` cannot clean up after us here.
+        let writer = match start_png(file, width, height, source) {
+            Ok(writer) => writer,
+            Err(e) => {
+                let _ = std::fs::remove_file(&partial);
+                return Err(e);
+            }
+        };
+
+        Ok(Self {
+            output: output.to_path_buf(),
+            partial,
+            width,
+            height,
+            tile_size,
+            cols: width.div_ceil(tile_size),
+            rows: height.div_ceil(tile_size),
+            next_row: 0,
+            pending: BTreeMap::new(),
+            writer: Some(writer),
+        })
+    }
+
+    /// Width and height of the tile at (`col`, `row`); edge tiles are smaller.
+    fn tile_dims(&self, col: u32, row: u32) -> (u32, u32) {
+        let w = self.tile_size.min(self.width - col * self.tile_size);
+        let h = self.tile_size.min(self.height - row * self.tile_size);
+        (w, h)
+    }
+
+    /// Add one encoded tile. Returns the output path once the last tile is in.
+    pub fn add_tile(
+        &mut self,
+        col: u32,
+        row: u32,
+        png: &[u8],
+    ) -> Result<Option<PathBuf>, AppError> {
+        if col >= self.cols || row >= self.rows {
+            return Err(AppError::InvalidImage(format!(
+                "tile ({}, {}) is outside the {}x{} grid",
+                col, row, self.cols, self.rows
+            )));
+        }
+        if row < self.next_row || self.pending.contains_key(&(row, col)) {
+            return Err(AppError::InvalidImage(format!(
+                "tile ({}, {}) was sent twice",
+                col, row
+            )));
+        }
+
+        let (w, h) = self.tile_dims(col, row);
+        let rgba = decode_rgba(png, w, h)
+            .map_err(|e| AppError::InvalidImage(format!("tile ({}, {}): {}", col, row, e)))?;
+        self.pending.insert((row, col), rgba);
+
+        while self.next_row < self.rows && self.row_is_complete(self.next_row) {
+            self.write_row(self.next_row)?;
+            self.next_row += 1;
+        }
+        if self.next_row < self.rows {
+            return Ok(None);
+        }
+
+        if let Some(writer) = self.writer.take() {
+            writer.finish().map_err(encoding_error)?;
+        }
+        std::fs::rename(&self.partial, &self.output)?;
+        Ok(Some(self.output.clone()))
+    }
+
+    fn row_is_complete(&self, row: u32) -> bool {
+        (0..self.cols).all(|col| self.pending.contains_key(&(row, col)))
+    }
+
+    fn write_row(&mut self, row: u32) -> Result<(), AppError> {
+        let tiles: Vec<(u32, Vec<u8>)> = (0..self.cols)
+            .map(|col| {
+                let rgba = self.pending.remove(&(row, col)).unwrap_or_default();
+                (self.tile_dims(col, row).0, rgba)
+            })
+            .collect();
+        let (_, h) = self.tile_dims(0, row);
+        let writer = self
+            .writer
+            .as_mut()
+            .ok_or_else(|| AppError::InvalidImage("image already finished".into()))?;
+
+        for y in 0..h as usize {
+            for (w, rgba) in &tiles {
+                let line = *w as usize * 4;
+                writer.write_all(&rgba[y * line..(y + 1) * line])?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for TileStitcher {
+    /// An export abandoned halfway leaves no partial file behind.
+    fn drop(&mut self) {
+        if let Some(writer) = self.writer.take() {
+            drop(writer);
+            let _ = std::fs::remove_file(&self.partial);
+        }
+    }
+}
+
+/// Write the PNG header and text chunks and return a writer for the pixels.
+fn start_png(
+    file: BufWriter<File>,
+    width: u32,
+    height: u32,
+    source: &Embedded,
+) -> Result<png::StreamWriter<'static, BufWriter<File>>, AppError> {
+    let mut encoder = png::Encoder::new(file, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    for (key, text) in embed::png_text(source) {
+        encoder
+            .add_itxt_chunk(key.to_string(), text.to_string())
+            .map_err(encoding_error)?;
+    }
+    encoder
+        .write_header()
+        .and_then(|w| w.into_stream_writer())
+        .map_err(encoding_error)
+}
+
+/// Decode a PNG of the given size to 8-bit RGBA.
+fn decode_rgba(png: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let mut decoder = png::Decoder::new(Cursor::new(png));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).map_err(|e| e.to_string())?;
+    if (info.width, info.height) != (width, height) {
+        return Err(format!(
+            "expected {}x{} pixels, got {}x{}",
+            width, height, info.width, info.height
+        ));
+    }
+    let pixels = &buf[..info.buffer_size()];
+
+    let rgba = match info.color_type {
+        png::ColorType::Rgba => pixels.to_vec(),
+        png::ColorType::Rgb => pixels
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => pixels
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        png::ColorType::Grayscale => pixels.iter().flat_map(|&g| [g, g, g, 255]).collect(),
+        png::ColorType::Indexed => return Err("unexpanded palette image".into()),
+    };
+    Ok(rgba)
+}
+
+fn encoding_error(e: png::EncodingError) -> AppError {
+    match e {
+        png::EncodingError::IoError(e) => AppError::Io(e),
+        e => AppError::InvalidImage(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut encoder = png::Encoder::new(&mut out, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(rgba).unwrap();
+        writer.finish().unwrap();
+        out
+    }
+
+    /// A solid tile whose red channel identifies it.
+    fn tile(id: u8, width: u32, height: u32) -> Vec<u8> {
+        let rgba: Vec<u8> = (0..width * height).flat_map(|_| [id, 0, 0, 255]).collect();
+        encode(width, height, &rgba)
+    }
+
+    #[test]
+    fn test_stitches_tiles_in_any_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("big.png");
+        let source = Embedded {
+            dbml: "Table a {}".into(),
+            layout: None,
+        };
+        // 5x3 pixels in 2px tiles: a 3x2 grid with narrower edge tiles
+        let mut stitcher = TileStitcher::create(&output, 5, 3, 2, &source).unwrap();
+
+        let tiles = [
+            (1, 0, 2, 2),
+            (0, 0, 2, 2),
+            (2, 0, 1, 2),
+            (0, 1, 2, 1),
+            (2, 1, 1, 1),
+        ];
+        for (col, row, w, h) in tiles {
+            let id = (row * 3 + col) as u8;
+            assert_eq!(stitcher.add_tile(col, row, &tile(id, w, h)).unwrap(), None);
+        }
+        assert!(stitcher.add_tile(0, 0, &tile(0, 2, 2)).is_err());
+        assert!(!output.exists());
+        let done = stitcher.add_tile(1, 1, &tile(4, 2, 1)).unwrap();
+        assert_eq!(done, Some(output.clone()));
+
+        let bytes = std::fs::read(&output).unwrap();
+        let rgba = decode_rgba(&bytes, 5, 3).unwrap();
+        let reds: Vec<u8> = rgba.chunks(4).map(|p| p[0]).collect();
+        assert_eq!(reds, [0, 0, 1, 1, 2, 0, 0, 1, 1, 2, 3, 3, 4, 4, 5]);
+        assert_eq!(
+            embed::read_png_chunks(&bytes).unwrap().map(|e| e.dbml),
+            Some("Table a {}".into())
+        );
+    }
+
+    #[test]
+    fn test_wrong_tile_size_is_rejected_and_cleaned_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("big.png");
+        let source = Embedded {
+            dbml: String::new(),
+            layout: None,
+        };
+        let mut stitcher = TileStitcher::create(&output, 4, 4, 2, &source).unwrap();
+        assert!(stitcher.add_tile(0, 0, &tile(0, 3, 2)).is_err());
+        drop(stitcher);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,207 @@
+//! Include and exclude filters on table names, for drawing part of a
+//! schema: `billing.*`, everything but `audit.*`, no `*_history` tables.
+//!
+//! Patterns are globs where `*` matches any run of characters and `?` one
+//! character. A pattern with a `.` is matched against the full
+//! `schema.table` name, one without against the table name alone.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::Deserialize;
+
+use crate::ir::{Diagram, TableId};
+use crate::junction;
+
+/// What happens to relationships between a shown and a hidden table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum HiddenRefs {
+    /// Leave them out
+    #[default]
+    Drop,
+    /// Draw them to a placeholder for the hidden table
+    Stub,
+}
+
+/// The `[filter]` section of the project config, with command-line flags
+/// applied on top.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TableFilter {
+    /// Show only tables matching one of these; all tables if empty.
+    pub include: Vec<String>,
+    /// Hide tables matching one of these, even if included.
+    pub exclude: Vec<String>,
+    pub hidden_refs: HiddenRefs,
+    /// Draw every junction table as a many-to-many relationship.
+    pub collapse_junctions: bool,
+}
+
+impl TableFilter {
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    pub fn shows(&self, id: &TableId) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| pattern_matches(p, id)))
+            && !self.exclude.iter().any(|p| pattern_matches(p, id))
+    }
+
+    /// Remove the hidden tables. With `HiddenRefs::Stub`, hidden tables
+    /// referenced from shown ones stay as stubs holding just the
+    /// referenced columns. Junction tables are marked collapsed after, if
+    /// `collapse_junctions` is set.
+    pub fn apply(&self, diagram: &mut Diagram) {
+        self.hide_tables(diagram);
+        if self.collapse_junctions {
+            junction::collapse_all(diagram);
+        }
+    }
+
+    fn hide_tables(&self, diagram: &mut Diagram) {
+        if self.is_empty() {
+            return;
+        }
+        let hidden: HashSet<TableId> = diagram
+            .tables
+            .iter()
+            .filter(|t| !self.shows(&t.id))
+            .map(|t| t.id.clone())
+            .collect();
+
+        // Referenced columns of each hidden table a shown table points at
+        let mut stubs: HashMap<TableId, HashSet<String>> = HashMap::new();
+        if self.hidden_refs == HiddenRefs::Stub {
+            for rel in &diagram.relationships {
+                for (end, other) in [(&rel.from, &rel.to), (&rel.to, &rel.from)] {
+                    if hidden.contains(&end.table_id) && !hidden.contains(&other.table_id) {
+                        stubs
+                            .entry(end.table_id.clone())
+                            .or_default()
+                            .extend(end.column_names.iter().cloned());
+                    }
+                }
+            }
+        }
+
+        diagram.retain_tables(|id| !hidden.contains(id) || stubs.contains_key(id));
+        diagram
+            .relationships
+            .retain(|r| !(hidden.contains(&r.from.table_id) && hidden.contains(&r.to.table_id)));
+        for group in &mut diagram.table_groups {
+            group.tables.retain(|entry| !hidden.contains(&entry.id));
+        }
+        diagram.table_groups.retain(|g| !g.tables.is_empty());
+
+        for table in &mut diagram.tables {
+            if let Some(columns) = stubs.get(&table.id) {
+                table.is_stub = true;
+                table.columns.retain(|c| columns.contains(&c.name));
+                table.indexes.clear();
+                table.note = None;
+            }
+        }
+    }
+}
+
+fn pattern_matches(pattern: &str, id: &TableId) -> bool {
+    if pattern.contains('.') {
+        glob_matches(pattern, &id.full_name())
+    } else {
+        glob_matches(pattern, &id.name)
+    }
+}
+
+/// Whether `text` matches `pattern` as a whole, `*` matching any run of
+/// characters and `?` any single character.
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                backtrack = Some((p, t));
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` take one more character
+                Some((star_p, star_t)) => {
+                    p = star_p;
+                    t = star_t + 1;
+                    backtrack = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_dbml;
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("billing.*", "billing.invoices"));
+        assert!(!glob_matches("billing.*", "public.billing"));
+        assert!(glob_matches("*_history", "orders_history"));
+        assert!(!glob_matches("*_history", "orders_history_old"));
+        assert!(glob_matches("a*b*c", "abxbc"));
+        assert!(glob_matches("user?", "users"));
+        assert!(!glob_matches("user?", "user"));
+        assert!(glob_matches("*", ""));
+    }
+
+    #[test]
+    fn test_hidden_tables_become_stubs() {
+        let source = "Project p {\n  database_type: 'PostgreSQL'\n}\n\
+            Table users {\n  id int [pk]\n  name varchar\n}\n\
+            Table posts {\n  id int [pk]\n  user_id int [ref: > users.id]\n}\n\
+            Table audit.events {\n  id int [pk]\n  user_id int [ref: > users.id]\n}\n\
+            Table posts_history {\n  id int [pk]\n  post_id int [ref: > posts.id]\n}\n";
+        let filter = TableFilter {
+            include: vec!["posts".into(), "audit.*".into(), "*_history".into()],
+            exclude: vec!["*_history".into()],
+            hidden_refs: HiddenRefs::Stub,
+            ..Default::default()
+        };
+        assert!(!filter.shows(&TableId::new("public", "users")));
+        assert!(filter.shows(&TableId::new("audit", "events")));
+
+        let mut diagram = parse_dbml(source).unwrap();
+        filter.apply(&mut diagram);
+        let tables: Vec<(String, bool, usize)> = diagram
+            .tables
+            .iter()
+            .map(|t| (t.id.full_name(), t.is_stub, t.columns.len()))
+            .collect();
+        assert_eq!(
+            tables,
+            [
+                ("public.users".to_string(), true, 1),
+                ("public.posts".to_string(), false, 2),
+                ("audit.events".to_string(), false, 2),
+                ("public.posts_history".to_string(), true, 1),
+            ]
+        );
+        assert_eq!(diagram.relationships.len(), 3);
+
+        let mut diagram = parse_dbml(source).unwrap();
+        TableFilter {
+            hidden_refs: HiddenRefs::Drop,
+            ..filter
+        }
+        .apply(&mut diagram);
+        assert_eq!(diagram.tables.len(), 2);
+        assert!(diagram.relationships.is_empty());
+    }
+}
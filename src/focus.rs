@@ -0,0 +1,68 @@
+//! Focus on one table: the part of a diagram within a few relationships of
+//! it, for looking at a large schema piece by piece.
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::error::AppError;
+use crate::graph::{self, Direction};
+use crate::ir::{Diagram, TableId};
+
+/// The focused table and how far around it to show. The editor receives
+/// this as `window.__INITIAL_FOCUS`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Focus {
+    /// Full `schema.table` name.
+    pub table: String,
+    pub depth: usize,
+    pub direction: Direction,
+}
+
+impl Focus {
+    /// Focus on the table called `name` in `diagram` (see `graph::resolve_table`).
+    pub fn new(
+        diagram: &Diagram,
+        name: &str,
+        depth: usize,
+        direction: Direction,
+    ) -> Result<Self, AppError> {
+        let index = graph::resolve_table(diagram, name)?;
+        Ok(Self {
+            table: diagram.tables[index].id.full_name(),
+            depth,
+            direction,
+        })
+    }
+
+    /// Drop every table outside the focus.
+    pub fn apply(&self, diagram: &mut Diagram) -> Result<(), AppError> {
+        let start = graph::resolve_table(diagram, &self.table)?;
+        let keep: HashSet<TableId> =
+            graph::neighborhood(diagram, start, self.depth, self.direction)
+                .into_iter()
+                .map(|i| diagram.tables[i].id.clone())
+                .collect();
+        diagram.retain_tables(|id| keep.contains(id));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_dbml;
+
+    #[test]
+    fn test_apply_keeps_relationships_inside_the_focus() {
+        let mut diagram = parse_dbml(include_str!("../tests/fixtures/basic.dbml")).unwrap();
+        let focus = Focus::new(&diagram, "posts", 1, Direction::Both).unwrap();
+        assert_eq!(focus.table, "public.posts");
+        focus.apply(&mut diagram).unwrap();
+
+        let names: Vec<&str> = diagram.tables.iter().map(|t| t.id.name.as_str()).collect();
+        assert_eq!(names, ["users", "posts", "comments"]);
+        // profiles -> users is outside; comments -> users is between two kept tables
+        assert_eq!(diagram.relationships.len(), 3);
+    }
+}
@@ -0,0 +1,117 @@
+//! Reading files as they were at a git revision.
+//!
+//! Runs the `git` command in the file's directory, so any revision git
+//! understands works (`HEAD~1`, `origin/main`, a tag or hash) and only the
+//! local object database is read.
+
+use std::path::Path;
+use std::process::Command;
+
+use crate::error::AppError;
+
+/// Contents of `path` at `rev`, or `None` if the file does not exist in
+/// that revision.
+pub fn read_at_rev(path: &Path, rev: &str) -> Result<Option<String>, AppError> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = path
+        .file_name()
+        .ok_or_else(|| AppError::GitError(format!("{} is not a file", path.display())))?;
+
+    // Check the revision first so that a typo is not reported as a missing file
+    git(
+        dir,
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", rev),
+        ],
+    )
+    .map_err(|e| match e {
+        GitFailure::Failed(msg) if msg.is_empty() => {
+            AppError::GitError(format!("unknown revision {}", rev))
+        }
+        e => e.into(),
+    })?;
+
+    // `./` makes the path relative to `dir` rather than the repository root
+    let spec = format!("{}:./{}", rev, name.to_string_lossy());
+    if git(dir, &["cat-file", "-e", &spec]).is_err() {
+        return Ok(None);
+    }
+    let blob = git(dir, &["cat-file", "blob", &spec])?;
+    String::from_utf8(blob)
+        .map(Some)
+        .map_err(|_| AppError::GitError(format!("{} is not UTF-8 text", spec)))
+}
+
+enum GitFailure {
+    /// Could not run git at all.
+    Spawn(std::io::Error),
+    /// git exited with an error; its message.
+    Failed(String),
+}
+
+impl From<GitFailure> for AppError {
+    fn from(e: GitFailure) -> Self {
+        match e {
+            GitFailure::Spawn(e) => AppError::GitError(format!("cannot run git: {}", e)),
+            GitFailure::Failed(msg) => AppError::GitError(msg),
+        }
+    }
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<Vec<u8>, GitFailure> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(GitFailure::Spawn)?;
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(GitFailure::Failed(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?}", args);
+    }
+
+    #[test]
+    fn test_read_at_rev() {
+        let repo = tempfile::tempdir().unwrap();
+        let sub = repo.path().join("db");
+        std::fs::create_dir(&sub).unwrap();
+        let file = sub.join("schema.dbml");
+
+        run(repo.path(), &["init", "-q"]);
+        std::fs::write(&file, "old").unwrap();
+        run(repo.path(), &["add", "."]);
+        run(repo.path(), &["commit", "-q", "-m", "first"]);
+        std::fs::write(&file, "new").unwrap();
+
+        assert_eq!(read_at_rev(&file, "HEAD").unwrap().as_deref(), Some("old"));
+        assert_eq!(read_at_rev(&sub.join("other.dbml"), "HEAD").unwrap(), None);
+        let err = read_at_rev(&file, "no-such-branch").unwrap_err();
+        assert!(err.to_string().contains("unknown revision no-such-branch"));
+    }
+}
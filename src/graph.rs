@@ -0,0 +1,516 @@
+//! Foreign key graph over `Diagram::relationships`.
+//!
+//! Tables are nodes and each foreign key is an edge from the referencing
+//! (child) table to the referenced (parent) table. Many-to-many
+//! relationships have no foreign key and are not part of the graph, except
+//! in `neighborhood`, which follows every relationship.
+
+use std::collections::VecDeque;
+
+use serde::Serialize;
+
+use crate::error::AppError;
+use crate::ir::Diagram;
+
+/// A foreign key between two tables, by index into `Diagram::tables`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FkEdge {
+    /// Index into `Diagram::relationships`.
+    pub rel: usize,
+    pub child: usize,
+    pub parent: usize,
+}
+
+/// All foreign keys whose tables both exist in the diagram.
+pub fn fk_edges(diagram: &Diagram) -> Vec<FkEdge> {
+    diagram
+        .relationships
+        .iter()
+        .enumerate()
+        .filter_map(|(rel, r)| {
+            let (child, parent) = r.foreign_key()?;
+            Some(FkEdge {
+                rel,
+                child: table_index(diagram, &child.table_id)?,
+                parent: table_index(diagram, &parent.table_id)?,
+            })
+        })
+        .collect()
+}
+
+/// Whether every column of the foreign key can be null, so a row can be
+/// inserted before the row it references.
+pub fn fk_is_nullable(diagram: &Diagram, edge: &FkEdge) -> bool {
+    let Some((child, _)) = diagram.relationships[edge.rel].foreign_key() else {
+        return false;
+    };
+    let table = &diagram.tables[edge.child];
+    !child.column_names.is_empty()
+        && child
+            .column_names
+            .iter()
+            .all(|name| table.is_nullable(name))
+}
+
+pub fn table_index(diagram: &Diagram, id: &crate::ir::TableId) -> Option<usize> {
+    diagram.tables.iter().position(|t| t.id == *id)
+}
+
+/// Index of the table called `name`: a full `schema.table` name, or just
+/// the table name if only one schema has a table by that name.
+pub fn resolve_table(diagram: &Diagram, name: &str) -> Result<usize, AppError> {
+    if let Some(i) = diagram.tables.iter().position(|t| t.id.full_name() == name) {
+        return Ok(i);
+    }
+    let matches: Vec<usize> = (0..diagram.tables.len())
+        .filter(|&i| diagram.tables[i].id.name == name)
+        .collect();
+    match matches.as_slice() {
+        [i] => Ok(*i),
+        [] => Err(AppError::TableNotFound(name.to_string())),
+        _ => {
+            let names: Vec<String> = matches
+                .iter()
+                .map(|&i| diagram.tables[i].id.full_name())
+                .collect();
+            Err(AppError::AmbiguousTable(format!(
+                "{} ({})",
+                name,
+                names.join(", ")
+            )))
+        }
+    }
+}
+
+/// Which relationships `neighborhood` follows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// Both ways
+    #[default]
+    Both,
+    /// From a table to the tables it references
+    Outgoing,
+    /// From a table to the tables that reference it
+    Incoming,
+}
+
+/// Tables at most `depth` relationships away from `start`, in declaration
+/// order. Many-to-many relationships are followed both ways whatever the
+/// direction. Mirrors `neighborhood()` in `editor.js`.
+pub fn neighborhood(
+    diagram: &Diagram,
+    start: usize,
+    depth: usize,
+    direction: Direction,
+) -> Vec<usize> {
+    // (from, to) pairs one hop can take
+    let mut hops: Vec<(usize, usize)> = Vec::new();
+    for rel in &diagram.relationships {
+        let (Some(from), Some(to)) = (
+            table_index(diagram, &rel.from.table_id),
+            table_index(diagram, &rel.to.table_id),
+        ) else {
+            continue;
+        };
+        let (child, parent) = match rel.foreign_key() {
+            Some((child, _)) if child.table_id == rel.from.table_id => (from, to),
+            Some(_) => (to, from),
+            None => {
+                hops.extend([(from, to), (to, from)]);
+                continue;
+            }
+        };
+        if direction != Direction::Incoming {
+            hops.push((child, parent));
+        }
+        if direction != Direction::Outgoing {
+            hops.push((parent, child));
+        }
+    }
+
+    let mut distance = vec![usize::MAX; diagram.tables.len()];
+    distance[start] = 0;
+    let mut queue = VecDeque::from([start]);
+    while let Some(table) = queue.pop_front() {
+        if distance[table] == depth {
+            continue;
+        }
+        for &(_, to) in hops.iter().filter(|(from, _)| *from == table) {
+            if distance[to] == usize::MAX {
+                distance[to] = distance[table] + 1;
+                queue.push_back(to);
+            }
+        }
+    }
+    (0..diagram.tables.len())
+        .filter(|&i| distance[i] != usize::MAX)
+        .collect()
+}
+
+/// Tables in foreign key dependency order.
+#[derive(Debug, Clone)]
+pub struct DependencyOrder {
+    /// Table indices, parents before children.
+    pub tables: Vec<usize>,
+    /// Foreign keys that point forward in `tables` because they close a
+    /// cycle; these have to be created after both tables exist.
+    pub deferred: Vec<FkEdge>,
+}
+
+/// Order tables so that every table comes after the tables it references.
+///
+/// Ties keep declaration order. When only cycles remain, the earliest
+/// declared remaining table whose blocking foreign keys are all nullable is
+/// emitted (or just the earliest, if there is none) and those foreign keys
+/// are deferred. Self-references never block ordering.
+pub fn dependency_order(diagram: &Diagram) -> DependencyOrder {
+    let n = diagram.tables.len();
+    let edges: Vec<FkEdge> = fk_edges(diagram)
+        .into_iter()
+        .filter(|e| e.child != e.parent)
+        .collect();
+
+    let mut placed = vec![false; n];
+    let mut tables = Vec::with_capacity(n);
+    let mut deferred = Vec::new();
+
+    while tables.len() < n {
+        let ready =
+            (0..n).find(|&i| !placed[i] && edges.iter().all(|e| e.child != i || placed[e.parent]));
+        let next = match ready {
+            Some(i) => i,
+            None => {
+                // Only cycles left: break where rows can be inserted with
+                // the foreign key null and set later, if possible
+                let placed = &placed;
+                let blocking = |i: usize| {
+                    edges
+                        .iter()
+                        .filter(move |e| e.child == i && !placed[e.parent])
+                };
+                let i = (0..n)
+                    .filter(|&i| !placed[i])
+                    .find(|&i| blocking(i).all(|e| fk_is_nullable(diagram, e)))
+                    .or_else(|| (0..n).find(|&i| !placed[i]))
+                    .unwrap_or_default();
+                deferred.extend(blocking(i).copied());
+                i
+            }
+        };
+        placed[next] = true;
+        tables.push(next);
+    }
+
+    DependencyOrder { tables, deferred }
+}
+
+/// Groups of tables connected by any relationship, each in declaration
+/// order, ordered by their first table.
+pub fn connected_components(diagram: &Diagram) -> Vec<Vec<usize>> {
+    let n = diagram.tables.len();
+    let mut neighbors: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for rel in &diagram.relationships {
+        if let (Some(a), Some(b)) = (
+            table_index(diagram, &rel.from.table_id),
+            table_index(diagram, &rel.to.table_id),
+        ) {
+            neighbors[a].push(b);
+            neighbors[b].push(a);
+        }
+    }
+
+    let mut component = vec![usize::MAX; n];
+    let mut components: Vec<Vec<usize>> = Vec::new();
+    for start in 0..n {
+        if component[start] != usize::MAX {
+            continue;
+        }
+        let id = components.len();
+        component[start] = id;
+        let mut members = vec![start];
+        let mut queue = VecDeque::from([start]);
+        while let Some(table) = queue.pop_front() {
+            for &next in &neighbors[table] {
+                if component[next] == usize::MAX {
+                    component[next] = id;
+                    members.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+        members.sort_unstable();
+        components.push(members);
+    }
+    components
+}
+
+/// Strongly connected components of the foreign key graph, each in
+/// declaration order, ordered by their first table. Every table is in
+/// exactly one; those of more than one table contain a cycle.
+pub fn strongly_connected_components(diagram: &Diagram) -> Vec<Vec<usize>> {
+    // Tarjan's algorithm
+    struct State {
+        parents: Vec<Vec<usize>>,
+        index: Vec<usize>,
+        low: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next_index: usize,
+        components: Vec<Vec<usize>>,
+    }
+
+    fn visit(s: &mut State, v: usize) {
+        s.index[v] = s.next_index;
+        s.low[v] = s.next_index;
+        s.next_index += 1;
+        s.stack.push(v);
+        s.on_stack[v] = true;
+        for i in 0..s.parents[v].len() {
+            let w = s.parents[v][i];
+            if s.index[w] == usize::MAX {
+                visit(s, w);
+                s.low[v] = s.low[v].min(s.low[w]);
+            } else if s.on_stack[w] {
+                s.low[v] = s.low[v].min(s.index[w]);
+            }
+        }
+        if s.low[v] == s.index[v] {
+            let mut component = Vec::new();
+            while let Some(w) = s.stack.pop() {
+                s.on_stack[w] = false;
+                component.push(w);
+                if w == v {
+                    break;
+                }
+            }
+            component.sort_unstable();
+            s.components.push(component);
+        }
+    }
+
+    let n = diagram.tables.len();
+    let mut parents: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for edge in fk_edges(diagram) {
+        parents[edge.child].push(edge.parent);
+    }
+    let mut state = State {
+        parents,
+        index: vec![usize::MAX; n],
+        low: vec![0; n],
+        on_stack: vec![false; n],
+        stack: Vec::new(),
+        next_index: 0,
+        components: Vec::new(),
+    };
+    for v in 0..n {
+        if state.index[v] == usize::MAX {
+            visit(&mut state, v);
+        }
+    }
+    state.components.sort_by_key(|c| c[0]);
+    state.components
+}
+
+/// Foreign key cycles, at most `limit` of them. Each cycle lists its
+/// foreign keys starting from its earliest declared table; a
+/// self-reference is a cycle of one.
+pub fn fk_cycles(diagram: &Diagram, limit: usize) -> Vec<Vec<FkEdge>> {
+    let edges = fk_edges(diagram);
+    let mut component = vec![0; diagram.tables.len()];
+    for (i, members) in strongly_connected_components(diagram).iter().enumerate() {
+        for &t in members {
+            component[t] = i;
+        }
+    }
+
+    fn extend(
+        edges: &[FkEdge],
+        component: &[usize],
+        start: usize,
+        path: &mut Vec<FkEdge>,
+        cycles: &mut Vec<Vec<FkEdge>>,
+        limit: usize,
+    ) {
+        let table = path.last().map_or(start, |e| e.parent);
+        for edge in edges.iter().filter(|e| e.child == table) {
+            if cycles.len() == limit {
+                return;
+            }
+            // Only tables after `start` in its component, so each cycle is
+            // found once, from its earliest table
+            if edge.parent < start || component[edge.parent] != component[start] {
+                continue;
+            }
+            path.push(*edge);
+            if edge.parent == start {
+                cycles.push(path.clone());
+            } else if !path[..path.len() - 1]
+                .iter()
+                .any(|e| e.parent == edge.parent)
+            {
+                extend(edges, component, start, path, cycles, limit);
+            }
+            path.pop();
+        }
+    }
+
+    let mut cycles = Vec::new();
+    for start in 0..diagram.tables.len() {
+        extend(
+            &edges,
+            &component,
+            start,
+            &mut Vec::new(),
+            &mut cycles,
+            limit,
+        );
+    }
+    cycles
+}
+
+/// The longest chain of foreign keys, as tables from the first child to
+/// the last parent. Foreign keys deferred by `dependency_order` to break
+/// cycles, and self-references, are not followed.
+pub fn longest_chain(diagram: &Diagram) -> Vec<usize> {
+    let order = dependency_order(diagram);
+    let edges: Vec<FkEdge> = fk_edges(diagram)
+        .into_iter()
+        .filter(|e| e.child != e.parent && !order.deferred.contains(e))
+        .collect();
+
+    // Length of the longest chain from each table up, and its next table
+    let mut length = vec![0; diagram.tables.len()];
+    let mut next = vec![None; diagram.tables.len()];
+    for &table in &order.tables {
+        for edge in edges.iter().filter(|e| e.child == table) {
+            if length[edge.parent] + 1 > length[table] {
+                length[table] = length[edge.parent] + 1;
+                next[table] = Some(edge.parent);
+            }
+        }
+    }
+
+    let Some(first) = (0..diagram.tables.len()).max_by_key(|&i| (length[i], usize::MAX - i)) else {
+        return Vec::new();
+    };
+    let mut chain = vec![first];
+    while let Some(parent) = next[*chain.last().unwrap_or(&first)] {
+        chain.push(parent);
+    }
+    chain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{EndPoint, RelationType, Relationship, Table, TableId};
+
+    fn diagram(tables: &[&str], fks: &[(&str, &str)]) -> Diagram {
+        let ep = |t: &str| EndPoint {
+            table_id: TableId::new("public", t),
+            column_names: vec!["id".into()],
+        };
+        Diagram {
+            tables: tables
+                .iter()
+                .map(|t| Table {
+                    id: TableId::new("public", *t),
+                    ..Default::default()
+                })
+                .collect(),
+            relationships: fks
+                .iter()
+                .map(|(child, parent)| {
+                    Relationship::new(RelationType::ManyToOne, ep(child), ep(parent))
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_neighborhood() {
+        // a -> b -> c -> d, e -> b
+        let d = diagram(
+            &["a", "b", "c", "d", "e"],
+            &[("a", "b"), ("b", "c"), ("c", "d"), ("e", "b")],
+        );
+        assert_eq!(neighborhood(&d, 1, 1, Direction::Both), vec![0, 1, 2, 4]);
+        assert_eq!(neighborhood(&d, 1, 2, Direction::Outgoing), vec![1, 2, 3]);
+        assert_eq!(
+            neighborhood(&d, 2, 2, Direction::Incoming),
+            vec![0, 1, 2, 4]
+        );
+        assert_eq!(neighborhood(&d, 0, 0, Direction::Both), vec![0]);
+
+        assert_eq!(resolve_table(&d, "public.c").unwrap(), 2);
+        assert_eq!(resolve_table(&d, "c").unwrap(), 2);
+        assert!(resolve_table(&d, "z").is_err());
+    }
+
+    #[test]
+    fn test_components_and_cycles() {
+        // a -> b -> c -> a, c -> d, d -> d, e alone
+        let d = diagram(
+            &["a", "b", "c", "d", "e"],
+            &[("a", "b"), ("b", "c"), ("c", "a"), ("c", "d"), ("d", "d")],
+        );
+        assert_eq!(connected_components(&d), vec![vec![0, 1, 2, 3], vec![4]]);
+        assert_eq!(
+            strongly_connected_components(&d),
+            vec![vec![0, 1, 2], vec![3], vec![4]]
+        );
+        let cycles: Vec<Vec<usize>> = fk_cycles(&d, 10)
+            .iter()
+            .map(|c| c.iter().map(|e| e.rel).collect())
+            .collect();
+        assert_eq!(cycles, vec![vec![0, 1, 2], vec![4]]);
+        assert_eq!(fk_cycles(&d, 1).len(), 1);
+        // a -> b is deferred to break the cycle
+        assert_eq!(longest_chain(&d), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn test_parents_come_first() {
+        let d = diagram(
+            &["comments", "posts", "users"],
+            &[("comments", "posts"), ("posts", "users")],
+        );
+        let order = dependency_order(&d);
+        assert_eq!(order.tables, vec![2, 1, 0]);
+        assert!(order.deferred.is_empty());
+    }
+
+    #[test]
+    fn test_one_to_many_points_the_other_way() {
+        let mut d = diagram(&["users", "posts"], &[]);
+        d.relationships.push(Relationship::new(
+            RelationType::OneToMany,
+            EndPoint {
+                table_id: TableId::new("public", "users"),
+                column_names: vec!["id".into()],
+            },
+            EndPoint {
+                table_id: TableId::new("public", "posts"),
+                column_names: vec!["user_id".into()],
+            },
+        ));
+        let edges = fk_edges(&d);
+        assert_eq!(edges[0].child, 1);
+        assert_eq!(edges[0].parent, 0);
+        assert_eq!(dependency_order(&d).tables, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_cycles_are_deferred() {
+        let d = diagram(
+            &["a", "b", "c"],
+            &[("a", "b"), ("b", "a"), ("c", "c"), ("c", "a")],
+        );
+        let order = dependency_order(&d);
+        assert_eq!(order.tables, vec![0, 1, 2]);
+        assert_eq!(order.deferred.len(), 1);
+        assert_eq!((order.deferred[0].child, order.deferred[0].parent), (0, 1));
+    }
+}
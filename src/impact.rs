@@ -0,0 +1,222 @@
+//! What depends on a column: the indexes and relationships that use it
+//! and, through foreign keys referencing it, the columns that would have to
+//! change with it, recursively.
+//!
+//! Mirrors `columnImpact()` in `editor.js`, which highlights the same
+//! columns and relationships.
+
+use std::collections::HashSet;
+
+use crate::diff::{describe_ref, index_summary};
+use crate::error::AppError;
+use crate::graph::{resolve_table, table_index};
+use crate::ir::{Diagram, IndexColumn};
+
+/// A column and what depends on it.
+#[derive(Debug)]
+pub struct ColumnImpact {
+    /// Index into `Diagram::tables`.
+    pub table: usize,
+    pub column: String,
+    /// Indexes of the table that include the column, by position in
+    /// `Table::indexes`.
+    pub indexes: Vec<usize>,
+    /// Relationships with the column in either endpoint.
+    pub relationships: Vec<usize>,
+    /// Columns whose foreign keys reference this one.
+    pub dependents: Vec<ColumnImpact>,
+}
+
+impl ColumnImpact {
+    /// Dependent columns, relationships and indexes, this column excluded.
+    pub fn counts(&self) -> (usize, usize, usize) {
+        let mut relationships: HashSet<usize> = self.relationships.iter().copied().collect();
+        let (mut columns, mut indexes) = (0, self.indexes.len());
+        for dependent in &self.dependents {
+            let mut stack = vec![dependent];
+            while let Some(impact) = stack.pop() {
+                columns += 1;
+                indexes += impact.indexes.len();
+                relationships.extend(&impact.relationships);
+                stack.extend(&impact.dependents);
+            }
+        }
+        (columns, relationships.len(), indexes)
+    }
+}
+
+/// The table and column named by `schema.table.column`, or `table.column`
+/// if the table name is unique.
+pub fn resolve_column(diagram: &Diagram, name: &str) -> Result<(usize, String), AppError> {
+    let (table, column) = name
+        .rsplit_once('.')
+        .ok_or_else(|| AppError::ColumnNotFound(name.to_string()))?;
+    let table = resolve_table(diagram, table)?;
+    if !diagram.tables[table]
+        .columns
+        .iter()
+        .any(|c| c.name == column)
+    {
+        return Err(AppError::ColumnNotFound(name.to_string()));
+    }
+    Ok((table, column.to_string()))
+}
+
+pub fn column_impact(diagram: &Diagram, table: usize, column: &str) -> ColumnImpact {
+    let mut visited = HashSet::new();
+    visit(diagram, table, column, &mut visited)
+}
+
+fn visit(
+    diagram: &Diagram,
+    table: usize,
+    column: &str,
+    visited: &mut HashSet<(usize, String)>,
+) -> ColumnImpact {
+    visited.insert((table, column.to_string()));
+    let id = &diagram.tables[table].id;
+
+    let indexes = diagram.tables[table]
+        .indexes
+        .iter()
+        .enumerate()
+        .filter(|(_, index)| {
+            index
+                .columns
+                .iter()
+                .any(|c| matches!(c, IndexColumn::Column(name) if name == column))
+        })
+        .map(|(i, _)| i)
+        .collect();
+
+    let uses = |table_id: &_, names: &[String]| table_id == id && names.iter().any(|n| n == column);
+    let relationships: Vec<usize> = diagram
+        .relationships
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| {
+            uses(&r.from.table_id, &r.from.column_names) || uses(&r.to.table_id, &r.to.column_names)
+        })
+        .map(|(i, _)| i)
+        .collect();
+
+    // The referencing column at the same position of each foreign key,
+    // composite ones included
+    let mut dependents = Vec::new();
+    for &rel in &relationships {
+        let Some((child, parent)) = diagram.relationships[rel].foreign_key() else {
+            continue;
+        };
+        if parent.table_id != *id {
+            continue;
+        }
+        let Some(position) = parent.column_names.iter().position(|c| c == column) else {
+            continue;
+        };
+        let (Some(child_table), Some(child_column)) = (
+            table_index(diagram, &child.table_id),
+            child.column_names.get(position),
+        ) else {
+            continue;
+        };
+        if !visited.contains(&(child_table, child_column.clone())) {
+            dependents.push(visit(diagram, child_table, child_column, visited));
+        }
+    }
+
+    ColumnImpact {
+        table,
+        column: column.to_string(),
+        indexes,
+        relationships,
+        dependents,
+    }
+}
+
+/// Text report of `impact` as an indented tree.
+pub fn report(diagram: &Diagram, impact: &ColumnImpact) -> String {
+    let mut out = String::new();
+    write_impact(diagram, impact, 0, &mut out);
+    let (columns, relationships, indexes) = impact.counts();
+    let count =
+        |n: usize, one: &str, many: &str| format!("{} {}", n, if n == 1 { one } else { many });
+    out.push_str(&format!(
+        "\n{}, {}, {} depend on {}.{}\n",
+        count(columns, "column", "columns"),
+        count(relationships, "relationship", "relationships"),
+        count(indexes, "index", "indexes"),
+        diagram.tables[impact.table].id,
+        impact.column
+    ));
+    out
+}
+
+fn write_impact(diagram: &Diagram, impact: &ColumnImpact, depth: usize, out: &mut String) {
+    let table = &diagram.tables[impact.table];
+    let indent = "  ".repeat(depth);
+    let mut line = format!("{}{}.{}", indent, table.id, impact.column);
+    if let Some(column) = table.columns.iter().find(|c| c.name == impact.column) {
+        if column.is_pk {
+            line.push_str(" (primary key)");
+        } else if column.is_unique {
+            line.push_str(" (unique)");
+        }
+    }
+    out.push_str(&line);
+    out.push('\n');
+    for &index in &impact.indexes {
+        out.push_str(&format!(
+            "{}  index {}\n",
+            indent,
+            index_summary(&table.indexes[index])
+        ));
+    }
+    for &rel in &impact.relationships {
+        out.push_str(&format!(
+            "{}  ref {}\n",
+            indent,
+            describe_ref(&diagram.relationships[rel])
+        ));
+    }
+    for dependent in &impact.dependents {
+        write_impact(diagram, dependent, depth + 1, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_dbml;
+
+    #[test]
+    fn test_impact_follows_composite_foreign_keys() {
+        let diagram = parse_dbml(
+            "Project p {\n  database_type: 'PostgreSQL'\n}\n\
+             Table users {\n  id int [pk]\n  manager_id int [ref: > users.id]\n}\n\
+             Table orders {\n  user_id int\n  no int\n  indexes {\n    (user_id, no) [pk]\n  }\n}\n\
+             Table lines {\n  user_id int\n  order_no int\n  indexes {\n    user_id\n  }\n}\n\
+             Ref: orders.user_id > users.id\n\
+             Ref: lines.(user_id, order_no) > orders.(user_id, no)\n",
+        )
+        .unwrap();
+        let (table, column) = resolve_column(&diagram, "users.id").unwrap();
+        let impact = column_impact(&diagram, table, &column);
+
+        let names: Vec<String> = impact
+            .dependents
+            .iter()
+            .map(|d| format!("{}.{}", diagram.tables[d.table].id.name, d.column))
+            .collect();
+        assert_eq!(names, ["orders.user_id", "users.manager_id"]);
+        let lines = &impact.dependents[0].dependents[0];
+        assert_eq!(diagram.tables[lines.table].id.name, "lines");
+        assert_eq!(lines.column, "user_id");
+        assert_eq!(lines.indexes, [0]);
+        assert_eq!(impact.counts(), (3, 3, 2));
+        assert!(report(&diagram, &impact)
+            .ends_with("3 columns, 3 relationships, 2 indexes depend on public.users.id\n"));
+
+        assert!(resolve_column(&diagram, "users.nope").is_err());
+        assert!(resolve_column(&diagram, "users").is_err());
+    }
+}
@@ -11,13 +11,39 @@
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Diagram {
     pub tables: Vec<Table>,
     pub relationships: Vec<Relationship>,
+    #[serde(default)]
+    pub enums: Vec<Enum>,
+    #[serde(default)]
+    pub table_groups: Vec<TableGroup>,
+    #[serde(default)]
+    pub project: Option<Project>,
+    /// Top-level `Note` blocks.
+    #[serde(default)]
+    pub notes: Vec<StickyNote>,
+    /// Comments after the last top-level block.
+    #[serde(default)]
+    pub trailing_comments: Vec<String>,
+}
+
+impl Diagram {
+    /// Keep the tables `keep` accepts, the relationships between them and
+    /// their table group entries.
+    pub fn retain_tables(&mut self, keep: impl Fn(&TableId) -> bool) {
+        self.tables.retain(|t| keep(&t.id));
+        self.relationships
+            .retain(|r| keep(&r.from.table_id) && keep(&r.to.table_id));
+        for group in &mut self.table_groups {
+            group.tables.retain(|entry| keep(&entry.id));
+        }
+        self.table_groups.retain(|g| !g.tables.is_empty());
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct TableId {
     pub schema: String,
     pub name: String,
@@ -42,19 +68,176 @@ impl std::fmt::Display for TableId {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Table {
     pub id: TableId,
     pub columns: Vec<Column>,
     pub position: Option<Position>,
+    /// Junction table drawn as a many-to-many relationship between the
+    /// tables it links (see `junction`). Kept in the layout file.
+    #[serde(default)]
+    pub collapsed: bool,
+    #[serde(default)]
+    pub alias: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub indexes: Vec<Index>,
+    /// Table settings other than the note, e.g. `headercolor`.
+    #[serde(default)]
+    pub settings: Vec<Setting>,
+    /// Comments directly above the table.
+    #[serde(default)]
+    pub comments: Vec<String>,
+    /// Comments inside the table body after the last column.
+    #[serde(default)]
+    pub end_comments: Vec<String>,
+    /// Comments directly above the `Indexes` block.
+    #[serde(default)]
+    pub index_comments: Vec<String>,
+    /// Comments inside the `Indexes` block after the last index.
+    #[serde(default)]
+    pub index_end_comments: Vec<String>,
+    /// Placeholder for a table hidden by a filter, kept so relationships
+    /// into it can still be drawn. Only the referenced columns remain.
+    #[serde(default)]
+    pub is_stub: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Table {
+    /// Columns of the primary key, from column settings or a `pk` index.
+    pub fn primary_key(&self) -> Vec<&str> {
+        let mut key: Vec<&str> = self
+            .columns
+            .iter()
+            .filter(|c| c.is_pk)
+            .map(|c| c.name.as_str())
+            .collect();
+        for index in self.indexes.iter().filter(|i| i.is_pk) {
+            for column in &index.columns {
+                if let IndexColumn::Column(name) = column {
+                    if !key.contains(&name.as_str()) {
+                        key.push(name);
+                    }
+                }
+            }
+        }
+        key
+    }
+
+    /// Whether the column can be null; primary key columns never are,
+    /// whatever their settings say. Unknown columns can.
+    pub fn is_nullable(&self, column: &str) -> bool {
+        match self.columns.iter().find(|c| c.name == column) {
+            Some(c) => c.is_nullable && !self.primary_key().contains(&column),
+            None => true,
+        }
+    }
+
+    /// Whether no two rows can have the same values in `columns`: they are
+    /// the primary key, a unique column or the columns of a unique index.
+    pub fn is_unique_key(&self, columns: &[String]) -> bool {
+        let same = |key: Vec<&str>| {
+            !key.is_empty()
+                && key.len() == columns.len()
+                && columns.iter().all(|c| key.contains(&c.as_str()))
+        };
+        if same(self.primary_key()) {
+            return true;
+        }
+        if let [column] = columns {
+            if self
+                .columns
+                .iter()
+                .any(|c| c.name == *column && c.is_unique)
+            {
+                return true;
+            }
+        }
+        self.indexes.iter().filter(|i| i.is_unique).any(|index| {
+            same(
+                index
+                    .columns
+                    .iter()
+                    .filter_map(|c| match c {
+                        IndexColumn::Column(name) => Some(name.as_str()),
+                        IndexColumn::Expression(_) => None,
+                    })
+                    .collect(),
+            ) && index.columns.len() == columns.len()
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Column {
     pub name: String,
     pub type_raw: String,
     pub is_pk: bool,
     pub is_nullable: bool,
+    #[serde(default)]
+    pub is_unique: bool,
+    #[serde(default)]
+    pub is_increment: bool,
+    #[serde(default)]
+    pub default: Option<DefaultValue>,
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Comments directly above the column.
+    #[serde(default)]
+    pub comments: Vec<String>,
+    /// Comment on the same line after the column.
+    #[serde(default)]
+    pub trailing_comment: Option<String>,
+}
+
+/// Default value of a column, keeping the kind of literal it was written as.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DefaultValue {
+    String(String),
+    /// Numeric literal as written in the source.
+    Number(String),
+    Bool(bool),
+    Null,
+    /// Backquoted expression, e.g. `` `now()` ``.
+    Expression(String),
+    /// Bare identifier, e.g. an enum value.
+    Identifier(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Index {
+    pub columns: Vec<IndexColumn>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub is_unique: bool,
+    #[serde(default)]
+    pub is_pk: bool,
+    /// Index method, e.g. `btree` or `hash`.
+    #[serde(default)]
+    pub index_type: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Comments directly above the index.
+    #[serde(default)]
+    pub comments: Vec<String>,
+    /// Comment on the same line after the index.
+    #[serde(default)]
+    pub trailing_comment: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum IndexColumn {
+    Column(String),
+    Expression(String),
+}
+
+/// A `key` or `key: value` setting kept verbatim from the source.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Setting {
+    pub key: String,
+    pub value: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,6 +245,56 @@ pub struct Relationship {
     pub relation_type: RelationType,
     pub from: EndPoint,
     pub to: EndPoint,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub on_delete: Option<ReferentialAction>,
+    #[serde(default)]
+    pub on_update: Option<ReferentialAction>,
+    /// Declared inline in the column settings of `from`.
+    #[serde(default)]
+    pub is_inline: bool,
+    /// Comments directly above the `Ref`.
+    #[serde(default)]
+    pub comments: Vec<String>,
+    /// Markers at the `from` and `to` ends, inferred from the foreign key
+    /// columns by `cardinality::infer`.
+    #[serde(default)]
+    pub cardinality: [Cardinality; 2],
+}
+
+/// How many rows at one end of a relationship go with a row at the other
+/// end: one or many, and whether there can be none.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Cardinality {
+    OneMandatory,
+    OneOptional,
+    ManyMandatory,
+    #[default]
+    ManyOptional,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReferentialAction {
+    NoAction,
+    Cascade,
+    Restrict,
+    SetNull,
+    SetDefault,
+}
+
+impl ReferentialAction {
+    /// Keyword form shared by DBML and SQL, e.g. `set null`.
+    pub fn keyword(self) -> &'static str {
+        match self {
+            ReferentialAction::NoAction => "no action",
+            ReferentialAction::Cascade => "cascade",
+            ReferentialAction::Restrict => "restrict",
+            ReferentialAction::SetNull => "set null",
+            ReferentialAction::SetDefault => "set default",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -78,6 +311,117 @@ pub struct EndPoint {
     pub column_names: Vec<String>,
 }
 
+impl Relationship {
+    /// The (referencing, referenced) endpoints of the foreign key this
+    /// relationship describes. `ManyToOne` and `OneToOne` keep the key on
+    /// `from`, `OneToMany` on `to`; `ManyToMany` has no foreign key.
+    pub fn foreign_key(&self) -> Option<(&EndPoint, &EndPoint)> {
+        match self.relation_type {
+            RelationType::ManyToOne | RelationType::OneToOne => Some((&self.from, &self.to)),
+            RelationType::OneToMany => Some((&self.to, &self.from)),
+            RelationType::ManyToMany => None,
+        }
+    }
+
+    pub fn new(relation_type: RelationType, from: EndPoint, to: EndPoint) -> Self {
+        Self {
+            relation_type,
+            from,
+            to,
+            name: None,
+            on_delete: None,
+            on_update: None,
+            is_inline: false,
+            comments: Vec::new(),
+            cardinality: Default::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Enum {
+    pub schema: String,
+    pub name: String,
+    pub values: Vec<EnumValue>,
+    #[serde(default)]
+    pub comments: Vec<String>,
+    /// Comments inside the body after the last value.
+    #[serde(default)]
+    pub end_comments: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnumValue {
+    pub name: String,
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Comments directly above the value.
+    #[serde(default)]
+    pub comments: Vec<String>,
+    /// Comment on the same line after the value.
+    #[serde(default)]
+    pub trailing_comment: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableGroup {
+    pub name: String,
+    pub tables: Vec<GroupEntry>,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub comments: Vec<String>,
+    /// Comments inside the body after the last table.
+    #[serde(default)]
+    pub end_comments: Vec<String>,
+}
+
+/// A table listed in a `TableGroup`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupEntry {
+    pub id: TableId,
+    /// Comments directly above the entry.
+    #[serde(default)]
+    pub comments: Vec<String>,
+    /// Comment on the same line after the entry.
+    #[serde(default)]
+    pub trailing_comment: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Project {
+    pub name: String,
+    /// Properties such as `database_type`.
+    pub properties: Vec<Property>,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub comments: Vec<String>,
+    /// Comments inside the body after the last property.
+    #[serde(default)]
+    pub end_comments: Vec<String>,
+}
+
+/// A `key: value` project property, the value kept verbatim.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Property {
+    pub key: String,
+    pub value: String,
+    /// Comments directly above the property.
+    #[serde(default)]
+    pub comments: Vec<String>,
+    /// Comment on the same line after the property.
+    #[serde(default)]
+    pub trailing_comment: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StickyNote {
+    pub value: String,
+    #[serde(default)]
+    pub comments: Vec<String>,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Position {
     pub x: f64,
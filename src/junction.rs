@@ -0,0 +1,152 @@
+//! Junction tables: tables that only link two others many-to-many, like
+//! `post_tags(post_id, tag_id)`. Their columns are basically two foreign
+//! keys that together form the primary key.
+//!
+//! A collapsed junction is drawn as one many-to-many relationship between
+//! the tables it links. The editor gets the junctions found here as
+//! `window.__JUNCTIONS` and collapses them itself, so they can be expanded
+//! again.
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::graph::table_index;
+use crate::ir::{Diagram, RelationType, Relationship, TableId};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Junction {
+    pub table: TableId,
+    /// Its two foreign keys, by index into `Diagram::relationships`.
+    pub rels: [usize; 2],
+}
+
+impl Junction {
+    /// The many-to-many relationship the junction stands for, between the
+    /// referenced columns of its two foreign keys.
+    pub fn relationship(&self, diagram: &Diagram) -> Option<Relationship> {
+        let (_, a) = diagram.relationships[self.rels[0]].foreign_key()?;
+        let (_, b) = diagram.relationships[self.rels[1]].foreign_key()?;
+        Some(Relationship::new(
+            RelationType::ManyToMany,
+            a.clone(),
+            b.clone(),
+        ))
+    }
+}
+
+/// Tables whose only relationships are two foreign keys of their own,
+/// whose columns together are exactly the primary key. Other columns, such
+/// as a `created_at`, are allowed.
+pub fn find_junctions(diagram: &Diagram) -> Vec<Junction> {
+    let mut junctions = Vec::new();
+    for (i, table) in diagram.tables.iter().enumerate() {
+        let rels: Vec<usize> = (0..diagram.relationships.len())
+            .filter(|&r| {
+                let rel = &diagram.relationships[r];
+                rel.from.table_id == table.id || rel.to.table_id == table.id
+            })
+            .collect();
+        let [a, b] = rels[..] else {
+            continue;
+        };
+
+        // Both foreign keys from this table to another one
+        let mut fk_columns: Vec<&String> = Vec::new();
+        let mut is_junction = true;
+        for r in [a, b] {
+            match diagram.relationships[r].foreign_key() {
+                Some((child, parent))
+                    if child.table_id == table.id
+                        && parent.table_id != table.id
+                        && table_index(diagram, &parent.table_id).is_some() =>
+                {
+                    fk_columns.extend(&child.column_names);
+                }
+                _ => is_junction = false,
+            }
+        }
+
+        let key: HashSet<&str> = table.primary_key().into_iter().collect();
+        let fk_set: HashSet<&str> = fk_columns.iter().map(|c| c.as_str()).collect();
+        if is_junction && fk_set.len() == fk_columns.len() && !key.is_empty() && key == fk_set {
+            junctions.push(Junction {
+                table: diagram.tables[i].id.clone(),
+                rels: [a, b],
+            });
+        }
+    }
+    junctions
+}
+
+/// Mark every junction table collapsed.
+pub fn collapse_all(diagram: &mut Diagram) {
+    let ids: HashSet<TableId> = find_junctions(diagram)
+        .into_iter()
+        .map(|j| j.table)
+        .collect();
+    for table in &mut diagram.tables {
+        if ids.contains(&table.id) {
+            table.collapsed = true;
+        }
+    }
+}
+
+/// Replace the junction tables marked collapsed with many-to-many
+/// relationships, which come after the other relationships.
+pub fn collapse_marked(diagram: &mut Diagram) {
+    let junctions: Vec<Junction> = find_junctions(diagram)
+        .into_iter()
+        .filter(|j| table_index(diagram, &j.table).is_some_and(|i| diagram.tables[i].collapsed))
+        .collect();
+    let relationships: Vec<Relationship> = junctions
+        .iter()
+        .filter_map(|j| j.relationship(diagram))
+        .collect();
+    let ids: HashSet<TableId> = junctions.into_iter().map(|j| j.table).collect();
+    diagram.retain_tables(|id| !ids.contains(id));
+    diagram.relationships.extend(relationships);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_dbml;
+
+    #[test]
+    fn test_find_and_collapse_junctions() {
+        let mut diagram = parse_dbml(
+            "Project p {\n  database_type: 'PostgreSQL'\n}\n\
+             Table posts {\n  id int [pk]\n}\n\
+             Table tags {\n  id int [pk]\n}\n\
+             Table post_tags {\n  post_id int [ref: > posts.id]\n  tag_id int [ref: > tags.id]\n\
+               created_at timestamp\n  indexes {\n    (post_id, tag_id) [pk]\n  }\n}\n\
+             Table likes {\n  id int [pk]\n  post_id int [ref: > posts.id]\n\
+               tag_id int [ref: > tags.id]\n}\n\
+             Table follows {\n  a int [ref: > posts.id]\n  b int [ref: > posts.id]\n\
+               indexes {\n    (a, b) [pk]\n  }\n}\n",
+        )
+        .unwrap();
+
+        // likes has its own id as primary key; follows links posts to posts
+        let names: Vec<String> = find_junctions(&diagram)
+            .iter()
+            .map(|j| j.table.name.clone())
+            .collect();
+        assert_eq!(names, ["post_tags", "follows"]);
+
+        diagram.tables[2].collapsed = true;
+        collapse_marked(&mut diagram);
+        assert_eq!(diagram.tables.len(), 4);
+        let m2m = diagram.relationships.last().unwrap();
+        assert_eq!(m2m.relation_type, RelationType::ManyToMany);
+        assert_eq!(
+            (
+                m2m.from.table_id.name.as_str(),
+                m2m.to.table_id.name.as_str()
+            ),
+            ("posts", "tags")
+        );
+        assert_eq!(diagram.relationships.len(), 5);
+    }
+}
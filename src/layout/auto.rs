@@ -0,0 +1,132 @@
+//! BFS cross layout, ported from `autoLayout()` in `editor.js`.
+//!
+//! The most connected table is placed at the origin of a grid and its
+//! neighbours are placed in the free cells around it, breadth first.
+//! Disconnected tables fill the nearest free cells afterwards.
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use super::metrics::{table_height, table_width, MIN_TABLE_WIDTH};
+use crate::ir::{Diagram, Position};
+
+const SPACING_X: f64 = 120.0;
+const SPACING_Y: f64 = 100.0;
+const START_X: f64 = 50.0;
+const START_Y: f64 = 50.0;
+
+const DIRECTIONS: [(i32, i32); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+pub fn auto_layout(diagram: &mut Diagram) {
+    let n = diagram.tables.len();
+    if n == 0 {
+        return;
+    }
+
+    // Build adjacency list (by index)
+    let mut adj: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for rel in &diagram.relationships {
+        let fi = diagram
+            .tables
+            .iter()
+            .position(|t| t.id == rel.from.table_id);
+        let ti = diagram.tables.iter().position(|t| t.id == rel.to.table_id);
+        if let (Some(fi), Some(ti)) = (fi, ti) {
+            adj[fi].push(ti);
+            adj[ti].push(fi);
+        }
+    }
+
+    // Root = most connected (first one wins on ties)
+    let mut root = 0;
+    for (i, neighbours) in adj.iter().enumerate() {
+        if neighbours.len() > adj[root].len() {
+            root = i;
+        }
+    }
+
+    let mut grid: HashMap<usize, (i32, i32)> = HashMap::new();
+    let mut occupied: HashSet<(i32, i32)> = HashSet::new();
+    let mut visited: HashSet<usize> = HashSet::new();
+    let mut queue: VecDeque<(usize, i32, i32)> = VecDeque::new();
+
+    grid.insert(root, (0, 0));
+    occupied.insert((0, 0));
+    visited.insert(root);
+    queue.push_back((root, 0, 0));
+
+    while let Some((cur, cx, cy)) = queue.pop_front() {
+        for &nb in &adj[cur] {
+            if !visited.insert(nb) {
+                continue;
+            }
+
+            let free = DIRECTIONS
+                .iter()
+                .map(|(dx, dy)| (cx + dx, cy + dy))
+                .find(|cell| !occupied.contains(cell))
+                .or_else(|| find_nearest_empty(cx, cy, &occupied));
+            if let Some(cell) = free {
+                grid.insert(nb, cell);
+                occupied.insert(cell);
+                queue.push_back((nb, cell.0, cell.1));
+            }
+        }
+    }
+
+    // Place disconnected tables
+    for i in 0..n {
+        if let std::collections::hash_map::Entry::Vacant(entry) = grid.entry(i) {
+            if let Some(cell) = find_nearest_empty(0, 0, &occupied) {
+                entry.insert(cell);
+                occupied.insert(cell);
+            }
+        }
+    }
+
+    let min_col = grid.values().map(|c| c.0).min().unwrap_or(0);
+    let min_row = grid.values().map(|c| c.1).min().unwrap_or(0);
+
+    // Column widths and row heights
+    let mut col_widths: BTreeMap<i32, f64> = BTreeMap::new();
+    let mut row_heights: BTreeMap<i32, f64> = BTreeMap::new();
+    for (&i, &(col, row)) in &grid {
+        let table = &diagram.tables[i];
+        let w = col_widths.entry(col).or_insert(0.0);
+        *w = w.max(table_width(table));
+        let h = row_heights.entry(row).or_insert(0.0);
+        *h = h.max(table_height(table));
+    }
+
+    // Assign pixel positions
+    for (i, table) in diagram.tables.iter_mut().enumerate() {
+        let Some(&(col, row)) = grid.get(&i) else {
+            continue;
+        };
+        let x = START_X
+            + (min_col..col)
+                .map(|c| col_widths.get(&c).copied().unwrap_or(MIN_TABLE_WIDTH) + SPACING_X)
+                .sum::<f64>();
+        let y = START_Y
+            + (min_row..row)
+                .map(|r| row_heights.get(&r).copied().unwrap_or(200.0) + SPACING_Y)
+                .sum::<f64>();
+        table.position = Some(Position { x, y });
+    }
+}
+
+fn find_nearest_empty(cx: i32, cy: i32, occupied: &HashSet<(i32, i32)>) -> Option<(i32, i32)> {
+    for radius in 1..20i32 {
+        for dx in -radius..=radius {
+            for dy in -radius..=radius {
+                if dx.abs() != radius && dy.abs() != radius {
+                    continue;
+                }
+                let cell = (cx + dx, cy + dy);
+                if !occupied.contains(&cell) {
+                    return Some(cell);
+                }
+            }
+        }
+    }
+    None
+}
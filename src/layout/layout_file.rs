@@ -6,17 +6,24 @@ use crate::error::AppError;
 pub fn read_layout(path: &Path) -> Result<LayoutData, AppError> {
     let content = std::fs::read_to_string(path)
         .map_err(|e| AppError::LayoutError(format!("Failed to read {}: {}", path.display(), e)))?;
-    let data: LayoutData = toml::from_str(&content)
-        .map_err(|e| AppError::LayoutError(format!("Failed to parse {}: {}", path.display(), e)))?;
+    parse_layout(&content, &path.display().to_string())
+}
 
-    Ok(data)
+/// Parse layout file contents; `name` identifies them in errors.
+pub fn parse_layout(content: &str, name: &str) -> Result<LayoutData, AppError> {
+    toml::from_str(content)
+        .map_err(|e| AppError::LayoutError(format!("Failed to parse {}: {}", name, e)))
 }
 
 pub fn write_layout(path: &Path, data: &LayoutData) -> Result<(), AppError> {
-    let content = toml::to_string_pretty(data)
-        .map_err(|e| AppError::LayoutError(format!("Failed to serialize layout data: {}", e)))?;
+    let content = layout_to_string(data)?;
     std::fs::write(path, content)
         .map_err(|e| AppError::LayoutError(format!("Failed to write {}: {}", path.display(), e)))?;
 
     Ok(())
 }
+
+pub fn layout_to_string(data: &LayoutData) -> Result<String, AppError> {
+    toml::to_string_pretty(data)
+        .map_err(|e| AppError::LayoutError(format!("Failed to serialize layout data: {}", e)))
+}
@@ -0,0 +1,61 @@
+//! Table geometry for the headless exporters.
+//!
+//! Mirrors the constants in `editor.js` so exported diagrams line up with
+//! what the editor shows. There is no font rasterizer on the Rust side, so
+//! text widths are estimated from average glyph advances of the editor fonts.
+
+use crate::ir::{Diagram, Table};
+
+pub const MIN_TABLE_WIDTH: f64 = 200.0;
+pub const HEADER_HEIGHT: f64 = 38.0;
+pub const ROW_HEIGHT: f64 = 32.0;
+pub const PADDING_X: f64 = 16.0;
+
+/// Extra width reserved in front of a PK column name for the badge.
+pub const PK_BADGE_EXTRA: f64 = 36.0;
+/// Gap between the column name and the right-aligned type.
+pub const COLUMN_GAP: f64 = 16.0;
+
+// Average advance of 13px monospace / semibold sans-serif glyphs.
+const MONO_CHAR_WIDTH: f64 = 7.8;
+const SANS_CHAR_WIDTH: f64 = 7.4;
+
+pub fn mono_text_width(text: &str) -> f64 {
+    text.chars().count() as f64 * MONO_CHAR_WIDTH
+}
+
+pub fn sans_text_width(text: &str) -> f64 {
+    text.chars().count() as f64 * SANS_CHAR_WIDTH
+}
+
+/// Width of a table box, same rule as `computeTableWidths()` in the editor.
+pub fn table_width(table: &Table) -> f64 {
+    let max_row_width = table
+        .columns
+        .iter()
+        .map(|c| {
+            let name_width = mono_text_width(&c.name) + if c.is_pk { PK_BADGE_EXTRA } else { 0.0 };
+            name_width + COLUMN_GAP + mono_text_width(&c.type_raw)
+        })
+        .fold(0.0, f64::max);
+    let header_width = sans_text_width(&table.id.name);
+
+    let content_width = max_row_width.max(header_width);
+    MIN_TABLE_WIDTH.max(content_width + 2.0 * PADDING_X)
+}
+
+pub fn table_height(table: &Table) -> f64 {
+    HEADER_HEIGHT + table.columns.len() as f64 * ROW_HEIGHT
+}
+
+/// Bounding box `(min_x, min_y, max_x, max_y)` of all positioned tables.
+pub fn diagram_bounds(diagram: &Diagram) -> Option<(f64, f64, f64, f64)> {
+    diagram
+        .tables
+        .iter()
+        .filter_map(|t| {
+            let p = t.position?;
+            Some((p.x, p.y, p.x + table_width(t), p.y + table_height(t)))
+        })
+        .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+}
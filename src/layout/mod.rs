@@ -1,21 +1,70 @@
-use crate::ir::Position;
 use crate::ir::Diagram;
+use crate::ir::Position;
+use std::collections::HashMap;
 use std::path::Path;
 
+pub(crate) mod auto;
 pub(crate) mod layout_file;
+pub(crate) mod metrics;
+pub(crate) mod router;
+pub(crate) mod routing;
 pub(crate) mod types;
 
+use types::{JunctionLayout, LayoutData, LayoutMeta, TableLayout};
+
 pub fn apply_layout(diagram: &mut Diagram, layout_path: Option<&Path>) {
     if let Some(path) = layout_path {
         if path.exists() {
             if let Ok(layout_data) = layout_file::read_layout(path) {
-                for table in &mut diagram.tables {
-                    let key = table.id.full_name();
-                    if let Some(tl) = layout_data.tables.get(&key) {
-                        table.position = Some(Position { x: tl.x, y: tl.y });
-                    }
-                }
+                apply_layout_data(diagram, &layout_data);
             }
         }
     }
 }
+
+/// Position the tables listed in `layout_data` and mark the collapsed
+/// junction tables.
+pub fn apply_layout_data(diagram: &mut Diagram, layout_data: &LayoutData) {
+    for table in &mut diagram.tables {
+        let key = table.id.full_name();
+        if let Some(tl) = layout_data.tables.get(&key) {
+            table.position = Some(Position { x: tl.x, y: tl.y });
+        }
+        table.collapsed = layout_data.junctions.collapsed.contains(&key);
+    }
+}
+
+/// Give every table a position, the same way the editor does on startup:
+/// if any table has no saved position, the whole diagram is auto-laid out.
+pub fn ensure_positions(diagram: &mut Diagram) {
+    if diagram.tables.iter().any(|t| t.position.is_none()) {
+        auto::auto_layout(diagram);
+    }
+}
+
+/// Current table positions as layout file data for the source file `source`.
+pub fn layout_data(diagram: &Diagram, source: &Path) -> LayoutData {
+    let mut tables = HashMap::new();
+    let mut collapsed = Vec::new();
+    for table in &diagram.tables {
+        if let Some(pos) = &table.position {
+            tables.insert(table.id.full_name(), TableLayout { x: pos.x, y: pos.y });
+        }
+        if table.collapsed {
+            collapsed.push(table.id.full_name());
+        }
+    }
+    collapsed.sort();
+
+    LayoutData {
+        meta: LayoutMeta {
+            version: 1,
+            source: source
+                .file_name()
+                .map(|f| f.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        },
+        tables,
+        junctions: JunctionLayout { collapsed },
+    }
+}
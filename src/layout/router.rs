@@ -0,0 +1,824 @@
+//! Orthogonal relationship routing around tables.
+//!
+//! Each relationship keeps the ends `routing::compute_routes` picks and
+//! leaves them straight for `MARKER_LENGTH`, so the markers sit on a straight
+//! segment. Between those stubs it follows horizontal and vertical segments
+//! that keep `CLEARANCE` away from every table, choosing the shortest path
+//! after penalties for bends, for crossing paths routed before it and for
+//! running along them.
+//!
+//! The search runs over a sparse grid of the lines along the table edges,
+//! pushed out by the clearance, and through the stubs. A path is a list of
+//! waypoints from one end to the other with a corner at every point in
+//! between.
+//!
+//! The editor keeps one [`Router`] per window and sends it the table it is
+//! dragging; only the paths that move can affect are routed again, and the
+//! grid is updated for the moved tables rather than built again.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::metrics::{table_height, table_width};
+use super::routing::{compute_routes, Route, Side, MARKER_LENGTH};
+use crate::ir::{Diagram, Position};
+
+/// Space kept between a path and any table.
+const CLEARANCE: f64 = 16.0;
+/// Extra cost of a corner, in pixels of length.
+const BEND_COST: f64 = 30.0;
+/// Extra cost of crossing a path routed earlier.
+const CROSSING_COST: f64 = 40.0;
+/// Extra cost of a grid step along a path routed earlier.
+const OVERLAP_COST: f64 = 20.0;
+
+/// A table's box.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub w: f64,
+    pub h: f64,
+}
+
+impl Rect {
+    fn inflate(self, d: f64) -> Rect {
+        Rect {
+            x: self.x - d,
+            y: self.y - d,
+            w: self.w + 2.0 * d,
+            h: self.h + 2.0 * d,
+        }
+    }
+
+    fn contains(&self, p: Position) -> bool {
+        p.x > self.x && p.x < self.x + self.w && p.y > self.y && p.y < self.y + self.h
+    }
+
+    /// Whether the horizontal or vertical segment `a`-`b` passes through the
+    /// inside of the box.
+    fn crosses(&self, a: Position, b: Position) -> bool {
+        let (x0, x1) = (a.x.min(b.x), a.x.max(b.x));
+        let (y0, y1) = (a.y.min(b.y), a.y.max(b.y));
+        x0 < self.x + self.w && x1 > self.x && y0 < self.y + self.h && y1 > self.y
+    }
+}
+
+/// Waypoints of a routed relationship, from its `from` end to its `to` end.
+pub type Path = Vec<Position>;
+
+/// Paths of a diagram's relationships, kept so they can be updated as
+/// tables move.
+pub struct Router {
+    tables: Vec<Rect>,
+    routes: Vec<Option<Route>>,
+    paths: Vec<Option<Path>>,
+    grid: Grid,
+    search: Search,
+}
+
+impl Router {
+    /// Route every relationship among `tables`, with the ends in `routes`
+    /// (whose table indices refer to `tables`).
+    pub fn new(tables: Vec<Rect>, routes: Vec<Option<Route>>) -> Self {
+        let mut router = Self {
+            paths: vec![None; routes.len()],
+            grid: Grid::new(&tables, &routes),
+            search: Search::default(),
+            tables,
+            routes,
+        };
+        let all: Vec<usize> = (0..router.routes.len()).collect();
+        router.route(&all);
+        router
+    }
+
+    /// Route a positioned diagram as the exporters draw it.
+    pub fn for_diagram(diagram: &Diagram) -> Self {
+        let tables = diagram
+            .tables
+            .iter()
+            .map(|t| {
+                let p = t.position.unwrap_or(Position { x: 0.0, y: 0.0 });
+                Rect {
+                    x: p.x,
+                    y: p.y,
+                    w: table_width(t),
+                    h: table_height(t),
+                }
+            })
+            .collect();
+        Self::new(tables, compute_routes(diagram))
+    }
+
+    pub fn routes(&self) -> &[Option<Route>] {
+        &self.routes
+    }
+
+    /// One path per route, `None` where there is no route or no way around
+    /// the tables; those are drawn as curves.
+    pub fn paths(&self) -> &[Option<Path>] {
+        &self.paths
+    }
+
+    /// Take new table boxes and ends after the tables `moved` were moved,
+    /// routing again only what changed: routes with different ends, routes
+    /// to the moved tables and paths the moved tables now lie on. Paths
+    /// that went around a moved table keep their detour until the next
+    /// full update, with `moved` = `None`.
+    pub fn update(
+        &mut self,
+        tables: Vec<Rect>,
+        routes: Vec<Option<Route>>,
+        moved: Option<&[usize]>,
+    ) {
+        let Some(moved) = moved
+            .filter(|_| tables.len() == self.tables.len() && routes.len() == self.routes.len())
+        else {
+            *self = Self::new(tables, routes);
+            return;
+        };
+
+        let moved_boxes: Vec<Rect> = moved
+            .iter()
+            .filter_map(|&t| tables.get(t))
+            .map(|r| r.inflate(CLEARANCE))
+            .collect();
+        let dirty: Vec<usize> = (0..routes.len())
+            .filter(|&i| {
+                let (old, new) = (&self.routes[i], &routes[i]);
+                let ends_changed = match (old, new) {
+                    (Some(a), Some(b)) => !same_ends(a, b),
+                    (None, None) => false,
+                    _ => true,
+                };
+                let touches_moved = new
+                    .is_some_and(|r| moved.contains(&r.from_table) || moved.contains(&r.to_table));
+                let blocked = self.paths[i].as_ref().is_some_and(|path| {
+                    path.windows(2)
+                        .any(|s| moved_boxes.iter().any(|b| b.crosses(s[0], s[1])))
+                });
+                ends_changed || touches_moved || blocked
+            })
+            .collect();
+
+        self.grid.update(&tables, &routes);
+        self.tables = tables;
+        self.routes = routes;
+        self.route(&dirty);
+    }
+
+    /// Route `indices` again, around the paths of all the others.
+    fn route(&mut self, indices: &[usize]) {
+        let grid = &self.grid;
+        let redo: HashSet<usize> = indices.iter().copied().collect();
+        let mut usage = Usage::new(grid);
+        for (i, path) in self.paths.iter().enumerate() {
+            if let Some(path) = path.as_ref().filter(|_| !redo.contains(&i)) {
+                usage.add(grid, path);
+            }
+        }
+        for &i in indices {
+            let path = self.routes[i].and_then(|r| grid.search(&r, &usage, &mut self.search));
+            if let Some(path) = &path {
+                usage.add(grid, path);
+            }
+            self.paths[i] = path;
+        }
+    }
+}
+
+fn same_ends(a: &Route, b: &Route) -> bool {
+    a.from_side == b.from_side
+        && a.to_side == b.to_side
+        && a.from.x == b.from.x
+        && a.from.y == b.from.y
+        && a.to.x == b.to.x
+        && a.to.y == b.to.y
+}
+
+/// Halfway along a path, where labels go.
+pub fn path_middle(path: &[Position]) -> Position {
+    let length = |a: Position, b: Position| (b.x - a.x).abs() + (b.y - a.y).abs();
+    let total: f64 = path.windows(2).map(|s| length(s[0], s[1])).sum();
+    let mut left = total / 2.0;
+    for s in path.windows(2) {
+        let l = length(s[0], s[1]);
+        if l >= left && l > 0.0 {
+            let t = left / l;
+            return Position {
+                x: s[0].x + (s[1].x - s[0].x) * t,
+                y: s[0].y + (s[1].y - s[0].y) * t,
+            };
+        }
+        left -= l;
+    }
+    path.first().copied().unwrap_or(Position { x: 0.0, y: 0.0 })
+}
+
+/// Where the straight segment at an end stops.
+fn stub(at: Position, side: Side) -> Position {
+    let Position { x, y } = at;
+    match side {
+        Side::Right => Position {
+            x: x + MARKER_LENGTH,
+            y,
+        },
+        Side::Left => Position {
+            x: x - MARKER_LENGTH,
+            y,
+        },
+        Side::Bottom => Position {
+            x,
+            y: y + MARKER_LENGTH,
+        },
+        Side::Top => Position {
+            x,
+            y: y - MARKER_LENGTH,
+        },
+    }
+}
+
+/// Directions of travel, in the order of the neighbours `Grid::step` visits.
+const RIGHT: usize = 0;
+const LEFT: usize = 1;
+const DOWN: usize = 2;
+const UP: usize = 3;
+
+fn direction(side: Side) -> usize {
+    match side {
+        Side::Right => RIGHT,
+        Side::Left => LEFT,
+        Side::Bottom => DOWN,
+        Side::Top => UP,
+    }
+}
+
+fn is_horizontal(dir: usize) -> bool {
+    dir == RIGHT || dir == LEFT
+}
+
+struct Grid {
+    xs: Vec<f64>,
+    ys: Vec<f64>,
+    /// Table boxes pushed out by the clearance.
+    boxes: Vec<Rect>,
+    /// Per node, how many boxes it lies inside.
+    blocked: Vec<u16>,
+    /// Per node, how many boxes the step to its right or down neighbour
+    /// crosses.
+    right_blocked: Vec<u16>,
+    down_blocked: Vec<u16>,
+    /// Per node, how many boxes overlap the cell to its lower right.
+    cell_blocked: Vec<u16>,
+}
+
+impl Grid {
+    fn new(tables: &[Rect], routes: &[Option<Route>]) -> Self {
+        let boxes: Vec<Rect> = tables.iter().map(|r| r.inflate(CLEARANCE)).collect();
+        let (xs, ys) = lines(&boxes, routes);
+        let n = xs.len() * ys.len();
+        let mut grid = Grid {
+            blocked: vec![0; n],
+            right_blocked: vec![0; n],
+            down_blocked: vec![0; n],
+            cell_blocked: vec![0; n],
+            boxes,
+            xs,
+            ys,
+        };
+        for i in 0..grid.boxes.len() {
+            grid.mark(grid.boxes[i], true);
+        }
+        grid
+    }
+
+    /// Take new table boxes and route ends, marking again only the boxes
+    /// that moved.
+    fn update(&mut self, tables: &[Rect], routes: &[Option<Route>]) {
+        let boxes: Vec<Rect> = tables.iter().map(|r| r.inflate(CLEARANCE)).collect();
+        let moved: Vec<usize> = (0..boxes.len())
+            .filter(|&t| boxes[t] != self.boxes[t])
+            .collect();
+        for &t in &moved {
+            self.mark(self.boxes[t], false);
+        }
+        self.boxes = boxes;
+        let (xs, ys) = lines(&self.boxes, routes);
+        if xs != self.xs || ys != self.ys {
+            self.relines(xs, ys);
+        }
+        for &t in &moved {
+            self.mark(self.boxes[t], true);
+        }
+    }
+
+    /// Count box `b` in, or out of, the nodes, steps and cells it blocks.
+    fn mark(&mut self, b: Rect, add: bool) {
+        let bump = |count: &mut u16| {
+            if add {
+                *count += 1;
+            } else {
+                *count -= 1;
+            }
+        };
+        let (w, h) = (self.xs.len(), self.ys.len());
+        // Only the nodes within a box's range of lines can be inside it
+        let (x0, x1) = self.range(&self.xs, b.x, b.x + b.w);
+        let (y0, y1) = self.range(&self.ys, b.y, b.y + b.h);
+        for iy in y0..=y1.min(h - 1) {
+            for ix in x0..=x1.min(w - 1) {
+                let node = self.node(ix, iy);
+                let p = self.position(node);
+                if b.contains(p) {
+                    bump(&mut self.blocked[node]);
+                }
+                if ix + 1 < w && b.crosses(p, self.position(node + 1)) {
+                    bump(&mut self.right_blocked[node]);
+                }
+                if iy + 1 < h && b.crosses(p, self.position(node + w)) {
+                    bump(&mut self.down_blocked[node]);
+                }
+                if ix + 1 < w && iy + 1 < h && b.crosses(p, self.position(node + w + 1)) {
+                    bump(&mut self.cell_blocked[node]);
+                }
+            }
+        }
+    }
+
+    /// Switch to new lines. Every counted box has its edges on the old
+    /// lines, so it is all around or clear of the space between two of
+    /// them; a node, step or cell of the new grid takes the count of the
+    /// old node, step or cell it lies on or in.
+    fn relines(&mut self, xs: Vec<f64>, ys: Vec<f64>) {
+        use Spot::*;
+
+        let (old_w, old_h) = (self.xs.len(), self.ys.len());
+        let cols: Vec<Spot> = xs.iter().map(|&x| Spot::find(&self.xs, x)).collect();
+        let rows: Vec<Spot> = ys.iter().map(|&y| Spot::find(&self.ys, y)).collect();
+        let old = |counts: &[u16], ix: usize, iy: usize| counts[iy * old_w + ix];
+
+        let (w, h) = (xs.len(), ys.len());
+        let mut blocked = vec![0; w * h];
+        let mut right_blocked = vec![0; w * h];
+        let mut down_blocked = vec![0; w * h];
+        let mut cell_blocked = vec![0; w * h];
+        for (iy, &row) in rows.iter().enumerate() {
+            for (ix, &col) in cols.iter().enumerate() {
+                let node = iy * w + ix;
+                let (gap_x, gap_y) = (col.gap(old_w), row.gap(old_h));
+                blocked[node] = match (col, row) {
+                    (On(i), On(j)) => old(&self.blocked, i, j),
+                    (Between(i), On(j)) => old(&self.right_blocked, i, j),
+                    (On(i), Between(j)) => old(&self.down_blocked, i, j),
+                    (Between(i), Between(j)) => old(&self.cell_blocked, i, j),
+                    _ => 0,
+                };
+                if ix + 1 < w {
+                    right_blocked[node] = match (gap_x, row) {
+                        (Some(i), On(j)) => old(&self.right_blocked, i, j),
+                        (Some(i), Between(j)) => old(&self.cell_blocked, i, j),
+                        _ => 0,
+                    };
+                }
+                if iy + 1 < h {
+                    down_blocked[node] = match (col, gap_y) {
+                        (On(i), Some(j)) => old(&self.down_blocked, i, j),
+                        (Between(i), Some(j)) => old(&self.cell_blocked, i, j),
+                        _ => 0,
+                    };
+                }
+                if ix + 1 < w && iy + 1 < h {
+                    if let (Some(i), Some(j)) = (gap_x, gap_y) {
+                        cell_blocked[node] = old(&self.cell_blocked, i, j);
+                    }
+                }
+            }
+        }
+
+        self.xs = xs;
+        self.ys = ys;
+        self.blocked = blocked;
+        self.right_blocked = right_blocked;
+        self.down_blocked = down_blocked;
+        self.cell_blocked = cell_blocked;
+    }
+
+    /// Indices of the lines from the one before `lo` to the one at `hi`.
+    fn range(&self, lines: &[f64], lo: f64, hi: f64) -> (usize, usize) {
+        let start = lines.partition_point(|&v| v < lo).saturating_sub(1);
+        let end = lines.partition_point(|&v| v <= hi);
+        (start, end)
+    }
+
+    fn node(&self, ix: usize, iy: usize) -> usize {
+        iy * self.xs.len() + ix
+    }
+
+    fn position(&self, node: usize) -> Position {
+        Position {
+            x: self.xs[node % self.xs.len()],
+            y: self.ys[node / self.xs.len()],
+        }
+    }
+
+    fn find(&self, p: Position) -> Option<usize> {
+        let line = |lines: &[f64], v: f64| {
+            let i = lines.partition_point(|&l| l < v);
+            (lines.get(i) == Some(&v)).then_some(i)
+        };
+        Some(self.node(line(&self.xs, p.x)?, line(&self.ys, p.y)?))
+    }
+
+    /// The neighbour of `node` in direction `dir`, if the step is free.
+    fn step(&self, node: usize, dir: usize) -> Option<usize> {
+        let w = self.xs.len();
+        let (ix, iy) = (node % w, node / w);
+        let next = match dir {
+            RIGHT if ix + 1 < w && self.right_blocked[node] == 0 => node + 1,
+            LEFT if ix > 0 && self.right_blocked[node - 1] == 0 => node - 1,
+            DOWN if iy + 1 < self.ys.len() && self.down_blocked[node] == 0 => node + w,
+            UP if iy > 0 && self.down_blocked[node - w] == 0 => node - w,
+            _ => return None,
+        };
+        (self.blocked[next] == 0).then_some(next)
+    }
+
+    /// Cheapest path between the stubs of `route`, with its ends added.
+    fn search(&self, route: &Route, usage: &Usage, search: &mut Search) -> Option<Path> {
+        let start = self.find(stub(route.from, route.from_side))?;
+        let goal = self.find(stub(route.to, route.to_side))?;
+        if self.blocked[start] > 0 || self.blocked[goal] > 0 {
+            return None;
+        }
+        let start_dir = direction(route.from_side);
+        // Arriving towards the table, opposite to the side's direction
+        let goal_dir = direction(route.to_side) ^ 1;
+        let goal_at = self.position(goal);
+        let estimate = |node: usize| {
+            let p = self.position(node);
+            (p.x - goal_at.x).abs() + (p.y - goal_at.y).abs()
+        };
+
+        // States are (node, direction of arrival)
+        search.reset(self.blocked.len() * 4);
+        let Search {
+            cost,
+            previous,
+            touched,
+            open,
+        } = search;
+        let first = start * 4 + start_dir;
+        cost[first] = 0.0;
+        touched.push(first);
+        open.push(Open {
+            priority: estimate(start),
+            state: first,
+        });
+
+        let mut reached = None;
+        while let Some(Open { priority, state }) = open.pop() {
+            let (node, dir) = (state / 4, state % 4);
+            if priority - estimate(node) > cost[state] {
+                continue;
+            }
+            if node == goal {
+                reached = Some(state);
+                break;
+            }
+            for next_dir in [RIGHT, LEFT, DOWN, UP] {
+                // No turning back
+                if next_dir == dir ^ 1 {
+                    continue;
+                }
+                let Some(next) = self.step(node, next_dir) else {
+                    continue;
+                };
+                let (a, b) = (self.position(node), self.position(next));
+                let mut step_cost = (b.x - a.x).abs() + (b.y - a.y).abs();
+                if next_dir != dir {
+                    step_cost += BEND_COST;
+                }
+                if next == goal && next_dir != goal_dir {
+                    step_cost += BEND_COST;
+                }
+                step_cost += usage.cost(self, node, next_dir);
+                let next_state = next * 4 + next_dir;
+                let total = cost[state] + step_cost;
+                if total < cost[next_state] {
+                    cost[next_state] = total;
+                    previous[next_state] = state;
+                    touched.push(next_state);
+                    open.push(Open {
+                        priority: total + estimate(next),
+                        state: next_state,
+                    });
+                }
+            }
+        }
+
+        let mut state = reached?;
+        let mut nodes = vec![state / 4];
+        while state != first {
+            state = previous[state];
+            nodes.push(state / 4);
+        }
+        nodes.reverse();
+
+        let mut path = vec![route.from];
+        path.extend(nodes.into_iter().map(|n| self.position(n)));
+        path.push(route.to);
+        Some(corners(path))
+    }
+}
+
+/// The lines along the boxes' edges and through the route stubs, sorted.
+fn lines(boxes: &[Rect], routes: &[Option<Route>]) -> (Vec<f64>, Vec<f64>) {
+    let mut xs = Vec::new();
+    let mut ys = Vec::new();
+    for b in boxes {
+        xs.extend([b.x, b.x + b.w]);
+        ys.extend([b.y, b.y + b.h]);
+    }
+    for r in routes.iter().flatten() {
+        for p in [stub(r.from, r.from_side), stub(r.to, r.to_side)] {
+            xs.push(p.x);
+            ys.push(p.y);
+        }
+    }
+    for v in [&mut xs, &mut ys] {
+        v.sort_by(f64::total_cmp);
+        v.dedup();
+    }
+    (xs, ys)
+}
+
+/// Where a line of a new grid lies among the lines of the old one.
+#[derive(Clone, Copy)]
+enum Spot {
+    On(usize),
+    /// Between the old lines `i` and `i + 1`.
+    Between(usize),
+    Outside,
+}
+
+impl Spot {
+    fn find(lines: &[f64], v: f64) -> Spot {
+        let i = lines.partition_point(|&l| l < v);
+        if lines.get(i) == Some(&v) {
+            Spot::On(i)
+        } else if i == 0 || i == lines.len() {
+            Spot::Outside
+        } else {
+            Spot::Between(i - 1)
+        }
+    }
+
+    /// The space between old lines that a step or cell starting here lies
+    /// in, out of `lines` lines.
+    fn gap(self, lines: usize) -> Option<usize> {
+        match self {
+            Spot::On(i) if i + 1 < lines => Some(i),
+            Spot::Between(i) => Some(i),
+            _ => None,
+        }
+    }
+}
+
+/// Buffers of `Grid::search`, kept from one search to the next.
+#[derive(Default)]
+struct Search {
+    /// Per state, the cheapest cost found and the state it was reached from.
+    cost: Vec<f64>,
+    previous: Vec<usize>,
+    /// States whose cost was set since the last reset.
+    touched: Vec<usize>,
+    open: BinaryHeap<Open>,
+}
+
+impl Search {
+    /// Get ready to search `states` states.
+    fn reset(&mut self, states: usize) {
+        if self.cost.len() == states {
+            for state in self.touched.drain(..) {
+                self.cost[state] = f64::INFINITY;
+            }
+        } else {
+            self.cost.clear();
+            self.cost.resize(states, f64::INFINITY);
+            self.previous.resize(states, usize::MAX);
+            self.touched.clear();
+        }
+        self.open.clear();
+    }
+}
+
+/// Drop the points in the middle of straight runs.
+fn corners(points: Path) -> Path {
+    let mut out: Path = Vec::with_capacity(points.len());
+    for p in points {
+        if let [.., a, b] = out[..] {
+            let straight = (a.x == b.x && b.x == p.x) || (a.y == b.y && b.y == p.y);
+            if straight {
+                out.pop();
+            }
+        }
+        if out.last().is_some_and(|l| l.x == p.x && l.y == p.y) {
+            continue;
+        }
+        out.push(p);
+    }
+    out
+}
+
+/// Entry of the search queue, cheapest first.
+struct Open {
+    priority: f64,
+    state: usize,
+}
+
+impl PartialEq for Open {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Open {}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Open {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .priority
+            .total_cmp(&self.priority)
+            .then_with(|| other.state.cmp(&self.state))
+    }
+}
+
+/// Grid steps taken by the paths routed so far.
+struct Usage {
+    /// Per node, how many paths step to its right neighbour or down.
+    right: Vec<u16>,
+    down: Vec<u16>,
+}
+
+impl Usage {
+    fn new(grid: &Grid) -> Self {
+        Self {
+            right: vec![0; grid.blocked.len()],
+            down: vec![0; grid.blocked.len()],
+        }
+    }
+
+    fn add(&mut self, grid: &Grid, path: &[Position]) {
+        let w = grid.xs.len();
+        // The end segments are not on the grid
+        let inner = &path[1.min(path.len())..path.len().saturating_sub(1)];
+        for s in inner.windows(2) {
+            let (Some(mut a), Some(b)) = (grid.find(s[0]), grid.find(s[1])) else {
+                continue;
+            };
+            let dir = if a % w == b % w {
+                if a < b {
+                    DOWN
+                } else {
+                    UP
+                }
+            } else if a < b {
+                RIGHT
+            } else {
+                LEFT
+            };
+            while a != b {
+                let next = match dir {
+                    RIGHT => a + 1,
+                    LEFT => a - 1,
+                    DOWN => a + w,
+                    _ => a - w,
+                };
+                match dir {
+                    RIGHT => self.right[a] += 1,
+                    LEFT => self.right[next] += 1,
+                    DOWN => self.down[a] += 1,
+                    _ => self.down[next] += 1,
+                }
+                a = next;
+            }
+        }
+    }
+
+    /// Penalty of stepping from `node` in direction `dir`: running along
+    /// earlier paths, and crossing them at the node stepped to.
+    fn cost(&self, grid: &Grid, node: usize, dir: usize) -> f64 {
+        let w = grid.xs.len();
+        let (along, next) = match dir {
+            RIGHT => (self.right[node], node + 1),
+            LEFT => (self.right[node - 1], node - 1),
+            DOWN => (self.down[node], node + w),
+            _ => (self.down[node - w], node - w),
+        };
+        let mut cost = along as f64 * OVERLAP_COST;
+        let across = if is_horizontal(dir) {
+            self.down[next] > 0 && next >= w && self.down[next - w] > 0
+        } else {
+            self.right[next] > 0 && next % w > 0 && self.right[next - 1] > 0
+        };
+        if across {
+            cost += CROSSING_COST;
+        }
+        cost
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_dbml;
+
+    #[test]
+    fn test_paths_go_around_tables() {
+        let mut d = parse_dbml(
+            "Project p {\n  database_type: 'PostgreSQL'\n}\n\
+             Table users {\n  id int [pk]\n}\n\
+             Table wall {\n  id int [pk]\n  a int\n  b int\n  c int\n}\n\
+             Table posts {\n  id int [pk]\n  user_id int [ref: > users.id]\n}\n",
+        )
+        .unwrap();
+        // wall is out of the way at first
+        for (table, x) in d.tables.iter_mut().zip([0.0, 300.0, 600.0]) {
+            table.position = Some(Position { x, y: 0.0 });
+        }
+        d.tables[1].position = Some(Position { x: 300.0, y: 400.0 });
+        let mut router = Router::for_diagram(&d);
+        // At most two corners between rows at different heights
+        assert!(router.paths()[0].as_ref().unwrap().len() <= 4);
+
+        // Moving it between users and posts routes the path around it
+        d.tables[1].position = Some(Position { x: 300.0, y: 0.0 });
+        let tables = Router::for_diagram(&d).tables;
+        router.update(tables, compute_routes(&d), Some(&[1]));
+        let path = router.paths()[0].clone().unwrap();
+        let route = router.routes()[0].unwrap();
+
+        assert_eq!((path[0].x, path[0].y), (route.from.x, route.from.y));
+        let last = path[path.len() - 1];
+        assert_eq!((last.x, last.y), (route.to.x, route.to.y));
+        // Only horizontal and vertical segments, none through a table
+        let wall = Rect {
+            x: 300.0,
+            y: 0.0,
+            w: table_width(&d.tables[1]),
+            h: table_height(&d.tables[1]),
+        };
+        for s in path.windows(2) {
+            assert!(s[0].x == s[1].x || s[0].y == s[1].y);
+            assert!(!wall.crosses(s[0], s[1]));
+        }
+        assert!(path.len() > 4);
+    }
+
+    #[test]
+    fn test_updated_grid_matches_a_new_one() {
+        let mut d = parse_dbml(
+            "Project p {\n  database_type: 'PostgreSQL'\n}\n\
+             Table users {\n  id int [pk]\n}\n\
+             Table wall {\n  id int [pk]\n  a int\n}\n\
+             Table posts {\n  id int [pk]\n  user_id int [ref: > users.id]\n}\n",
+        )
+        .unwrap();
+        for (table, x) in d.tables.iter_mut().zip([0.0, 300.0, 600.0]) {
+            table.position = Some(Position { x, y: 0.0 });
+        }
+        let mut grid = Router::for_diagram(&d).grid;
+        // Drag wall across the others, then onto users
+        for (x, y) in [(300.0, 150.0), (450.0, 20.0), (620.0, 40.0), (10.0, 0.0)] {
+            d.tables[1].position = Some(Position { x, y });
+            let fresh = Router::for_diagram(&d);
+            grid.update(&fresh.tables, &fresh.routes);
+            let expected = fresh.grid;
+            assert_eq!((&grid.xs, &grid.ys), (&expected.xs, &expected.ys));
+            assert_eq!(grid.blocked, expected.blocked);
+            assert_eq!(grid.right_blocked, expected.right_blocked);
+            assert_eq!(grid.down_blocked, expected.down_blocked);
+            assert_eq!(grid.cell_blocked, expected.cell_blocked);
+        }
+    }
+
+    #[test]
+    fn test_path_middle() {
+        let path = [
+            Position { x: 0.0, y: 0.0 },
+            Position { x: 10.0, y: 0.0 },
+            Position { x: 10.0, y: 10.0 },
+        ];
+        let middle = path_middle(&path);
+        assert_eq!((middle.x, middle.y), (10.0, 0.0));
+    }
+}
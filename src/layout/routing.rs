@@ -0,0 +1,378 @@
+//! Relationship routing, ported from `computeRoutes()` and
+//! `distributeConnectionPoints()` in `editor.js`.
+//!
+//! Each relationship leaves and enters a table on one side. Horizontally
+//! separated tables connect left/right at the row of the first endpoint
+//! column; overlapping ones connect top/bottom. When several endpoints share
+//! a side of a table they are spread evenly along it.
+//!
+//! A relationship from a table to itself loops out of its left or right
+//! side, whichever has fewer other relationships.
+//! Several relationships between the same two tables have their curves
+//! pushed apart, and these and self-loops are labelled with their foreign
+//! key columns, since the ends alone no longer tell them apart.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::metrics::{table_height, table_width, HEADER_HEIGHT, ROW_HEIGHT};
+use crate::ir::{Diagram, Position, Relationship, Table};
+
+/// Length of the straight segment at each end, where the markers go.
+pub const MARKER_LENGTH: f64 = 24.0;
+/// How far a self-loop reaches out past its markers.
+const LOOP_SIZE: f64 = 40.0;
+/// Distance between the curves of parallel relationships.
+const PARALLEL_GAP: f64 = 24.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Side {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl Side {
+    /// Direction pointing away from the table, in radians.
+    pub fn angle(self) -> f64 {
+        match self {
+            Side::Right => 0.0,
+            Side::Left => std::f64::consts::PI,
+            Side::Bottom => std::f64::consts::FRAC_PI_2,
+            Side::Top => -std::f64::consts::FRAC_PI_2,
+        }
+    }
+
+    fn is_horizontal(self) -> bool {
+        matches!(self, Side::Left | Side::Right)
+    }
+}
+
+/// Ends of a relationship. The editor sends its own to be routed (see
+/// `router::Router`), with the same field names.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Route {
+    /// Table indices of the `from` and `to` endpoints.
+    pub from_table: usize,
+    pub to_table: usize,
+    pub from_side: Side,
+    pub to_side: Side,
+    pub from: Position,
+    pub to: Position,
+    /// How far the middle of the curve is moved to keep it apart from
+    /// parallel relationships; for a self-loop, how far it reaches out.
+    pub offset: f64,
+    /// Whether the route carries its foreign key columns as a label.
+    pub labelled: bool,
+}
+
+impl Route {
+    /// Both ends leave through the left or right sides.
+    pub fn is_horizontal(&self) -> bool {
+        self.from_side.is_horizontal() && self.to_side.is_horizontal()
+    }
+
+    /// Both ends leave through the top or bottom sides.
+    pub fn is_vertical(&self) -> bool {
+        !self.from_side.is_horizontal() && !self.to_side.is_horizontal()
+    }
+
+    pub fn is_self_loop(&self) -> bool {
+        self.from_table == self.to_table
+    }
+
+    /// The bezier between the straight end segments, as its four points.
+    /// Same as `routeCurve()` in `editor.js`.
+    pub fn curve(&self) -> [Position; 4] {
+        let (fa, ta) = (self.from_side.angle(), self.to_side.angle());
+        let start = Position {
+            x: self.from.x + fa.cos() * MARKER_LENGTH,
+            y: self.from.y + fa.sin() * MARKER_LENGTH,
+        };
+        let end = Position {
+            x: self.to.x + ta.cos() * MARKER_LENGTH,
+            y: self.to.y + ta.sin() * MARKER_LENGTH,
+        };
+        let at = |x, y| Position { x, y };
+
+        if self.is_self_loop() {
+            let x = if self.from_side == Side::Left {
+                start.x.min(end.x) - self.offset
+            } else {
+                start.x.max(end.x) + self.offset
+            };
+            [start, at(x, start.y), at(x, end.y), end]
+        } else if self.is_horizontal() {
+            let mid_x = (start.x + end.x) / 2.0 + self.offset;
+            [start, at(mid_x, start.y), at(mid_x, end.y), end]
+        } else if self.is_vertical() {
+            let mid_y = (start.y + end.y) / 2.0 + self.offset;
+            [start, at(start.x, mid_y), at(end.x, mid_y), end]
+        } else {
+            [start, at(end.x, start.y), at(end.x, end.y), end]
+        }
+    }
+
+    /// Halfway along the curve, where labels go.
+    pub fn middle(&self) -> Position {
+        let [p0, p1, p2, p3] = self.curve();
+        Position {
+            x: (p0.x + 3.0 * p1.x + 3.0 * p2.x + p3.x) / 8.0,
+            y: (p0.y + 3.0 * p1.y + 3.0 * p2.y + p3.y) / 8.0,
+        }
+    }
+}
+
+/// Label of a relationship that needs one: its foreign key columns, like
+/// `created_by`.
+pub fn column_label(rel: &Relationship) -> String {
+    let (child, _) = rel.foreign_key().unwrap_or((&rel.from, &rel.to));
+    child.column_names.join(", ")
+}
+
+/// One route per relationship, `None` where a table is missing.
+///
+/// Tables must already have positions (see `layout::ensure_positions`).
+pub fn compute_routes(diagram: &Diagram) -> Vec<Option<Route>> {
+    let mut routes: Vec<Option<Route>> = diagram
+        .relationships
+        .iter()
+        .map(|rel| {
+            let fi = diagram
+                .tables
+                .iter()
+                .position(|t| t.id == rel.from.table_id)?;
+            let ti = diagram
+                .tables
+                .iter()
+                .position(|t| t.id == rel.to.table_id)?;
+            let (ft, tt) = (&diagram.tables[fi], &diagram.tables[ti]);
+            let (from_side, to_side) = if fi == ti {
+                (Side::Right, Side::Right)
+            } else {
+                determine_sides(ft, tt)
+            };
+            let from_col = rel.from.column_names.first().map(String::as_str);
+            let to_col = rel.to.column_names.first().map(String::as_str);
+            Some(Route {
+                from_table: fi,
+                to_table: ti,
+                from_side,
+                to_side,
+                from: connection_point(ft, from_side, from_col),
+                to: connection_point(tt, to_side, to_col),
+                offset: 0.0,
+                labelled: false,
+            })
+        })
+        .collect();
+
+    place_self_loops(diagram, &mut routes);
+    distribute_connection_points(diagram, &mut routes);
+    separate_parallel(&mut routes);
+    routes
+}
+
+/// Move self-loops to the left side of tables with more relationships on
+/// the right, so they stay out of the way.
+fn place_self_loops(diagram: &Diagram, routes: &mut [Option<Route>]) {
+    let mut counts: BTreeMap<(usize, Side), usize> = BTreeMap::new();
+    for r in routes.iter().flatten().filter(|r| !r.is_self_loop()) {
+        *counts.entry((r.from_table, r.from_side)).or_default() += 1;
+        *counts.entry((r.to_table, r.to_side)).or_default() += 1;
+    }
+    for (rel, route) in diagram.relationships.iter().zip(routes.iter_mut()) {
+        let Some(r) = route.as_mut().filter(|r| r.is_self_loop()) else {
+            continue;
+        };
+        let count = |side| counts.get(&(r.from_table, side)).copied().unwrap_or(0);
+        if count(Side::Right) > count(Side::Left) {
+            let table = &diagram.tables[r.from_table];
+            let from_col = rel.from.column_names.first().map(String::as_str);
+            let to_col = rel.to.column_names.first().map(String::as_str);
+            r.from_side = Side::Left;
+            r.to_side = Side::Left;
+            r.from = connection_point(table, Side::Left, from_col);
+            r.to = connection_point(table, Side::Left, to_col);
+        }
+    }
+}
+
+/// Push apart the curves of relationships between the same two tables,
+/// and nest the self-loops of a table.
+fn separate_parallel(routes: &mut [Option<Route>]) {
+    let mut groups: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+    for (i, route) in routes.iter().enumerate() {
+        let Some(r) = route else { continue };
+        let pair = (r.from_table.min(r.to_table), r.from_table.max(r.to_table));
+        groups.entry(pair).or_default().push(i);
+    }
+
+    for ((a, b), indices) in groups {
+        let count = indices.len() as f64;
+        for (k, i) in indices.into_iter().enumerate() {
+            let Some(r) = routes[i].as_mut() else {
+                continue;
+            };
+            if a == b {
+                r.offset = LOOP_SIZE + k as f64 * PARALLEL_GAP;
+                r.labelled = true;
+            } else if count > 1.0 {
+                r.offset = (k as f64 - (count - 1.0) / 2.0) * PARALLEL_GAP;
+                r.labelled = true;
+            }
+        }
+    }
+}
+
+fn position(table: &Table) -> Position {
+    table.position.unwrap_or(Position { x: 0.0, y: 0.0 })
+}
+
+fn determine_sides(from: &Table, to: &Table) -> (Side, Side) {
+    let (fp, tp) = (position(from), position(to));
+    let (fw, tw) = (table_width(from), table_width(to));
+    let h_overlap = fp.x < tp.x + tw && tp.x < fp.x + fw;
+    if h_overlap {
+        let from_cy = fp.y + table_height(from) / 2.0;
+        let to_cy = tp.y + table_height(to) / 2.0;
+        return if from_cy < to_cy {
+            (Side::Bottom, Side::Top)
+        } else {
+            (Side::Top, Side::Bottom)
+        };
+    }
+    if fp.x < tp.x {
+        (Side::Right, Side::Left)
+    } else {
+        (Side::Left, Side::Right)
+    }
+}
+
+fn column_row_y(table: &Table, column: Option<&str>) -> f64 {
+    let i = column
+        .and_then(|name| table.columns.iter().position(|c| c.name == name))
+        .unwrap_or(0);
+    HEADER_HEIGHT + i as f64 * ROW_HEIGHT + ROW_HEIGHT / 2.0
+}
+
+fn connection_point(table: &Table, side: Side, column: Option<&str>) -> Position {
+    let p = position(table);
+    let w = table_width(table);
+    match side {
+        Side::Left => Position {
+            x: p.x,
+            y: p.y + column_row_y(table, column),
+        },
+        Side::Right => Position {
+            x: p.x + w,
+            y: p.y + column_row_y(table, column),
+        },
+        Side::Top => Position {
+            x: p.x + w / 2.0,
+            y: p.y,
+        },
+        Side::Bottom => Position {
+            x: p.x + w / 2.0,
+            y: p.y + table_height(table),
+        },
+    }
+}
+
+fn distribute_connection_points(diagram: &Diagram, routes: &mut [Option<Route>]) {
+    // (table, side) -> [(route index, is the `from` end)], in route order
+    let mut groups: BTreeMap<(usize, Side), Vec<(usize, bool)>> = BTreeMap::new();
+    for (i, route) in routes.iter().enumerate() {
+        let Some(r) = route else { continue };
+        groups
+            .entry((r.from_table, r.from_side))
+            .or_default()
+            .push((i, true));
+        groups
+            .entry((r.to_table, r.to_side))
+            .or_default()
+            .push((i, false));
+    }
+
+    for ((ti, side), entries) in groups {
+        if entries.len() <= 1 {
+            continue;
+        }
+        let table = &diagram.tables[ti];
+        let p = position(table);
+        let count = entries.len() as f64;
+
+        for (j, (ri, is_from)) in entries.into_iter().enumerate() {
+            let Some(r) = routes[ri].as_mut() else {
+                continue;
+            };
+            let point = if is_from { &mut r.from } else { &mut r.to };
+            let t = (j + 1) as f64 / (count + 1.0);
+            if side.is_horizontal() {
+                let range_y = table_height(table) - HEADER_HEIGHT;
+                point.y = p.y + HEADER_HEIGHT + range_y * t;
+            } else {
+                point.x = p.x + table_width(table) * t;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout;
+    use crate::parser::parse_dbml;
+
+    #[test]
+    fn test_routes_attach_to_sides() {
+        let mut d = parse_dbml(include_str!("../../tests/fixtures/basic.dbml")).unwrap();
+        layout::ensure_positions(&mut d);
+        let routes = compute_routes(&d);
+        assert_eq!(routes.len(), 4);
+
+        for route in routes.iter().flatten() {
+            let from = &d.tables[route.from_table];
+            let p = from.position.unwrap();
+            match route.from_side {
+                Side::Left => assert_eq!(route.from.x, p.x),
+                Side::Right => assert_eq!(route.from.x, p.x + table_width(from)),
+                Side::Top => assert_eq!(route.from.y, p.y),
+                Side::Bottom => assert_eq!(route.from.y, p.y + table_height(from)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_self_loops_and_parallel_routes() {
+        let mut d = parse_dbml(
+            "Project p {\n  database_type: 'PostgreSQL'\n}\n\
+             Table users {\n  id int [pk]\n  manager_id int [ref: > users.id]\n}\n\
+             Table posts {\n  id int [pk]\n  created_by int [ref: > users.id]\n\
+               updated_by int [ref: > users.id]\n}\n",
+        )
+        .unwrap();
+        d.tables[0].position = Some(Position { x: 0.0, y: 0.0 });
+        d.tables[1].position = Some(Position { x: 400.0, y: 0.0 });
+        let routes: Vec<Route> = compute_routes(&d).into_iter().flatten().collect();
+        let labels: Vec<String> = d.relationships.iter().map(column_label).collect();
+        assert_eq!(labels, ["manager_id", "created_by", "updated_by"]);
+
+        let manager = routes[0];
+        assert!(manager.is_self_loop() && manager.labelled);
+        assert_ne!(manager.from.y, manager.to.y);
+        // posts is to the right, so the loop goes out of the left side
+        assert_eq!(
+            (manager.from_side, manager.to_side),
+            (Side::Left, Side::Left)
+        );
+        assert!(manager.middle().x < -MARKER_LENGTH);
+
+        let (created, updated) = (routes[1], routes[2]);
+        assert!(created.labelled && updated.labelled);
+        assert_eq!(created.offset, -updated.offset);
+        assert_ne!(created.middle().x, updated.middle().x);
+    }
+}
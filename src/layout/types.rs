@@ -13,6 +13,9 @@
 //! [tables."public.posts"]
 //! x = 450.0
 //! y = 200.0
+//!
+//! [junctions]
+//! collapsed = ["public.post_tags"]
 //! ```
 
 use serde::{Deserialize, Serialize};
@@ -23,6 +26,20 @@ use std::collections::HashMap;
 pub struct LayoutData {
     pub meta: LayoutMeta,
     pub tables: HashMap<String, TableLayout>, // Key is "schema.table" (e.g., "public.users")
+    #[serde(default, skip_serializing_if = "JunctionLayout::is_empty")]
+    pub junctions: JunctionLayout,
+}
+
+/// Junction tables drawn as many-to-many relationships.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JunctionLayout {
+    pub collapsed: Vec<String>,
+}
+
+impl JunctionLayout {
+    pub fn is_empty(&self) -> bool {
+        self.collapsed.is_empty()
+    }
 }
 
 /// Metadata about the layout file.
@@ -1,34 +1,396 @@
+mod cardinality;
 mod cli;
+mod config;
+mod diff;
+mod docs;
 mod editor;
+mod embed;
 mod error;
+mod export;
+mod filter;
+mod focus;
+mod git;
+mod graph;
+mod impact;
 mod ir;
+mod junction;
 mod layout;
+mod order;
 mod parser;
+mod path;
+mod stats;
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use clap::Parser;
 
-use cli::{Cli, Commands};
+use cli::{Cli, Commands, FilterArgs, FocusArgs, ImageArgs, RenameArgs, Versions};
+use config::ProjectConfig;
+use docs::DocsFormat;
 use error::AppError;
+use export::sql::Dialect;
+use export::ExportFormat;
+use parser::InputFormat;
+use stats::StatsFormat;
 
 fn main() -> Result<(), AppError> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Open { input } => cmd_open(input)?,
+        Commands::Open {
+            input,
+            from,
+            filter,
+            focus,
+        } => cmd_open(input, from, filter, focus)?,
+        Commands::Export {
+            input,
+            from,
+            format,
+            output,
+            image,
+            filter,
+            focus,
+        } => cmd_export(input, from, format, output, image, filter, focus)?,
+        Commands::Fmt { input, check } => cmd_fmt(input, check)?,
+        Commands::Sql {
+            input,
+            from,
+            dialect,
+            output,
+        } => cmd_sql(input, from, dialect, output)?,
+        Commands::Docs {
+            input,
+            from,
+            output,
+            format,
+        } => cmd_docs(input, from, output, format)?,
+        Commands::Path {
+            input,
+            table_a,
+            table_b,
+            from,
+            limit,
+        } => cmd_path(input, table_a, table_b, from, limit)?,
+        Commands::Stats {
+            input,
+            from,
+            format,
+        } => cmd_stats(input, from, format)?,
+        Commands::Impact {
+            input,
+            column,
+            from,
+        } => cmd_impact(input, column, from)?,
+        Commands::Order {
+            input,
+            from,
+            reverse,
+        } => cmd_order(input, from, reverse)?,
+        Commands::Diff {
+            versions,
+            from,
+            output,
+            image,
+        } => cmd_diff(versions, from, output, image)?,
+        Commands::Migrate {
+            versions,
+            from,
+            dialect,
+            renames,
+            output,
+        } => cmd_migrate(versions, from, dialect, renames, output)?,
     }
 
     Ok(())
 }
 
-fn cmd_open(input: PathBuf) -> Result<(), AppError> {
-    let dbml_content = std::fs::read_to_string(&input)?;
-    let mut diagram = parser::parse_dbml(&dbml_content)?;
+fn cmd_open(
+    input: PathBuf,
+    from: Option<InputFormat>,
+    filter: FilterArgs,
+    focus: FocusArgs,
+) -> Result<(), AppError> {
+    // An exported PNG or SVG opens as the DBML and layout embedded in it
+    let input = match embed::read_image(&input)? {
+        Some(embedded) => {
+            let dbml_path = embed::extract(&input, &embedded)?;
+            println!("Extracted: {}", dbml_path.display());
+            dbml_path
+        }
+        None => input,
+    };
+
+    let mut diagram = parser::load(&input, from)?;
 
     // Derive layout file path from input (e.g., schema.dbml -> schema.layout.toml)
     let layout_path = input.with_extension("layout.toml");
     layout::apply_layout(&mut diagram, Some(layout_path.as_path()));
 
-    editor::open_editor(diagram, input, layout_path)
+    // The editor shows the filtered tables but keeps the whole diagram, so
+    // positions of hidden tables are saved too; the focus view can be left
+    let config = ProjectConfig::for_input(&input)?;
+    let mut view = diagram.clone();
+    table_filter(&config, &filter).apply(&mut view);
+    let focus = focus.focus(&view)?;
+    editor::open_editor(diagram, view, input, layout_path, config, focus)
+}
+
+fn cmd_export(
+    input: PathBuf,
+    from: Option<InputFormat>,
+    format: ExportFormat,
+    output: Option<PathBuf>,
+    image: ImageArgs,
+    filter: FilterArgs,
+    focus: FocusArgs,
+) -> Result<(), AppError> {
+    let config = ProjectConfig::for_input(&input)?;
+    let mut options = config.export.options();
+    image.apply(&mut options);
+    options.validate()?;
+
+    let mut diagram = parser::load(&input, from)?;
+
+    let layout_path = input.with_extension("layout.toml");
+    layout::apply_layout(&mut diagram, Some(layout_path.as_path()));
+
+    table_filter(&config, &filter).apply(&mut diagram);
+    if let Some(focus) = focus.focus(&diagram)? {
+        focus.apply(&mut diagram)?;
+    }
+
+    let output = output.unwrap_or_else(|| config.export_path(&input, format.extension()));
+    export::options::create_output_dir(&output)?;
+    export::export(diagram, format, &input, &output, &options)?;
+    println!("Exported: {}", output.display());
+
+    Ok(())
+}
+
+/// The config file's table filter with the command-line filters added.
+fn table_filter(config: &ProjectConfig, args: &FilterArgs) -> filter::TableFilter {
+    let mut filter = config.filter.clone();
+    args.apply(&mut filter);
+    filter
+}
+
+fn cmd_fmt(input: PathBuf, check: bool) -> Result<(), AppError> {
+    let dbml_content = std::fs::read_to_string(&input)?;
+    let diagram = parser::parse_dbml(&dbml_content)?;
+    let formatted = export::dbml::write_dbml(&diagram);
+
+    if formatted == dbml_content {
+        return Ok(());
+    }
+    if check {
+        return Err(AppError::NotFormatted(input.display().to_string()));
+    }
+    std::fs::write(&input, formatted)?;
+    println!("Formatted: {}", input.display());
+
+    Ok(())
+}
+
+fn cmd_sql(
+    input: PathBuf,
+    from: Option<InputFormat>,
+    dialect: Dialect,
+    output: Option<PathBuf>,
+) -> Result<(), AppError> {
+    let diagram = parser::load(&input, from)?;
+    let sql = export::sql::write_sql(&diagram, dialect);
+
+    match output {
+        Some(output) => {
+            std::fs::write(&output, sql)?;
+            println!("Exported: {}", output.display());
+        }
+        None => print!("{}", sql),
+    }
+
+    Ok(())
+}
+
+fn cmd_docs(
+    input: PathBuf,
+    from: Option<InputFormat>,
+    output: PathBuf,
+    format: DocsFormat,
+) -> Result<(), AppError> {
+    let mut diagram = parser::load(&input, from)?;
+
+    let layout_path = input.with_extension("layout.toml");
+    layout::apply_layout(&mut diagram, Some(layout_path.as_path()));
+
+    docs::write_docs(diagram, format, &output)?;
+    println!("Written: {}", output.display());
+
+    Ok(())
+}
+
+fn cmd_path(
+    input: PathBuf,
+    table_a: String,
+    table_b: String,
+    from: Option<InputFormat>,
+    limit: usize,
+) -> Result<(), AppError> {
+    let diagram = parser::load(&input, from)?;
+    let start = graph::resolve_table(&diagram, &table_a)?;
+    let end = graph::resolve_table(&diagram, &table_b)?;
+    let paths = path::shortest_paths(&diagram, start, end, limit);
+    print!("{}", path::report(&diagram, start, end, &paths));
+    Ok(())
+}
+
+fn cmd_stats(
+    input: PathBuf,
+    from: Option<InputFormat>,
+    format: StatsFormat,
+) -> Result<(), AppError> {
+    let diagram = parser::load(&input, from)?;
+    let stats = stats::stats(&diagram);
+    match format {
+        StatsFormat::Text => print!("{}", stats::report(&stats)),
+        StatsFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&stats).expect("stats serialize")
+        ),
+    }
+    Ok(())
+}
+
+fn cmd_impact(input: PathBuf, column: String, from: Option<InputFormat>) -> Result<(), AppError> {
+    let diagram = parser::load(&input, from)?;
+    let (table, column) = impact::resolve_column(&diagram, &column)?;
+    let impact = impact::column_impact(&diagram, table, &column);
+    print!("{}", impact::report(&diagram, &impact));
+    Ok(())
+}
+
+fn cmd_order(input: PathBuf, from: Option<InputFormat>, reverse: bool) -> Result<(), AppError> {
+    let diagram = parser::load(&input, from)?;
+    let order = graph::dependency_order(&diagram);
+    print!("{}", order::table_list(&diagram, &order, reverse));
+    // On stderr so the table list can be piped
+    eprint!("{}", order::cycle_report(&diagram, &order));
+    Ok(())
+}
+
+fn cmd_diff(
+    versions: Versions,
+    from: Option<InputFormat>,
+    output: Option<PathBuf>,
+    image: ImageArgs,
+) -> Result<(), AppError> {
+    let (old_diagram, new) = load_old_version(versions, from)?;
+
+    let config = ProjectConfig::for_input(&new)?;
+    let mut options = config.export.options();
+    image.apply(&mut options);
+    options.validate()?;
+
+    let mut new_diagram = parser::load(&new, from)?;
+
+    // The diagram is drawn with the newer file's layout
+    let layout_path = new.with_extension("layout.toml");
+    layout::apply_layout(&mut new_diagram, Some(layout_path.as_path()));
+    layout::ensure_positions(&mut new_diagram);
+
+    let changes = diff::diff(&old_diagram, &new_diagram);
+    print!("{}", changes.report());
+
+    if let Some(output) = output {
+        let (diagram, highlights) = changes.highlighted_diagram();
+        let name = new
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let svg = export::svg::write_highlighted_svg(&diagram, &name, &options, None, &highlights);
+        std::fs::write(&output, svg)?;
+        println!("Exported: {}", output.display());
+    }
+
+    Ok(())
+}
+
+fn cmd_migrate(
+    versions: Versions,
+    from: Option<InputFormat>,
+    dialect: Dialect,
+    renames: RenameArgs,
+    output: Option<PathBuf>,
+) -> Result<(), AppError> {
+    let renames = renames.renames()?;
+    let (old_diagram, new) = load_old_version(versions, from)?;
+    let new_diagram = parser::load(&new, from)?;
+
+    let changes = diff::diff_with_renames(&old_diagram, &new_diagram, &renames)?;
+    let migration = export::migrate::write_migration(&changes, dialect);
+    let sql = migration.to_sql();
+
+    match output {
+        Some(output) => {
+            std::fs::write(&output, sql)?;
+            println!("Exported: {}", output.display());
+        }
+        None => print!("{}", sql),
+    }
+    let destructive = migration.destructive_count();
+    if destructive > 0 {
+        eprintln!(
+            "Warning: {} destructive step{} marked with -- DESTRUCTIVE",
+            destructive,
+            if destructive == 1 { "" } else { "s" }
+        );
+    }
+
+    Ok(())
+}
+
+/// The older diagram of `versions`, with its layout, and the path of the
+/// newer file.
+fn load_old_version(
+    versions: Versions,
+    from: Option<InputFormat>,
+) -> Result<(ir::Diagram, PathBuf), AppError> {
+    let Versions { old, new, rev } = versions;
+    match (rev, new) {
+        // The old version of the file comes from git, the new one is on disk
+        (Some(rev), _) => Ok((load_at_rev(&old, &rev, from)?, old)),
+        (None, Some(new)) => {
+            let mut diagram = parser::load(&old, from)?;
+            let layout_path = old.with_extension("layout.toml");
+            layout::apply_layout(&mut diagram, Some(layout_path.as_path()));
+            Ok((diagram, new))
+        }
+        (None, None) => unreachable!("clap requires NEW without --rev"),
+    }
+}
+
+/// Parse `input` and apply its layout file as they were at git revision `rev`.
+fn load_at_rev(
+    input: &Path,
+    rev: &str,
+    from: Option<InputFormat>,
+) -> Result<ir::Diagram, AppError> {
+    let format = match from {
+        Some(format) => format,
+        None => InputFormat::detect(input)?,
+    };
+    let source = git::read_at_rev(input, rev)?.ok_or_else(|| {
+        AppError::GitError(format!("{} does not exist at {}", input.display(), rev))
+    })?;
+    let mut diagram = parser::parse_str(&source, format)?;
+
+    let layout_path = input.with_extension("layout.toml");
+    if let Some(content) = git::read_at_rev(&layout_path, rev)? {
+        let name = format!("{}:{}", rev, layout_path.display());
+        layout::apply_layout_data(
+            &mut diagram,
+            &layout::layout_file::parse_layout(&content, &name)?,
+        );
+    }
+    Ok(diagram)
 }
@@ -0,0 +1,127 @@
+//! Table order for loading data: parents before the children that
+//! reference them, or the reverse for deleting.
+
+use crate::graph::{self, DependencyOrder, FkEdge};
+use crate::ir::Diagram;
+
+/// Most cycles reported.
+const MAX_CYCLES: usize = 100;
+
+/// Full table names in dependency order, one per line.
+pub fn table_list(diagram: &Diagram, order: &DependencyOrder, reverse: bool) -> String {
+    let mut tables: Vec<usize> = order.tables.clone();
+    if reverse {
+        tables.reverse();
+    }
+    tables
+        .iter()
+        .map(|&i| format!("{}\n", diagram.tables[i].id))
+        .collect()
+}
+
+/// The foreign key cycles and the nullable foreign keys that break them,
+/// or an empty string if there are none.
+pub fn cycle_report(diagram: &Diagram, order: &DependencyOrder) -> String {
+    let mut out = String::new();
+    let cycles = graph::fk_cycles(diagram, MAX_CYCLES + 1);
+    for cycle in cycles.iter().take(MAX_CYCLES) {
+        let mut tables = vec![diagram.tables[cycle[0].child].id.to_string()];
+        tables.extend(
+            cycle
+                .iter()
+                .map(|e| diagram.tables[e.parent].id.to_string()),
+        );
+        out.push_str(&format!("Cycle: {}\n", tables.join(" -> ")));
+
+        let nullable: Vec<&FkEdge> = cycle
+            .iter()
+            .filter(|e| graph::fk_is_nullable(diagram, e))
+            .collect();
+        for edge in &nullable {
+            out.push_str(&format!(
+                "  can be broken at {} (nullable)\n",
+                foreign_key(diagram, edge)
+            ));
+        }
+        if nullable.is_empty() {
+            out.push_str("  no nullable foreign key breaks it; make one nullable or deferrable\n");
+        }
+        for edge in cycle.iter().filter(|e| order.deferred.contains(e)) {
+            if graph::fk_is_nullable(diagram, edge) {
+                out.push_str(&format!(
+                    "  this order breaks it at {}: insert it as null, then update it\n",
+                    foreign_key(diagram, edge)
+                ));
+            } else {
+                out.push_str(&format!(
+                    "  this order breaks it at {}, which is not null\n",
+                    foreign_key(diagram, edge)
+                ));
+            }
+        }
+    }
+    if cycles.len() > MAX_CYCLES {
+        out.push_str(&format!(
+            "More than {} cycles; not all listed\n",
+            MAX_CYCLES
+        ));
+    }
+    out
+}
+
+/// `schema.table.column -> schema.table.column`, with the columns in
+/// parentheses for a composite key.
+fn foreign_key(diagram: &Diagram, edge: &FkEdge) -> String {
+    let Some((child, parent)) = diagram.relationships[edge.rel].foreign_key() else {
+        return String::new();
+    };
+    let end = |table: usize, columns: &[String]| {
+        let id = &diagram.tables[table].id;
+        match columns {
+            [column] => format!("{}.{}", id, column),
+            _ => format!("{}({})", id, columns.join(", ")),
+        }
+    };
+    format!(
+        "{} -> {}",
+        end(edge.child, &child.column_names),
+        end(edge.parent, &parent.column_names)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_dbml;
+
+    #[test]
+    fn test_cycle_broken_at_nullable_foreign_key() {
+        let diagram = parse_dbml(
+            "Project p {\n  database_type: 'PostgreSQL'\n}\n\
+             Table teams {\n  id int [pk]\n  captain_id int [not null, ref: > players.id]\n}\n\
+             Table players {\n  id int [pk]\n  team_id int [null]\n}\n\
+             Table games {\n  id int [pk]\n  team_id int [not null]\n}\n\
+             Ref: teams.id < players.team_id\n\
+             Ref: teams.id < games.team_id\n",
+        )
+        .unwrap();
+        let order = graph::dependency_order(&diagram);
+
+        // players.team_id is the nullable side of the cycle
+        assert_eq!(
+            table_list(&diagram, &order, false),
+            "public.players\npublic.teams\npublic.games\n"
+        );
+        assert_eq!(
+            table_list(&diagram, &order, true),
+            "public.games\npublic.teams\npublic.players\n"
+        );
+        assert_eq!(
+            cycle_report(&diagram, &order),
+            "Cycle: public.teams -> public.players -> public.teams\n  \
+             can be broken at public.players.team_id -> public.teams.id (nullable)\n  \
+             this order breaks it at public.players.team_id -> public.teams.id: \
+             insert it as null, then update it\n"
+        );
+    }
+}
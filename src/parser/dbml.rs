@@ -0,0 +1,483 @@
+use std::ops::Range;
+
+use dbml_rs::ast;
+
+use crate::cardinality;
+use crate::error::AppError;
+use crate::ir::{
+    Column, DefaultValue, Diagram, EndPoint, Enum, EnumValue, GroupEntry, Index, IndexColumn,
+    Project, Property, ReferentialAction, RelationType, Relationship, Setting, StickyNote, Table,
+    TableGroup, TableId,
+};
+
+/// Parse a DBML string into a Diagram.
+pub fn parse_dbml(input: &str) -> Result<Diagram, AppError> {
+    let schema = dbml_rs::parse_dbml(input).map_err(|e| AppError::ParseError(format!("{}", e)))?;
+    let comments = Comments::scan(input);
+
+    let mut diagram = Diagram::default();
+    let mut inline_refs: Vec<Relationship> = Vec::new();
+
+    // Walk top-level blocks in source order so comments attach to the block below them
+    let mut prev_end = 0;
+    for block in &schema.blocks {
+        let span = block_span(block);
+        let leading = comments.between(prev_end, span.start);
+        prev_end = span.end;
+
+        match block {
+            ast::TopLevelBlock::Project(p) => {
+                let spans: Vec<_> = p.properties.iter().map(|x| x.span_range.clone()).collect();
+                let (around, end_comments) =
+                    comments.around(comments.body_start(&p.span_range), &spans, p.span_range.end);
+                diagram.project = Some(Project {
+                    name: p.ident.to_string.clone(),
+                    properties: p
+                        .properties
+                        .iter()
+                        .zip(around)
+                        .map(|(prop, (comments, trailing_comment))| Property {
+                            key: prop.key.to_string.clone(),
+                            value: prop.value.raw.clone(),
+                            comments,
+                            trailing_comment,
+                        })
+                        .collect(),
+                    note: p.note.as_ref().map(note_text),
+                    comments: leading,
+                    end_comments,
+                });
+            }
+            ast::TopLevelBlock::Table(t) => {
+                let (table, refs) = parse_table(t, &comments);
+                let mut table = table;
+                table.comments = leading;
+                diagram.tables.push(table);
+                inline_refs.extend(refs);
+            }
+            ast::TopLevelBlock::Enum(e) => {
+                let spans: Vec<_> = e.values.iter().map(|v| v.span_range.clone()).collect();
+                let (around, end_comments) =
+                    comments.around(comments.body_start(&e.span_range), &spans, e.span_range.end);
+                diagram.enums.push(Enum {
+                    schema: schema_name(&e.ident.schema),
+                    name: e.ident.name.to_string.clone(),
+                    values: e
+                        .values
+                        .iter()
+                        .zip(around)
+                        .map(|(v, (comments, trailing_comment))| EnumValue {
+                            name: v.value.to_string.clone(),
+                            note: v.settings.as_ref().and_then(|s| s.note.clone()),
+                            comments,
+                            trailing_comment,
+                        })
+                        .collect(),
+                    comments: leading,
+                    end_comments,
+                });
+            }
+            ast::TopLevelBlock::TableGroup(g) => {
+                let spans: Vec<_> = g.items.iter().map(|i| i.span_range.clone()).collect();
+                let (around, end_comments) =
+                    comments.around(comments.body_start(&g.span_range), &spans, g.span_range.end);
+                diagram.table_groups.push(TableGroup {
+                    name: g.ident.to_string.clone(),
+                    tables: g
+                        .items
+                        .iter()
+                        .zip(around)
+                        .map(|(item, (comments, trailing_comment))| GroupEntry {
+                            id: TableId::new(
+                                schema_name(&item.schema),
+                                item.ident_alias.to_string.clone(),
+                            ),
+                            comments,
+                            trailing_comment,
+                        })
+                        .collect(),
+                    note: g.note.as_ref().map(note_text),
+                    comments: leading,
+                    end_comments,
+                });
+            }
+            ast::TopLevelBlock::Ref(r) => {
+                // Get Relationships from parsed schema
+                let Some(relation_type) = relation_type(&r.rel) else {
+                    continue;
+                };
+
+                // Create EndPoints from Left-Hand-Side and Right-Hand-Side
+                // e.g. posts.user_id > users.id -> posts.user_id, users.id
+                let mut rel = Relationship::new(relation_type, endpoint(&r.lhs), endpoint(&r.rhs));
+                rel.name = r.name.as_ref().map(|n| n.to_string.clone());
+                if let Some(settings) = &r.settings {
+                    rel.on_delete = settings.on_delete.as_ref().map(referential_action);
+                    rel.on_update = settings.on_update.as_ref().map(referential_action);
+                }
+                rel.comments = leading;
+                diagram.relationships.push(rel);
+            }
+            ast::TopLevelBlock::Note(n) => {
+                diagram.notes.push(StickyNote {
+                    value: note_text(n),
+                    comments: leading,
+                });
+            }
+        }
+    }
+    diagram.trailing_comments = comments.between(prev_end, input.len());
+
+    // Inline refs come after the Ref blocks, as before
+    diagram.relationships.extend(inline_refs);
+
+    cardinality::infer(&mut diagram);
+    Ok(diagram)
+}
+
+fn parse_table(t: &ast::TableBlock, comments: &Comments) -> (Table, Vec<Relationship>) {
+    let id = TableId::new(schema_name(&t.ident.schema), t.ident.name.to_string.clone());
+
+    // Comments after the last column go above the `Indexes` block when it
+    // comes next, as it usually does, and at the end of the body if not
+    let cols_end = t
+        .cols
+        .last()
+        .map_or(t.span_range.start, |c| c.span_range.end);
+    let index_block = t
+        .indexes
+        .as_ref()
+        .filter(|b| b.span_range.start >= cols_end);
+
+    let spans: Vec<_> = t.cols.iter().map(|c| c.span_range.clone()).collect();
+    let (around, rest) = comments.around(
+        comments.body_start(&t.span_range),
+        &spans,
+        index_block.map_or(t.span_range.end, |b| b.span_range.start),
+    );
+    let (index_comments, mut end_comments) = match index_block {
+        Some(_) => (rest, Vec::new()),
+        None => (Vec::new(), rest),
+    };
+
+    let mut columns: Vec<Column> = Vec::new();
+    let mut refs: Vec<Relationship> = Vec::new();
+    for (c, (leading, trailing)) in t.cols.iter().zip(around) {
+        let settings = c.settings.as_ref();
+        let is_nullable = settings
+            .and_then(|s| s.nullable.as_ref())
+            .map(|n| matches!(n, ast::Nullable::Null))
+            .unwrap_or(true);
+        let default = settings.and_then(|s| {
+            s.attributes
+                .iter()
+                .find(|a| a.key.to_string == "default")
+                .and_then(|a| a.value.as_ref())
+                .map(default_value)
+        });
+
+        columns.push(Column {
+            name: c.name.to_string.clone(),
+            type_raw: c.r#type.raw.clone(),
+            is_pk: settings.map(|s| s.is_pk).unwrap_or(false),
+            is_nullable,
+            is_unique: settings.map(|s| s.is_unique).unwrap_or(false),
+            is_increment: settings.map(|s| s.is_incremental).unwrap_or(false),
+            default,
+            note: settings.and_then(|s| s.note.clone()),
+            comments: leading,
+            trailing_comment: trailing,
+        });
+
+        // Retrieve inline refs from the column
+        for inline_ref in settings.map(|s| s.refs.as_slice()).unwrap_or_default() {
+            let Some(relation_type) = relation_type(&inline_ref.rel) else {
+                continue;
+            };
+            let from = EndPoint {
+                table_id: id.clone(),
+                column_names: vec![c.name.to_string.clone()],
+            };
+            let mut rel = Relationship::new(relation_type, from, endpoint(&inline_ref.rhs));
+            rel.is_inline = true;
+            refs.push(rel);
+        }
+    }
+
+    // Comments around each index, then the ones after the block
+    let (index_around, index_end_comments) = match index_block {
+        Some(block) => {
+            let spans: Vec<_> = block.defs.iter().map(|d| d.span_range.clone()).collect();
+            end_comments = comments.between(block.span_range.end, t.span_range.end);
+            comments.around(
+                comments.body_start(&block.span_range),
+                &spans,
+                block.span_range.end,
+            )
+        }
+        None => (Vec::new(), Vec::new()),
+    };
+    let mut index_around = index_around.into_iter();
+
+    let mut note = t.note.as_ref().map(note_text);
+    let mut settings = Vec::new();
+    for attr in t.settings.iter().flat_map(|s| &s.attributes) {
+        if attr.key.to_string == "note" {
+            note = note.or_else(|| attr.value.as_ref().map(|v| v.value.to_string()));
+        } else {
+            settings.push(Setting {
+                key: attr.key.to_string.clone(),
+                value: attr.value.as_ref().map(|v| v.raw.clone()),
+            });
+        }
+    }
+
+    let indexes = t
+        .indexes
+        .iter()
+        .flat_map(|block| &block.defs)
+        .map(|def| {
+            let s = def.settings.as_ref();
+            let (comments, trailing_comment) = index_around.next().unwrap_or_default();
+            Index {
+                columns: def
+                    .cols
+                    .iter()
+                    .map(|col| match col {
+                        ast::IndexesColumnType::String(ident) => {
+                            IndexColumn::Column(ident.to_string.clone())
+                        }
+                        ast::IndexesColumnType::Expr(lit) => {
+                            IndexColumn::Expression(lit.value.to_string())
+                        }
+                    })
+                    .collect(),
+                name: s.and_then(|s| s.name.clone()),
+                is_unique: s.map(|s| s.is_unique).unwrap_or(false),
+                is_pk: s.map(|s| s.is_pk).unwrap_or(false),
+                index_type: s.and_then(|s| s.r#type.as_ref()).map(|t| {
+                    match t {
+                        ast::IndexesType::BTree => "btree",
+                        ast::IndexesType::Gin => "gin",
+                        ast::IndexesType::Gist => "gist",
+                        ast::IndexesType::Hash => "hash",
+                    }
+                    .to_string()
+                }),
+                note: s.and_then(|s| s.note.clone()),
+                comments,
+                trailing_comment,
+            }
+        })
+        .collect();
+
+    let table = Table {
+        id,
+        columns,
+        position: None,
+        collapsed: false,
+        alias: t.ident.alias.as_ref().map(|a| a.to_string.clone()),
+        note,
+        indexes,
+        settings,
+        comments: Vec::new(),
+        end_comments,
+        index_comments,
+        index_end_comments,
+        is_stub: false,
+    };
+    (table, refs)
+}
+
+fn schema_name(schema: &Option<ast::Ident>) -> String {
+    schema
+        .as_ref()
+        .map(|s| s.to_string.clone())
+        .unwrap_or_else(|| "public".to_string())
+}
+
+fn endpoint(ident: &ast::RefIdent) -> EndPoint {
+    EndPoint {
+        table_id: TableId::new(schema_name(&ident.schema), ident.table.to_string.clone()),
+        column_names: ident
+            .compositions
+            .iter()
+            .map(|c| c.to_string.clone())
+            .collect(),
+    }
+}
+
+fn relation_type(rel: &ast::Relation) -> Option<RelationType> {
+    match rel {
+        ast::Relation::One2One => Some(RelationType::OneToOne),
+        ast::Relation::One2Many => Some(RelationType::OneToMany),
+        ast::Relation::Many2One => Some(RelationType::ManyToOne),
+        ast::Relation::Many2Many => Some(RelationType::ManyToMany),
+        ast::Relation::Undef => None,
+    }
+}
+
+fn referential_action(action: &ast::ReferentialAction) -> ReferentialAction {
+    match action {
+        ast::ReferentialAction::NoAction => ReferentialAction::NoAction,
+        ast::ReferentialAction::Cascade => ReferentialAction::Cascade,
+        ast::ReferentialAction::Restrict => ReferentialAction::Restrict,
+        ast::ReferentialAction::SetNull => ReferentialAction::SetNull,
+        ast::ReferentialAction::SetDefault => ReferentialAction::SetDefault,
+    }
+}
+
+fn default_value(lit: &ast::Literal) -> DefaultValue {
+    match &lit.value {
+        ast::Value::String(s) | ast::Value::HexColor(s) => DefaultValue::String(s.clone()),
+        ast::Value::Integer(_) | ast::Value::Decimal(_) => DefaultValue::Number(lit.raw.clone()),
+        ast::Value::Bool(b) => DefaultValue::Bool(*b),
+        ast::Value::Null => DefaultValue::Null,
+        ast::Value::Expr(e) => DefaultValue::Expression(e.clone()),
+        ast::Value::Enum(e) => DefaultValue::Identifier(e.clone()),
+    }
+}
+
+fn note_text(note: &ast::NoteBlock) -> String {
+    note.value.value.to_string()
+}
+
+fn block_span(block: &ast::TopLevelBlock) -> Range<usize> {
+    match block {
+        ast::TopLevelBlock::Project(b) => b.span_range.clone(),
+        ast::TopLevelBlock::Table(b) => b.span_range.clone(),
+        ast::TopLevelBlock::TableGroup(b) => b.span_range.clone(),
+        ast::TopLevelBlock::Note(b) => b.span_range.clone(),
+        ast::TopLevelBlock::Ref(b) => b.span_range.clone(),
+        ast::TopLevelBlock::Enum(b) => b.span_range.clone(),
+    }
+}
+
+/// Comments directly above a node and the one trailing it on its line.
+type Attached = (Vec<String>, Option<String>);
+
+/// Comments in the source text. The DBML AST drops them, so they are
+/// located separately and attached to the nearest following node by offset.
+struct Comments<'a> {
+    source: &'a str,
+    /// Byte range and raw text (`// ...` or `/* ... */`) of every comment.
+    items: Vec<(Range<usize>, String)>,
+}
+
+impl<'a> Comments<'a> {
+    fn scan(source: &'a str) -> Self {
+        let bytes = source.as_bytes();
+        let mut items = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            let rest = &source[i..];
+            if rest.starts_with("//") {
+                let end = rest.find('\n').map(|n| i + n).unwrap_or(source.len());
+                items.push((i..end, source[i..end].trim_end().to_string()));
+                i = end;
+            } else if rest.starts_with("/*") {
+                let end = rest.find("*/").map(|n| i + n + 2).unwrap_or(source.len());
+                items.push((i..end, source[i..end].to_string()));
+                i = end;
+            } else if let Some(quote) = ["'''", "'", "\"", "`"]
+                .iter()
+                .find(|q| rest.starts_with(**q))
+            {
+                // Skip string literals so `//` inside a note is not a comment
+                let body = i + quote.len();
+                i = source[body..]
+                    .find(quote)
+                    .map(|n| body + n + quote.len())
+                    .unwrap_or(source.len());
+            } else {
+                i += rest.chars().next().map(char::len_utf8).unwrap_or(1);
+            }
+        }
+        Self { source, items }
+    }
+
+    /// All comments fully inside `start..end`.
+    fn between(&self, start: usize, end: usize) -> Vec<String> {
+        self.items
+            .iter()
+            .filter(|(r, _)| r.start >= start && r.end <= end)
+            .map(|(_, text)| text.clone())
+            .collect()
+    }
+
+    /// Where comments inside the body of a block start: after its opening
+    /// brace.
+    fn body_start(&self, span: &Range<usize>) -> usize {
+        self.source
+            .get(span.clone())
+            .and_then(|s| s.find('{'))
+            .map(|i| span.start + i + 1)
+            .unwrap_or(span.start)
+    }
+
+    /// Comments around the `items` of a body running from `start` to `end`:
+    /// for each item the ones directly above it and one trailing it on the
+    /// same line, then the ones after the last item. A comment on the line
+    /// of the opening brace goes above the first item.
+    fn around(
+        &self,
+        start: usize,
+        items: &[Range<usize>],
+        end: usize,
+    ) -> (Vec<Attached>, Vec<String>) {
+        let mut attached: Vec<Attached> = Vec::new();
+        let mut prev_end = start;
+        for item in items.iter().map(|i| self.trim(i)) {
+            let (trailing, mut leading) = self.split_trailing(prev_end, item.start);
+            match attached.last_mut() {
+                Some(prev) => prev.1 = trailing,
+                None => leading.splice(0..0, trailing).for_each(drop),
+            }
+            attached.push((leading, None));
+            prev_end = item.end;
+        }
+        let (trailing, mut rest) = self.split_trailing(prev_end, end);
+        match attached.last_mut() {
+            Some(prev) => prev.1 = trailing,
+            None => rest.splice(0..0, trailing).for_each(drop),
+        }
+        (attached, rest)
+    }
+
+    /// `span` without the whitespace and comments at its end, which the
+    /// parser includes in nodes that have no settings.
+    fn trim(&self, span: &Range<usize>) -> Range<usize> {
+        let mut end = span.end;
+        loop {
+            end = span.start + self.source[span.start..end].trim_end().len();
+            match self
+                .items
+                .iter()
+                .find(|(r, text)| r.start >= span.start && r.start + text.len() == end)
+            {
+                Some((r, _)) => end = r.start,
+                None => return span.start..end,
+            }
+        }
+    }
+
+    /// Split the comments in `start..end` into one on the same line as
+    /// `start` (trailing the previous node) and the rest.
+    fn split_trailing(&self, start: usize, end: usize) -> (Option<String>, Vec<String>) {
+        let mut trailing = None;
+        let mut rest = Vec::new();
+        for (r, text) in self
+            .items
+            .iter()
+            .filter(|(r, _)| r.start >= start && r.end <= end)
+        {
+            if trailing.is_none() && rest.is_empty() && !self.source[start..r.start].contains('\n')
+            {
+                trailing = Some(text.clone());
+            } else {
+                rest.push(text.clone());
+            }
+        }
+        (trailing, rest)
+    }
+}
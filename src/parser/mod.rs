@@ -0,0 +1,82 @@
+//! Front ends that turn a schema source into an `ir::Diagram`.
+
+pub(crate) mod dbml;
+pub(crate) mod prisma;
+pub(crate) mod sql;
+pub(crate) mod sqlite;
+
+use std::path::Path;
+
+use crate::error::AppError;
+use crate::ir::Diagram;
+
+pub use dbml::parse_dbml;
+pub use prisma::parse_prisma;
+pub use sql::parse_sql;
+pub use sqlite::read_sqlite;
+
+/// Input formats accepted by the commands that read a schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum InputFormat {
+    /// DBML
+    Dbml,
+    /// PostgreSQL or MySQL DDL
+    Sql,
+    /// SQLite database file
+    Sqlite,
+    /// Prisma schema
+    Prisma,
+}
+
+impl InputFormat {
+    /// Detect the format from the file extension, falling back to the
+    /// SQLite file header for databases with other extensions.
+    pub fn detect(path: &Path) -> Result<Self, AppError> {
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match ext.as_str() {
+            "dbml" => Ok(InputFormat::Dbml),
+            "sql" | "ddl" => Ok(InputFormat::Sql),
+            "prisma" => Ok(InputFormat::Prisma),
+            "db" | "sqlite" | "sqlite3" | "db3" => Ok(InputFormat::Sqlite),
+            _ if has_sqlite_header(path) => Ok(InputFormat::Sqlite),
+            _ => Err(AppError::UnknownInputFormat(path.display().to_string())),
+        }
+    }
+}
+
+/// Read and parse `input`, detecting the format unless `from` is given.
+pub fn load(input: &Path, from: Option<InputFormat>) -> Result<Diagram, AppError> {
+    let format = match from {
+        Some(format) => format,
+        None => InputFormat::detect(input)?,
+    };
+    match format {
+        InputFormat::Sqlite => read_sqlite(input),
+        format => parse_str(&std::fs::read_to_string(input)?, format),
+    }
+}
+
+/// Parse schema source text. SQLite databases are files, not text, and
+/// are rejected.
+pub fn parse_str(source: &str, format: InputFormat) -> Result<Diagram, AppError> {
+    match format {
+        InputFormat::Dbml => parse_dbml(source),
+        InputFormat::Sql => parse_sql(source),
+        InputFormat::Prisma => parse_prisma(source),
+        InputFormat::Sqlite => Err(AppError::SqliteError(
+            "a SQLite database cannot be read from text".into(),
+        )),
+    }
+}
+
+fn has_sqlite_header(path: &Path) -> bool {
+    use std::io::Read;
+
+    let mut header = [0u8; 16];
+    std::fs::File::open(path)
+        .and_then(|mut f| f.read_exact(&mut header))
+        .is_ok_and(|_| header == sqlite::MAGIC)
+}
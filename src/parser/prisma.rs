@@ -0,0 +1,784 @@
+//! Prisma schema front end.
+//!
+//! Models become tables and their scalar fields columns. Relation fields are
+//! not columns: a field with `@relation(fields: [...], references: [...])`
+//! becomes a foreign key, and a pair of list fields without one is Prisma's
+//! implicit many-to-many. `datasource`, `generator`, `view` and `type` blocks
+//! are skipped.
+
+use std::collections::HashSet;
+
+use crate::cardinality;
+use crate::error::AppError;
+use crate::ir::{
+    Column, DefaultValue, Diagram, EndPoint, Enum, EnumValue, Index, IndexColumn,
+    ReferentialAction, RelationType, Relationship, Table, TableId,
+};
+
+/// Parse a Prisma schema string into a Diagram.
+pub fn parse_prisma(input: &str) -> Result<Diagram, AppError> {
+    let blocks = parse_blocks(input)?;
+    let models: Vec<&Block> = blocks.iter().filter(|b| b.kind == "model").collect();
+
+    let mut diagram = Diagram::default();
+    for block in blocks.iter().filter(|b| b.kind == "enum") {
+        diagram.enums.push(Enum {
+            schema: block_schema(block),
+            name: block.name.clone(),
+            values: block
+                .fields
+                .iter()
+                .map(|f| EnumValue {
+                    name: f.name.clone(),
+                    note: f.doc.clone(),
+                    comments: f.comments.clone(),
+                    trailing_comment: f.trailing_comment.clone(),
+                })
+                .collect(),
+            comments: block.comments.clone(),
+            end_comments: Vec::new(),
+        });
+    }
+
+    for model in &models {
+        diagram.tables.push(model_table(model, &models));
+    }
+
+    let mut implicit_pairs: HashSet<(String, String, String)> = HashSet::new();
+    for model in &models {
+        for field in &model.fields {
+            let Some(target) = models.iter().find(|m| m.name == field.type_name) else {
+                continue;
+            };
+            if let Some(rel) = relation(model, field, target, &mut implicit_pairs) {
+                diagram.relationships.push(rel);
+            }
+        }
+    }
+
+    cardinality::infer(&mut diagram);
+    Ok(diagram)
+}
+
+fn model_table(model: &Block, models: &[&Block]) -> Table {
+    let mut table = Table {
+        id: TableId::new(block_schema(model), model.name.clone()),
+        note: model.doc.clone(),
+        comments: model.comments.clone(),
+        ..Default::default()
+    };
+
+    for field in &model.fields {
+        if models.iter().any(|m| m.name == field.type_name) {
+            continue;
+        }
+        let default = field
+            .attr("default")
+            .and_then(|a| a.args.first())
+            .map(|a| &a.value);
+        let is_increment = matches!(default, Some(Value::Call(f, _)) if f == "autoincrement");
+        table.columns.push(Column {
+            name: field.name.clone(),
+            type_raw: field.type_raw(),
+            is_pk: field.attr("id").is_some(),
+            is_nullable: field.optional,
+            is_unique: field.attr("unique").is_some(),
+            is_increment,
+            default: default.and_then(default_value),
+            note: field.doc.clone(),
+            comments: field.comments.clone(),
+            trailing_comment: field.trailing_comment.clone(),
+        });
+    }
+
+    for attr in &model.block_attrs {
+        let columns = attr
+            .positional()
+            .map(|v| v.names().into_iter().map(IndexColumn::Column).collect())
+            .unwrap_or_default();
+        let name = attr
+            .named("map")
+            .or_else(|| attr.named("name"))
+            .and_then(Value::string);
+        let index = |is_unique, is_pk| Index {
+            columns,
+            name,
+            is_unique,
+            is_pk,
+            index_type: attr
+                .named("type")
+                .and_then(Value::ident)
+                .map(str::to_lowercase),
+            note: None,
+            comments: Vec::new(),
+            trailing_comment: None,
+        };
+        match attr.name.as_str() {
+            "id" => table.indexes.push(index(false, true)),
+            "unique" => table.indexes.push(index(true, false)),
+            "index" => table.indexes.push(index(false, false)),
+            _ => {}
+        }
+    }
+
+    table
+}
+
+/// The relationship a relation field declares, if this side declares it.
+fn relation(
+    model: &Block,
+    field: &Field,
+    target: &Block,
+    implicit_pairs: &mut HashSet<(String, String, String)>,
+) -> Option<Relationship> {
+    let attr = field.attr("relation");
+    let relation_name = attr.and_then(|a| {
+        a.named("name")
+            .or_else(|| a.positional())
+            .and_then(Value::string)
+    });
+    // The field on the other model that points back at this one
+    let back = target.fields.iter().find(|f| {
+        f.type_name == model.name
+            && !(model.name == target.name && f.name == field.name)
+            && f.attr("relation")
+                .and_then(|a| a.named("name").or_else(|| a.positional()))
+                .and_then(Value::string)
+                == relation_name
+    });
+
+    let endpoint = |block: &Block, columns: Vec<String>| EndPoint {
+        table_id: TableId::new(block_schema(block), block.name.clone()),
+        column_names: columns,
+    };
+
+    if let Some(fields) = attr.and_then(|a| a.named("fields")) {
+        let references = attr.and_then(|a| a.named("references"))?;
+        let from = fields.names();
+        // A unique foreign key, or a singular field on the other side, is one-to-one
+        let unique_fk = match from.as_slice() {
+            [single] => model.fields.iter().any(|f| {
+                f.name == *single && (f.attr("unique").is_some() || f.attr("id").is_some())
+            }),
+            _ => model
+                .block_attrs
+                .iter()
+                .filter(|a| a.name == "unique" || a.name == "id")
+                .any(|a| a.positional().is_some_and(|v| v.names() == from)),
+        };
+        let one_to_one = unique_fk || back.is_some_and(|b| !b.list);
+
+        let mut rel = Relationship::new(
+            if one_to_one {
+                RelationType::OneToOne
+            } else {
+                RelationType::ManyToOne
+            },
+            endpoint(model, from),
+            endpoint(target, references.names()),
+        );
+        rel.name = relation_name;
+        rel.on_delete = attr
+            .and_then(|a| a.named("onDelete"))
+            .and_then(Value::ident)
+            .and_then(referential_action);
+        rel.on_update = attr
+            .and_then(|a| a.named("onUpdate"))
+            .and_then(Value::ident)
+            .and_then(referential_action);
+        return Some(rel);
+    }
+
+    // Implicit many-to-many: list fields on both sides and no foreign key
+    let back = back?;
+    if !field.list || !back.list {
+        return None;
+    }
+    let mut pair = [
+        format!("{}.{}", model.name, field.name),
+        format!("{}.{}", target.name, back.name),
+    ];
+    pair.sort();
+    let [a, b] = pair;
+    if !implicit_pairs.insert((a, b, relation_name.clone().unwrap_or_default())) {
+        return None;
+    }
+
+    let id_of = |block: &Block| {
+        block
+            .fields
+            .iter()
+            .find(|f| f.attr("id").is_some())
+            .map(|f| vec![f.name.clone()])
+    };
+    let mut rel = Relationship::new(
+        RelationType::ManyToMany,
+        endpoint(model, id_of(model)?),
+        endpoint(target, id_of(target)?),
+    );
+    rel.name = relation_name;
+    Some(rel)
+}
+
+fn default_value(value: &Value) -> Option<DefaultValue> {
+    match value {
+        Value::Str(s) => Some(DefaultValue::String(s.clone())),
+        Value::Num(n) => Some(DefaultValue::Number(n.clone())),
+        Value::Ident(b) if b == "true" => Some(DefaultValue::Bool(true)),
+        Value::Ident(b) if b == "false" => Some(DefaultValue::Bool(false)),
+        Value::Ident(e) => Some(DefaultValue::Identifier(e.clone())),
+        Value::Call(f, _) if f == "autoincrement" => None,
+        Value::Call(f, args) if f == "dbgenerated" => args
+            .first()
+            .and_then(|a| a.value.string())
+            .map(DefaultValue::Expression),
+        Value::Call(f, _) => Some(DefaultValue::Expression(format!("{}()", f))),
+        Value::List(_) => None,
+    }
+}
+
+fn referential_action(action: &str) -> Option<ReferentialAction> {
+    match action {
+        "Cascade" => Some(ReferentialAction::Cascade),
+        "Restrict" => Some(ReferentialAction::Restrict),
+        "NoAction" => Some(ReferentialAction::NoAction),
+        "SetNull" => Some(ReferentialAction::SetNull),
+        "SetDefault" => Some(ReferentialAction::SetDefault),
+        _ => None,
+    }
+}
+
+/// `@@schema("name")`, used with Prisma's multi-schema support.
+fn block_schema(block: &Block) -> String {
+    block
+        .block_attrs
+        .iter()
+        .find(|a| a.name == "schema")
+        .and_then(|a| a.positional())
+        .and_then(Value::string)
+        .unwrap_or_else(|| "public".to_string())
+}
+
+/// A top-level `keyword Name { ... }` block.
+#[derive(Debug)]
+struct Block {
+    kind: String,
+    name: String,
+    /// `///` documentation comment above the block.
+    doc: Option<String>,
+    /// `//` comments above the block.
+    comments: Vec<String>,
+    /// Fields of a model, or values of an enum.
+    fields: Vec<Field>,
+    /// `@@` attributes.
+    block_attrs: Vec<Attribute>,
+}
+
+#[derive(Debug, Default)]
+struct Field {
+    name: String,
+    /// Base type without `?` / `[]`, e.g. `String` or `Unsupported("point")`.
+    type_name: String,
+    optional: bool,
+    list: bool,
+    attrs: Vec<Attribute>,
+    doc: Option<String>,
+    comments: Vec<String>,
+    trailing_comment: Option<String>,
+}
+
+impl Field {
+    fn attr(&self, name: &str) -> Option<&Attribute> {
+        self.attrs.iter().find(|a| a.name == name)
+    }
+
+    fn type_raw(&self) -> String {
+        if self.list {
+            format!("{}[]", self.type_name)
+        } else {
+            self.type_name.clone()
+        }
+    }
+}
+
+/// `@name(args)` or `@@name(args)`; `@db.VarChar(255)` is named `db.VarChar`.
+#[derive(Debug)]
+struct Attribute {
+    name: String,
+    args: Vec<Arg>,
+}
+
+impl Attribute {
+    /// The first unnamed argument.
+    fn positional(&self) -> Option<&Value> {
+        self.args
+            .iter()
+            .find(|a| a.name.is_none())
+            .map(|a| &a.value)
+    }
+
+    fn named(&self, name: &str) -> Option<&Value> {
+        self.args
+            .iter()
+            .find(|a| a.name.as_deref() == Some(name))
+            .map(|a| &a.value)
+    }
+}
+
+#[derive(Debug)]
+struct Arg {
+    name: Option<String>,
+    value: Value,
+}
+
+#[derive(Debug)]
+enum Value {
+    Str(String),
+    Num(String),
+    Ident(String),
+    Call(String, Vec<Arg>),
+    List(Vec<Value>),
+}
+
+impl Value {
+    fn string(&self) -> Option<String> {
+        match self {
+            Value::Str(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+
+    fn ident(&self) -> Option<&str> {
+        match self {
+            Value::Ident(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Field names of a `[a, b]` list; `[a(sort: Desc)]` yields `a`.
+    fn names(&self) -> Vec<String> {
+        let name = |v: &Value| match v {
+            Value::Ident(n) | Value::Call(n, _) => Some(n.clone()),
+            _ => None,
+        };
+        match self {
+            Value::List(items) => items.iter().filter_map(name).collect(),
+            other => name(other).into_iter().collect(),
+        }
+    }
+}
+
+fn parse_blocks(input: &str) -> Result<Vec<Block>, AppError> {
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut current: Option<Block> = None;
+    let mut doc: Vec<String> = Vec::new();
+    let mut comments: Vec<String> = Vec::new();
+
+    for (n, line) in input.lines().enumerate() {
+        let line_no = n + 1;
+        let trimmed = line.trim();
+
+        if let Some(text) = trimmed.strip_prefix("///") {
+            doc.push(text.trim().to_string());
+            continue;
+        }
+        if trimmed.starts_with("//") {
+            comments.push(trimmed.to_string());
+            continue;
+        }
+        let (code, trailing) = split_comment(trimmed);
+        if code.is_empty() {
+            continue;
+        }
+        let take_doc =
+            |doc: &mut Vec<String>| (!doc.is_empty()).then(|| std::mem::take(doc).join("\n"));
+
+        if code == "}" && current.is_some() {
+            blocks.extend(current.take());
+            doc.clear();
+            comments.clear();
+            continue;
+        }
+
+        match current.as_mut() {
+            None => {
+                let header: Vec<&str> = code.split_whitespace().collect();
+                let [kind, name, "{"] = header.as_slice() else {
+                    return Err(error(line_no, "expected a block like `model Name {`"));
+                };
+                current = Some(Block {
+                    kind: kind.to_string(),
+                    name: name.to_string(),
+                    doc: take_doc(&mut doc),
+                    comments: std::mem::take(&mut comments),
+                    fields: Vec::new(),
+                    block_attrs: Vec::new(),
+                });
+            }
+            Some(block) if block.kind == "model" || block.kind == "enum" => {
+                let tokens = tokenize(code).map_err(|msg| error(line_no, &msg))?;
+                let mut p = Parser { tokens, pos: 0 };
+                if p.eat('@') {
+                    p.expect('@').map_err(|msg| error(line_no, &msg))?;
+                    block
+                        .block_attrs
+                        .push(p.attribute().map_err(|msg| error(line_no, &msg))?);
+                    continue;
+                }
+                let mut field = p
+                    .field(block.kind == "enum")
+                    .map_err(|msg| error(line_no, &msg))?;
+                field.doc = take_doc(&mut doc);
+                field.comments = std::mem::take(&mut comments);
+                field.trailing_comment = trailing.map(str::to_string);
+                block.fields.push(field);
+            }
+            // datasource, generator, view, type
+            Some(_) => {}
+        }
+    }
+
+    if let Some(block) = current {
+        return Err(error(
+            input.lines().count(),
+            &format!("unclosed block `{}`", block.name),
+        ));
+    }
+    Ok(blocks)
+}
+
+/// Split a trailing `// comment` off a line, ignoring `//` inside strings.
+fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let mut in_string = false;
+    let mut prev = '\0';
+    for (i, ch) in line.char_indices() {
+        if ch == '"' && prev != '\\' {
+            in_string = !in_string;
+        } else if !in_string && line[i..].starts_with("//") {
+            return (line[..i].trim_end(), Some(line[i..].trim_end()));
+        }
+        prev = ch;
+    }
+    (line, None)
+}
+
+fn error(line: usize, message: &str) -> AppError {
+    AppError::PrismaParseError(format!("line {}: {}", line, message))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Ident(String),
+    Str(String),
+    Num(String),
+    Punct(char),
+}
+
+fn tokenize(line: &str) -> Result<Vec<Tok>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(&ch) = chars.peek() {
+        if ch.is_whitespace() {
+            chars.next();
+        } else if ch == '"' {
+            chars.next();
+            let mut text = String::new();
+            loop {
+                match chars.next() {
+                    Some('\\') => text.extend(chars.next()),
+                    Some('"') => break,
+                    Some(c) => text.push(c),
+                    None => return Err("unterminated string".to_string()),
+                }
+            }
+            tokens.push(Tok::Str(text));
+        } else if ch.is_ascii_digit() || ch == '-' {
+            let mut text = String::new();
+            while let Some(&c) = chars.peek() {
+                if !(c.is_ascii_alphanumeric() || c == '.' || (c == '-' && text.is_empty())) {
+                    break;
+                }
+                text.push(c);
+                chars.next();
+            }
+            tokens.push(Tok::Num(text));
+        } else if ch.is_alphanumeric() || ch == '_' {
+            let mut text = String::new();
+            while let Some(&c) = chars.peek() {
+                if !(c.is_alphanumeric() || c == '_') {
+                    break;
+                }
+                text.push(c);
+                chars.next();
+            }
+            tokens.push(Tok::Ident(text));
+        } else {
+            tokens.push(Tok::Punct(ch));
+            chars.next();
+        }
+    }
+    Ok(tokens)
+}
+
+/// Cursor over the tokens of one line.
+struct Parser {
+    tokens: Vec<Tok>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Tok> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(&mut self, punct: char) -> bool {
+        let found = self.peek() == Some(&Tok::Punct(punct));
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, punct: char) -> Result<(), String> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            Err(format!("expected '{}'", punct))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some(Tok::Ident(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => Err("expected a name".to_string()),
+        }
+    }
+
+    /// `name Type? @attr(...) ...`, or `VALUE @map("...")` in an enum.
+    fn field(&mut self, enum_value: bool) -> Result<Field, String> {
+        let mut field = Field {
+            name: self.ident()?,
+            ..Default::default()
+        };
+        if !enum_value {
+            field.type_name = self.ident()?;
+            // Unsupported("type") keeps its argument
+            if self.eat('(') {
+                let arg = match self.peek() {
+                    Some(Tok::Str(s)) => format!("\"{}\"", s),
+                    _ => String::new(),
+                };
+                self.pos += 1;
+                self.expect(')')?;
+                field.type_name = format!("{}({})", field.type_name, arg);
+            }
+            if self.eat('[') {
+                self.expect(']')?;
+                field.list = true;
+            }
+            field.optional = self.eat('?');
+        }
+        while self.eat('@') {
+            field.attrs.push(self.attribute()?);
+        }
+        if self.pos < self.tokens.len() {
+            return Err(format!("unexpected {:?}", self.tokens[self.pos]));
+        }
+        Ok(field)
+    }
+
+    /// After the `@`: `name[.name](args)`
+    fn attribute(&mut self) -> Result<Attribute, String> {
+        let mut name = self.ident()?;
+        while self.eat('.') {
+            name = format!("{}.{}", name, self.ident()?);
+        }
+        let args = if self.eat('(') {
+            self.args(')')?
+        } else {
+            Vec::new()
+        };
+        Ok(Attribute { name, args })
+    }
+
+    /// Comma-separated `[name:] value` up to `close`.
+    fn args(&mut self, close: char) -> Result<Vec<Arg>, String> {
+        let mut args = Vec::new();
+        while !self.eat(close) {
+            let name = match (self.peek(), self.tokens.get(self.pos + 1)) {
+                (Some(Tok::Ident(n)), Some(Tok::Punct(':'))) => {
+                    let n = n.clone();
+                    self.pos += 2;
+                    Some(n)
+                }
+                _ => None,
+            };
+            args.push(Arg {
+                name,
+                value: self.value()?,
+            });
+            if !self.eat(',') {
+                self.expect(close)?;
+                break;
+            }
+        }
+        Ok(args)
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        if self.eat('[') {
+            let items = self.args(']')?;
+            return Ok(Value::List(items.into_iter().map(|a| a.value).collect()));
+        }
+        match self.peek().cloned() {
+            Some(Tok::Str(s)) => {
+                self.pos += 1;
+                Ok(Value::Str(s))
+            }
+            Some(Tok::Num(n)) => {
+                self.pos += 1;
+                Ok(Value::Num(n))
+            }
+            Some(Tok::Ident(_)) => {
+                let name = self.ident()?;
+                if self.eat('(') {
+                    Ok(Value::Call(name, self.args(')')?))
+                } else {
+                    Ok(Value::Ident(name))
+                }
+            }
+            _ => Err("expected a value".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = r#"
+datasource db {
+  provider = "postgresql"
+  url      = env("DATABASE_URL")
+}
+
+generator client {
+  provider = "prisma-client-js"
+}
+
+enum Role {
+  USER
+  ADMIN
+}
+
+/// Registered users
+model User {
+  id        Int      @id @default(autoincrement())
+  email     String   @unique @db.VarChar(255) // login
+  name      String?
+  role      Role     @default(USER)
+  createdAt DateTime @default(now())
+  posts     Post[]   @relation("authored")
+  profile   Profile?
+  groups    Group[]
+}
+
+model Profile {
+  id     Int    @id @default(autoincrement())
+  bio    String @default("")
+  user   User   @relation(fields: [userId], references: [id])
+  userId Int    @unique
+}
+
+// Blog posts
+model Post {
+  id       Int    @id @default(autoincrement())
+  title    String
+  author   User   @relation("authored", fields: [authorId], references: [id], onDelete: Cascade)
+  authorId Int
+  tags     String[]
+
+  @@index([authorId, title], map: "posts_author_title")
+}
+
+model Group {
+  id      String @id @default(uuid())
+  members User[]
+}
+"#;
+
+    #[test]
+    fn test_models_and_fields() {
+        let d = parse_prisma(SCHEMA).unwrap();
+
+        assert_eq!(d.enums.len(), 1);
+        assert_eq!(d.enums[0].values.len(), 2);
+        let names: Vec<_> = d.tables.iter().map(|t| t.id.name.as_str()).collect();
+        assert_eq!(names, vec!["User", "Profile", "Post", "Group"]);
+
+        let user = &d.tables[0];
+        assert_eq!(user.note.as_deref(), Some("Registered users"));
+        // Relation fields are not columns
+        assert_eq!(user.columns.len(), 5);
+        let id = &user.columns[0];
+        assert!(id.is_pk && id.is_increment && !id.is_nullable);
+        assert_eq!(id.default, None);
+        assert!(user.columns[1].is_unique);
+        assert_eq!(
+            user.columns[1].trailing_comment.as_deref(),
+            Some("// login")
+        );
+        assert!(user.columns[2].is_nullable);
+        assert_eq!(
+            user.columns[3].default,
+            Some(DefaultValue::Identifier("USER".into()))
+        );
+        assert_eq!(
+            user.columns[4].default,
+            Some(DefaultValue::Expression("now()".into()))
+        );
+
+        let post = &d.tables[2];
+        assert_eq!(post.comments, vec!["// Blog posts"]);
+        assert_eq!(post.columns[3].type_raw, "String[]");
+        assert_eq!(post.indexes[0].name.as_deref(), Some("posts_author_title"));
+        assert_eq!(post.indexes[0].columns.len(), 2);
+    }
+
+    #[test]
+    fn test_relations() {
+        let d = parse_prisma(SCHEMA).unwrap();
+        assert_eq!(d.relationships.len(), 3);
+
+        // Implicit many-to-many, added once from the first model
+        let groups = &d.relationships[0];
+        assert_eq!(groups.relation_type, RelationType::ManyToMany);
+        assert_eq!(groups.from.table_id.name, "User");
+        assert_eq!(groups.to.table_id.name, "Group");
+
+        // Unique foreign key
+        let profile = &d.relationships[1];
+        assert_eq!(profile.relation_type, RelationType::OneToOne);
+        assert_eq!(profile.from.table_id.name, "Profile");
+        assert_eq!(profile.from.column_names, vec!["userId"]);
+        assert_eq!(profile.to.column_names, vec!["id"]);
+
+        let author = &d.relationships[2];
+        assert_eq!(author.relation_type, RelationType::ManyToOne);
+        assert_eq!(author.name.as_deref(), Some("authored"));
+        assert_eq!(author.on_delete, Some(ReferentialAction::Cascade));
+    }
+
+    #[test]
+    fn test_reports_line_of_error() {
+        let err = parse_prisma("model A {\n  id Int @id(\n}").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to parse Prisma schema: line 2: expected a value"
+        );
+    }
+}
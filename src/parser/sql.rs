@@ -0,0 +1,1176 @@
+//! PostgreSQL / MySQL DDL front end.
+//!
+//! Understands the statements that describe a schema: `CREATE TABLE` with
+//! inline and table-level constraints, `ALTER TABLE ... ADD`, `CREATE INDEX`,
+//! `CREATE TYPE ... AS ENUM` and `COMMENT ON`. Every other statement (`SET`,
+//! functions, grants, ...) is skipped, so the output of
+//! `pg_dump --schema-only` or `mysqldump --no-data` can be read as is.
+
+use crate::cardinality;
+use crate::error::AppError;
+use crate::ir::{
+    Column, DefaultValue, Diagram, EndPoint, Enum, EnumValue, Index, IndexColumn,
+    ReferentialAction, RelationType, Relationship, Table, TableId,
+};
+
+/// Parse SQL DDL into a Diagram.
+pub fn parse_sql(input: &str) -> Result<Diagram, AppError> {
+    let tokens = tokenize(input)?;
+    let mut diagram = Diagram::default();
+
+    for statement in tokens.split(|t| t.is_punct(";")) {
+        let mut p = Parser {
+            input,
+            tokens: statement,
+            pos: 0,
+        };
+        parse_statement(&mut p, &mut diagram)?;
+    }
+
+    resolve_references(&mut diagram);
+    cardinality::infer(&mut diagram);
+    Ok(diagram)
+}
+
+fn parse_statement(p: &mut Parser, diagram: &mut Diagram) -> Result<(), AppError> {
+    if p.eat_kw("CREATE") {
+        p.eat_kws(&["OR", "REPLACE"]);
+        while p.eat_any_kw(&["TEMP", "TEMPORARY", "UNLOGGED", "GLOBAL", "LOCAL"]) {}
+        if p.eat_kw("TABLE") {
+            return create_table(p, diagram);
+        }
+        if p.eat_kw("TYPE") {
+            return create_type(p, diagram);
+        }
+        let unique = p.eat_kw("UNIQUE");
+        let fulltext = p.eat_any_kw(&["FULLTEXT", "SPATIAL"]);
+        if p.eat_kw("INDEX") {
+            return create_index(p, diagram, unique, fulltext);
+        }
+    } else if p.eat_kws(&["ALTER", "TABLE"]) {
+        return alter_table(p, diagram);
+    } else if p.eat_kws(&["COMMENT", "ON"]) {
+        return comment_on(p, diagram);
+    }
+    Ok(())
+}
+
+fn create_table(p: &mut Parser, diagram: &mut Diagram) -> Result<(), AppError> {
+    p.eat_kws(&["IF", "NOT", "EXISTS"]);
+    let id = table_id(p.qualified_name()?);
+    // CREATE TABLE ... AS SELECT / PARTITION OF have no column list
+    if !p.eat_punct("(") {
+        return Ok(());
+    }
+
+    let mut table = Table {
+        id,
+        ..Default::default()
+    };
+    if !p.eat_punct(")") {
+        loop {
+            table_element(p, &mut table, &mut diagram.relationships)?;
+            if p.eat_punct(",") {
+                continue;
+            }
+            p.expect_punct(")")?;
+            break;
+        }
+    }
+
+    // Table options, e.g. MySQL `ENGINE=InnoDB COMMENT='...'`
+    while !p.at_end() {
+        if p.eat_kw("COMMENT") {
+            p.eat_punct("=");
+            table.note = Some(p.string()?);
+        } else {
+            p.pos += 1;
+        }
+    }
+
+    diagram.tables.push(table);
+    Ok(())
+}
+
+fn create_type(p: &mut Parser, diagram: &mut Diagram) -> Result<(), AppError> {
+    let mut name = p.qualified_name()?;
+    if !p.eat_kws(&["AS", "ENUM"]) {
+        return Ok(());
+    }
+    p.expect_punct("(")?;
+    let mut values = Vec::new();
+    if !p.eat_punct(")") {
+        loop {
+            values.push(EnumValue {
+                name: p.string()?,
+                note: None,
+                comments: Vec::new(),
+                trailing_comment: None,
+            });
+            if p.eat_punct(",") {
+                continue;
+            }
+            p.expect_punct(")")?;
+            break;
+        }
+    }
+
+    let enum_name = name.pop().unwrap_or_default();
+    diagram.enums.push(Enum {
+        schema: name.pop().unwrap_or_else(|| "public".to_string()),
+        name: enum_name,
+        values,
+        comments: Vec::new(),
+        end_comments: Vec::new(),
+    });
+    Ok(())
+}
+
+fn create_index(
+    p: &mut Parser,
+    diagram: &mut Diagram,
+    unique: bool,
+    fulltext: bool,
+) -> Result<(), AppError> {
+    p.eat_kw("CONCURRENTLY");
+    p.eat_kws(&["IF", "NOT", "EXISTS"]);
+    // PostgreSQL allows unnamed indexes: CREATE INDEX ON t (...)
+    let name = if p.is_kw("ON") {
+        None
+    } else {
+        Some(p.ident()?)
+    };
+    if !p.eat_kw("ON") {
+        return Err(p.error("expected ON"));
+    }
+    p.eat_kw("ONLY");
+    let id = table_id(p.qualified_name()?);
+
+    let mut index_type = fulltext.then(|| "fulltext".to_string());
+    if p.eat_kw("USING") {
+        index_type = Some(p.ident()?.to_lowercase());
+    }
+    let columns = p.index_columns()?;
+    if p.eat_kw("USING") {
+        index_type = Some(p.ident()?.to_lowercase());
+    }
+
+    if let Some(table) = diagram.tables.iter_mut().find(|t| t.id == id) {
+        table.indexes.push(Index {
+            columns,
+            name,
+            is_unique: unique,
+            is_pk: false,
+            index_type,
+            note: None,
+            comments: Vec::new(),
+            trailing_comment: None,
+        });
+    }
+    Ok(())
+}
+
+fn alter_table(p: &mut Parser, diagram: &mut Diagram) -> Result<(), AppError> {
+    p.eat_kws(&["IF", "EXISTS"]);
+    p.eat_kw("ONLY");
+    let id = table_id(p.qualified_name()?);
+    let Some(table) = diagram.tables.iter_mut().find(|t| t.id == id) else {
+        return Ok(());
+    };
+
+    loop {
+        if p.eat_kw("ADD") {
+            p.eat_kw("COLUMN");
+            p.eat_kws(&["IF", "NOT", "EXISTS"]);
+            table_element(p, table, &mut diagram.relationships)?;
+        } else if p.eat_kw("ALTER") {
+            p.eat_kw("COLUMN");
+            let name = p.ident()?;
+            if let Some(col) = table.columns.iter_mut().find(|c| c.name == name) {
+                if p.eat_kws(&["SET", "DEFAULT"]) {
+                    set_default(col, p);
+                } else if p.eat_kws(&["SET", "NOT", "NULL"]) {
+                    col.is_nullable = false;
+                } else if p.eat_kws(&["DROP", "NOT", "NULL"]) {
+                    col.is_nullable = true;
+                }
+            }
+        }
+        p.skip_element();
+        if !p.eat_punct(",") {
+            return Ok(());
+        }
+    }
+}
+
+fn comment_on(p: &mut Parser, diagram: &mut Diagram) -> Result<(), AppError> {
+    let on_column = if p.eat_kw("TABLE") {
+        false
+    } else if p.eat_kw("COLUMN") {
+        true
+    } else {
+        return Ok(());
+    };
+    let mut name = p.qualified_name()?;
+    if !p.eat_kw("IS") {
+        return Err(p.error("expected IS"));
+    }
+    let note = if p.eat_kw("NULL") {
+        None
+    } else {
+        Some(p.string()?)
+    };
+
+    let column = if on_column { name.pop() } else { None };
+    let id = table_id(name);
+    let Some(table) = diagram.tables.iter_mut().find(|t| t.id == id) else {
+        return Ok(());
+    };
+    match column {
+        Some(column) => {
+            if let Some(col) = table.columns.iter_mut().find(|c| c.name == column) {
+                col.note = note;
+            }
+        }
+        None => table.note = note,
+    }
+    Ok(())
+}
+
+/// One entry of a `CREATE TABLE` body or an `ALTER TABLE ... ADD`.
+fn table_element(
+    p: &mut Parser,
+    table: &mut Table,
+    relationships: &mut Vec<Relationship>,
+) -> Result<(), AppError> {
+    let constraint_name = if p.eat_kw("CONSTRAINT") {
+        Some(p.ident()?)
+    } else {
+        None
+    };
+
+    if p.eat_kws(&["PRIMARY", "KEY"]) {
+        let columns = p.key_columns()?;
+        set_primary_key(table, &columns);
+    } else if p.eat_kw("UNIQUE") {
+        p.eat_any_kw(&["KEY", "INDEX"]);
+        let name = optional_index_name(p)?.or(constraint_name);
+        let columns = p.index_columns()?;
+        add_unique(table, name, columns);
+    } else if p.eat_kws(&["FOREIGN", "KEY"]) {
+        let name = optional_index_name(p)?.or(constraint_name);
+        let columns = p.key_columns()?;
+        let rel = references(p, &table.id, columns, name)?;
+        relationships.push(rel);
+    } else if p.is_kw("CHECK") || p.is_kw("EXCLUDE") || p.is_kw("LIKE") {
+        // Not part of the diagram
+    } else if p.eat_any_kw(&["KEY", "INDEX", "FULLTEXT", "SPATIAL"]) {
+        // MySQL inline index
+        let fulltext = matches!(p.prev_word().as_deref(), Some("FULLTEXT" | "SPATIAL"));
+        if fulltext {
+            p.eat_any_kw(&["KEY", "INDEX"]);
+        }
+        let name = optional_index_name(p)?;
+        let columns = p.index_columns()?;
+        let mut index_type = fulltext.then(|| "fulltext".to_string());
+        if p.eat_kw("USING") {
+            index_type = Some(p.ident()?.to_lowercase());
+        }
+        table.indexes.push(Index {
+            columns,
+            name,
+            is_unique: false,
+            is_pk: false,
+            index_type,
+            note: None,
+            comments: Vec::new(),
+            trailing_comment: None,
+        });
+    } else {
+        column_def(p, table, relationships)?;
+    }
+
+    p.skip_element();
+    Ok(())
+}
+
+fn column_def(
+    p: &mut Parser,
+    table: &mut Table,
+    relationships: &mut Vec<Relationship>,
+) -> Result<(), AppError> {
+    let name = p.ident()?;
+    let mut col = Column {
+        type_raw: p.column_type()?,
+        name,
+        is_nullable: true,
+        ..Default::default()
+    };
+
+    let mut constraint_name = None;
+    while !p.at_element_end() {
+        if p.eat_kw("CONSTRAINT") {
+            constraint_name = Some(p.ident()?);
+        } else if p.eat_kws(&["NOT", "NULL"]) {
+            col.is_nullable = false;
+        } else if p.eat_kws(&["PRIMARY", "KEY"]) {
+            col.is_pk = true;
+            col.is_nullable = false;
+        } else if p.eat_kw("UNIQUE") {
+            p.eat_kw("KEY");
+            col.is_unique = true;
+        } else if p.eat_kw("DEFAULT") {
+            set_default(&mut col, p);
+        } else if p.is_kw("REFERENCES") {
+            let mut rel = references(p, &table.id, vec![col.name.clone()], constraint_name.take())?;
+            rel.is_inline = true;
+            relationships.push(rel);
+        } else if p.eat_any_kw(&["AUTO_INCREMENT", "AUTOINCREMENT"]) {
+            col.is_increment = true;
+        } else if p.eat_kw("GENERATED") {
+            if p.eat_kws(&["ALWAYS", "AS", "IDENTITY"])
+                || p.eat_kws(&["BY", "DEFAULT", "AS", "IDENTITY"])
+            {
+                col.is_increment = true;
+            }
+        } else if p.eat_kw("COMMENT") {
+            col.note = Some(p.string()?);
+        } else if p.is_punct("(") {
+            p.skip_group();
+        } else {
+            // NULL, CHECK, COLLATE, ON UPDATE, ...
+            p.pos += 1;
+        }
+    }
+
+    table.columns.push(col);
+    Ok(())
+}
+
+/// `REFERENCES parent [(cols)] [ON DELETE ...] [ON UPDATE ...]`
+fn references(
+    p: &mut Parser,
+    child: &TableId,
+    columns: Vec<String>,
+    name: Option<String>,
+) -> Result<Relationship, AppError> {
+    if !p.eat_kw("REFERENCES") {
+        return Err(p.error("expected REFERENCES"));
+    }
+    let parent = table_id(p.qualified_name()?);
+    // Without a column list the parent's primary key is referenced; it is
+    // filled in once every table is known
+    let parent_columns = if p.is_punct("(") {
+        p.key_columns()?
+    } else {
+        Vec::new()
+    };
+
+    let mut rel = Relationship::new(
+        RelationType::ManyToOne,
+        EndPoint {
+            table_id: child.clone(),
+            column_names: columns,
+        },
+        EndPoint {
+            table_id: parent,
+            column_names: parent_columns,
+        },
+    );
+    rel.name = name;
+
+    loop {
+        if p.eat_kws(&["ON", "DELETE"]) {
+            rel.on_delete = Some(referential_action(p)?);
+        } else if p.eat_kws(&["ON", "UPDATE"]) {
+            rel.on_update = Some(referential_action(p)?);
+        } else if p.eat_kw("MATCH") {
+            p.pos += 1;
+        } else {
+            return Ok(rel);
+        }
+    }
+}
+
+fn referential_action(p: &mut Parser) -> Result<ReferentialAction, AppError> {
+    if p.eat_kw("CASCADE") {
+        Ok(ReferentialAction::Cascade)
+    } else if p.eat_kw("RESTRICT") {
+        Ok(ReferentialAction::Restrict)
+    } else if p.eat_kws(&["SET", "NULL"]) {
+        Ok(ReferentialAction::SetNull)
+    } else if p.eat_kws(&["SET", "DEFAULT"]) {
+        Ok(ReferentialAction::SetDefault)
+    } else if p.eat_kws(&["NO", "ACTION"]) {
+        Ok(ReferentialAction::NoAction)
+    } else {
+        Err(p.error("expected a referential action"))
+    }
+}
+
+/// MySQL allows a name before the column list of `UNIQUE KEY` / `FOREIGN KEY`.
+fn optional_index_name(p: &mut Parser) -> Result<Option<String>, AppError> {
+    if p.is_punct("(") {
+        Ok(None)
+    } else {
+        p.ident().map(Some)
+    }
+}
+
+fn set_primary_key(table: &mut Table, columns: &[String]) {
+    for col in table.columns.iter_mut() {
+        if columns.contains(&col.name) {
+            col.is_nullable = false;
+            col.is_pk = columns.len() == 1;
+        }
+    }
+    if columns.len() > 1 {
+        table.indexes.push(Index {
+            columns: columns.iter().cloned().map(IndexColumn::Column).collect(),
+            name: None,
+            is_unique: false,
+            is_pk: true,
+            index_type: None,
+            note: None,
+            comments: Vec::new(),
+            trailing_comment: None,
+        });
+    }
+}
+
+fn add_unique(table: &mut Table, name: Option<String>, columns: Vec<IndexColumn>) {
+    if let [IndexColumn::Column(single)] = columns.as_slice() {
+        if let Some(col) = table.columns.iter_mut().find(|c| c.name == *single) {
+            col.is_unique = true;
+            return;
+        }
+    }
+    table.indexes.push(Index {
+        columns,
+        name,
+        is_unique: true,
+        is_pk: false,
+        index_type: None,
+        note: None,
+        comments: Vec::new(),
+        trailing_comment: None,
+    });
+}
+
+fn set_default(col: &mut Column, p: &mut Parser) {
+    let start = p.pos;
+    let mut groups = 0;
+    // The first token may itself be a keyword, e.g. DEFAULT NULL
+    while !p.at_element_end() && (p.pos == start || !p.is_column_keyword()) {
+        if p.is_punct("(") {
+            p.skip_group();
+            groups += 1;
+        } else {
+            p.pos += 1;
+        }
+    }
+    let tokens = &p.tokens[start..p.pos];
+    let raw = p.source(start, p.pos);
+
+    // A sequence default is how pg_dump spells a serial column
+    if raw.starts_with("nextval(") {
+        col.is_increment = true;
+        col.default = None;
+        return;
+    }
+    col.default = match tokens {
+        [] => None,
+        [t] if t.kind == Kind::Str => Some(DefaultValue::String(t.text.clone())),
+        // 'x'::character varying
+        [t, cast, ..] if t.kind == Kind::Str && cast.is_punct("::") => {
+            Some(DefaultValue::String(t.text.clone()))
+        }
+        [t] if t.kind == Kind::Num => Some(DefaultValue::Number(t.text.clone())),
+        [sign, t] if sign.is_punct("-") && t.kind == Kind::Num => {
+            Some(DefaultValue::Number(format!("-{}", t.text)))
+        }
+        [t, ..] if t.is_kw("NULL") => Some(DefaultValue::Null),
+        [t] if t.is_kw("TRUE") => Some(DefaultValue::Bool(true)),
+        [t] if t.is_kw("FALSE") => Some(DefaultValue::Bool(false)),
+        // MySQL and SQLite wrap expression defaults in parentheses
+        [open, .., close] if groups == 1 && open.is_punct("(") && close.is_punct(")") => {
+            Some(DefaultValue::Expression(raw[1..raw.len() - 1].to_string()))
+        }
+        _ => Some(DefaultValue::Expression(raw.to_string())),
+    };
+}
+
+/// Classify a default given as expression text, e.g. SQLite's `PRAGMA table_info`.
+pub(super) fn parse_default(text: &str) -> Option<DefaultValue> {
+    let tokens = tokenize(text).ok()?;
+    let mut p = Parser {
+        input: text,
+        tokens: &tokens,
+        pos: 0,
+    };
+    let mut col = Column::default();
+    set_default(&mut col, &mut p);
+    col.default
+}
+
+/// Whether `column` is declared `AUTOINCREMENT` in a `CREATE TABLE`
+/// statement, e.g. the text SQLite keeps in `sqlite_schema`.
+pub(super) fn is_increment(create_table: &str, column: &str) -> bool {
+    parse_sql(create_table).is_ok_and(|d| {
+        d.tables
+            .iter()
+            .flat_map(|t| &t.columns)
+            .any(|c| c.is_increment && c.name.eq_ignore_ascii_case(column))
+    })
+}
+
+/// Fill in omitted referenced columns and drop references to tables that
+/// are not in the input.
+pub(super) fn resolve_references(diagram: &mut Diagram) {
+    let tables = &diagram.tables;
+    diagram.relationships.retain_mut(|rel| {
+        let Some(parent) = tables.iter().find(|t| t.id == rel.to.table_id) else {
+            return false;
+        };
+        if rel.to.column_names.is_empty() {
+            rel.to.column_names = primary_key_columns(parent);
+        }
+        tables.iter().any(|t| t.id == rel.from.table_id)
+    });
+}
+
+fn primary_key_columns(table: &Table) -> Vec<String> {
+    let columns: Vec<String> = table
+        .columns
+        .iter()
+        .filter(|c| c.is_pk)
+        .map(|c| c.name.clone())
+        .collect();
+    if !columns.is_empty() {
+        return columns;
+    }
+    table
+        .indexes
+        .iter()
+        .find(|i| i.is_pk)
+        .map(|i| {
+            i.columns
+                .iter()
+                .filter_map(|c| match c {
+                    IndexColumn::Column(name) => Some(name.clone()),
+                    IndexColumn::Expression(_) => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `[schema.]name`; MySQL's `db.table` is treated as a schema as well.
+fn table_id(mut parts: Vec<String>) -> TableId {
+    let name = parts.pop().unwrap_or_default();
+    let schema = parts.pop().unwrap_or_else(|| "public".to_string());
+    TableId::new(schema, name)
+}
+
+/// Keywords that end a column type or a default expression.
+const COLUMN_KEYWORDS: &[&str] = &[
+    "NOT",
+    "NULL",
+    "PRIMARY",
+    "UNIQUE",
+    "DEFAULT",
+    "REFERENCES",
+    "CHECK",
+    "CONSTRAINT",
+    "AUTO_INCREMENT",
+    "AUTOINCREMENT",
+    "GENERATED",
+    "COMMENT",
+    "COLLATE",
+    "ON",
+    "CHARSET",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// Unquoted word: keyword or identifier.
+    Word,
+    /// `"quoted"` or `` `quoted` `` identifier.
+    Quoted,
+    /// String literal, including dollar-quoted bodies.
+    Str,
+    Num,
+    Punct,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: Kind,
+    /// Unquoted and unescaped text.
+    text: String,
+    start: usize,
+    end: usize,
+}
+
+impl Token {
+    fn is_kw(&self, kw: &str) -> bool {
+        self.kind == Kind::Word && self.text.eq_ignore_ascii_case(kw)
+    }
+
+    fn is_punct(&self, punct: &str) -> bool {
+        self.kind == Kind::Punct && self.text == punct
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, AppError> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < input.len() {
+        let rest = &input[i..];
+        let c = rest.chars().next().unwrap_or_default();
+        let start = i;
+
+        if c.is_whitespace() {
+            i += c.len_utf8();
+        } else if rest.starts_with("--") || c == '#' {
+            i += rest.find('\n').unwrap_or(rest.len());
+        } else if rest.starts_with("/*") {
+            let end = rest
+                .find("*/")
+                .ok_or_else(|| error_at(input, start, "unterminated comment"))?;
+            i += end + 2;
+        } else if c == '\'' || ((c == 'E' || c == 'e') && rest[1..].starts_with('\'')) {
+            let escapes = c != '\'';
+            let body = i + if escapes { 2 } else { 1 };
+            let (text, end) = read_quoted(input, body, '\'', escapes)
+                .ok_or_else(|| error_at(input, start, "unterminated string"))?;
+            tokens.push(token(Kind::Str, text, start, end));
+            i = end;
+        } else if c == '"' || c == '`' {
+            let (text, end) = read_quoted(input, i + 1, c, false)
+                .ok_or_else(|| error_at(input, start, "unterminated identifier"))?;
+            tokens.push(token(Kind::Quoted, text, start, end));
+            i = end;
+        } else if let Some(tag) = dollar_tag(rest) {
+            // $$ ... $$ or $tag$ ... $tag$ function bodies
+            let body = i + tag.len();
+            let close = input[body..]
+                .find(tag)
+                .ok_or_else(|| error_at(input, start, "unterminated dollar-quoted string"))?;
+            let end = body + close + tag.len();
+            tokens.push(token(
+                Kind::Str,
+                input[body..body + close].to_string(),
+                start,
+                end,
+            ));
+            i = end;
+        } else if c.is_ascii_digit() {
+            let len = rest
+                .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '.'))
+                .unwrap_or(rest.len());
+            i += len;
+            tokens.push(token(Kind::Num, rest[..len].to_string(), start, i));
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest
+                .find(|ch: char| !(ch.is_alphanumeric() || ch == '_' || ch == '$'))
+                .unwrap_or(rest.len());
+            i += len;
+            tokens.push(token(Kind::Word, rest[..len].to_string(), start, i));
+        } else if rest.starts_with("::") {
+            i += 2;
+            tokens.push(token(Kind::Punct, "::".to_string(), start, i));
+        } else {
+            i += c.len_utf8();
+            tokens.push(token(Kind::Punct, c.to_string(), start, i));
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn token(kind: Kind, text: String, start: usize, end: usize) -> Token {
+    Token {
+        kind,
+        text,
+        start,
+        end,
+    }
+}
+
+/// Read a quoted body starting at `start` (just after the opening quote).
+/// Returns the unescaped text and the offset after the closing quote.
+fn read_quoted(input: &str, start: usize, quote: char, backslash: bool) -> Option<(String, usize)> {
+    let mut text = String::new();
+    let mut chars = input[start..].char_indices().peekable();
+    while let Some((n, ch)) = chars.next() {
+        if backslash && ch == '\\' {
+            if let Some((_, escaped)) = chars.next() {
+                text.push(escaped);
+            }
+        } else if ch == quote {
+            // A doubled quote is an escaped quote
+            if chars.peek().map(|(_, next)| *next) == Some(quote) {
+                chars.next();
+                text.push(quote);
+            } else {
+                return Some((text, start + n + ch.len_utf8()));
+            }
+        } else {
+            text.push(ch);
+        }
+    }
+    None
+}
+
+fn dollar_tag(rest: &str) -> Option<&str> {
+    let inner = rest.strip_prefix('$')?;
+    let len = inner.find('$')?;
+    inner[..len]
+        .chars()
+        .all(|ch| ch.is_alphanumeric() || ch == '_')
+        .then(|| &rest[..len + 2])
+}
+
+fn error_at(input: &str, offset: usize, message: &str) -> AppError {
+    let line = input[..offset].matches('\n').count() + 1;
+    AppError::SqlParseError(format!("line {}: {}", line, message))
+}
+
+/// Cursor over the tokens of one statement.
+struct Parser<'a> {
+    input: &'a str,
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    fn is_kw(&self, kw: &str) -> bool {
+        self.peek().is_some_and(|t| t.is_kw(kw))
+    }
+
+    fn is_punct(&self, punct: &str) -> bool {
+        self.peek().is_some_and(|t| t.is_punct(punct))
+    }
+
+    fn eat_kw(&mut self, kw: &str) -> bool {
+        let found = self.is_kw(kw);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn eat_any_kw(&mut self, kws: &[&str]) -> bool {
+        kws.iter().any(|kw| self.eat_kw(kw))
+    }
+
+    /// Consume a keyword sequence only if all of it matches.
+    fn eat_kws(&mut self, kws: &[&str]) -> bool {
+        let matches = kws
+            .iter()
+            .enumerate()
+            .all(|(n, kw)| self.tokens.get(self.pos + n).is_some_and(|t| t.is_kw(kw)));
+        if matches {
+            self.pos += kws.len();
+        }
+        matches
+    }
+
+    fn eat_punct(&mut self, punct: &str) -> bool {
+        let found = self.is_punct(punct);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_punct(&mut self, punct: &str) -> Result<(), AppError> {
+        if self.eat_punct(punct) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", punct)))
+        }
+    }
+
+    /// Uppercased text of the previous token.
+    fn prev_word(&self) -> Option<String> {
+        self.pos
+            .checked_sub(1)
+            .and_then(|n| self.tokens.get(n))
+            .map(|t| t.text.to_uppercase())
+    }
+
+    fn ident(&mut self) -> Result<String, AppError> {
+        match self.peek() {
+            Some(t) if t.kind == Kind::Word || t.kind == Kind::Quoted => {
+                let text = t.text.clone();
+                self.pos += 1;
+                Ok(text)
+            }
+            _ => Err(self.error("expected an identifier")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, AppError> {
+        match self.peek() {
+            Some(t) if t.kind == Kind::Str => {
+                let text = t.text.clone();
+                self.pos += 1;
+                Ok(text)
+            }
+            _ => Err(self.error("expected a string")),
+        }
+    }
+
+    /// `a`, `a.b` or `a.b.c`
+    fn qualified_name(&mut self) -> Result<Vec<String>, AppError> {
+        let mut parts = vec![self.ident()?];
+        while self.eat_punct(".") {
+            parts.push(self.ident()?);
+        }
+        Ok(parts)
+    }
+
+    /// Parenthesized index elements: plain columns (with optional ordering,
+    /// prefix length or operator class) or expressions.
+    fn index_columns(&mut self) -> Result<Vec<IndexColumn>, AppError> {
+        self.expect_punct("(")?;
+        let mut columns = Vec::new();
+        loop {
+            let start = self.pos;
+            self.skip_element();
+            let tokens = &self.tokens[start..self.pos];
+            let is_column = match tokens {
+                [first, rest @ ..] if matches!(first.kind, Kind::Word | Kind::Quoted) => match rest
+                {
+                    [] => true,
+                    [open, n, ..] if open.is_punct("(") => n.kind == Kind::Num,
+                    [next, ..] => next.kind == Kind::Word,
+                },
+                [] => return Err(self.error("expected an index column")),
+                _ => false,
+            };
+            if is_column {
+                columns.push(IndexColumn::Column(tokens[0].text.clone()));
+            } else {
+                let raw = self.source(start, self.pos);
+                let expr = raw
+                    .strip_prefix('(')
+                    .and_then(|r| r.strip_suffix(')'))
+                    .unwrap_or(raw);
+                columns.push(IndexColumn::Expression(expr.to_string()));
+            }
+            if self.eat_punct(",") {
+                continue;
+            }
+            self.expect_punct(")")?;
+            return Ok(columns);
+        }
+    }
+
+    /// Column names of a key; expressions are not allowed here.
+    fn key_columns(&mut self) -> Result<Vec<String>, AppError> {
+        self.index_columns()?
+            .into_iter()
+            .map(|c| match c {
+                IndexColumn::Column(name) => Ok(name),
+                IndexColumn::Expression(_) => Err(self.error("expected a column name")),
+            })
+            .collect()
+    }
+
+    /// Everything up to the next keyword that starts a column constraint.
+    fn column_type(&mut self) -> Result<String, AppError> {
+        let start = self.pos;
+        while !self.at_element_end() && !self.is_column_keyword() {
+            if self.is_punct("(") {
+                self.skip_group();
+            } else {
+                self.pos += 1;
+            }
+        }
+        if start == self.pos {
+            return Err(self.error("expected a column type"));
+        }
+
+        let type_raw = join_tokens(&self.tokens[start..self.pos]);
+        Ok(type_raw
+            .strip_prefix("public.")
+            .map(str::to_string)
+            .unwrap_or(type_raw))
+    }
+
+    fn is_column_keyword(&self) -> bool {
+        COLUMN_KEYWORDS.iter().any(|kw| self.is_kw(kw))
+            || (self.is_kw("CHARACTER")
+                && self
+                    .tokens
+                    .get(self.pos + 1)
+                    .is_some_and(|t| t.is_kw("SET")))
+    }
+
+    /// At a `,` or `)` that ends a table element, or the end of the statement.
+    fn at_element_end(&self) -> bool {
+        self.at_end() || self.is_punct(",") || self.is_punct(")")
+    }
+
+    /// Skip to the end of the current table element.
+    fn skip_element(&mut self) {
+        while !self.at_element_end() {
+            if self.is_punct("(") {
+                self.skip_group();
+            } else {
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// Skip a parenthesized group, including nested groups.
+    fn skip_group(&mut self) {
+        let mut depth = 0;
+        while let Some(t) = self.peek() {
+            if t.is_punct("(") {
+                depth += 1;
+            } else if t.is_punct(")") {
+                depth -= 1;
+            }
+            self.pos += 1;
+            if depth == 0 {
+                break;
+            }
+        }
+    }
+
+    /// Source text of tokens `start..end`.
+    fn source(&self, start: usize, end: usize) -> &str {
+        if start >= end {
+            return "";
+        }
+        &self.input[self.tokens[start].start..self.tokens[end - 1].end]
+    }
+
+    fn error(&self, message: &str) -> AppError {
+        let offset = self
+            .peek()
+            .or(self.tokens.last())
+            .map(|t| t.start)
+            .unwrap_or(0);
+        error_at(self.input, offset, message)
+    }
+}
+
+/// Rebuild a type name with quotes removed, e.g. `"public"."mood"` -> `public.mood`.
+fn join_tokens(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    let mut prev: Option<&Token> = None;
+    for t in tokens {
+        let attach = ["(", ")", ",", ".", "[", "]"].iter().any(|p| t.is_punct(p))
+            || prev.is_some_and(|p| ["(", ",", ".", "["].iter().any(|q| p.is_punct(q)));
+        if prev.is_some() && !attach {
+            out.push(' ');
+        }
+        match t.kind {
+            Kind::Str => out.push_str(&format!("'{}'", t.text.replace('\'', "''"))),
+            _ => out.push_str(&t.text),
+        }
+        prev = Some(t);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::sql::{write_sql, Dialect};
+    use crate::parser::parse_dbml;
+
+    #[test]
+    fn test_round_trip_through_generated_ddl() {
+        let original = parse_dbml(include_str!("../../tests/fixtures/basic.dbml")).unwrap();
+        for dialect in [Dialect::Postgres, Dialect::Mysql] {
+            let parsed = parse_sql(&write_sql(&original, dialect)).unwrap();
+
+            assert_eq!(parsed.tables.len(), original.tables.len());
+            for (a, b) in original.tables.iter().zip(&parsed.tables) {
+                assert_eq!(a.id, b.id);
+                for (ca, cb) in a.columns.iter().zip(&b.columns) {
+                    assert_eq!(ca.name, cb.name);
+                    // MySQL gets a length for a bare varchar
+                    let type_raw = match (dialect, ca.type_raw.as_str()) {
+                        (Dialect::Mysql, "varchar") => "VARCHAR(255)",
+                        (_, type_raw) => type_raw,
+                    };
+                    assert_eq!(type_raw, cb.type_raw);
+                    assert_eq!(ca.is_pk, cb.is_pk);
+                    assert_eq!(ca.is_unique, cb.is_unique);
+                    assert_eq!(ca.is_increment, cb.is_increment);
+                    assert_eq!(ca.default, cb.default);
+                }
+            }
+
+            let fks = |d: &Diagram| -> Vec<(String, String)> {
+                let mut fks: Vec<_> = d
+                    .relationships
+                    .iter()
+                    .filter_map(|r| r.foreign_key())
+                    .map(|(c, p)| {
+                        (
+                            format!("{}.{:?}", c.table_id, c.column_names),
+                            format!("{}.{:?}", p.table_id, p.column_names),
+                        )
+                    })
+                    .collect();
+                fks.sort();
+                fks
+            };
+            assert_eq!(fks(&parsed), fks(&original));
+        }
+    }
+
+    #[test]
+    fn test_pg_dump_output() {
+        let sql = r#"
+SET statement_timeout = 0;
+
+CREATE TYPE public.mood AS ENUM (
+    'happy',
+    'sad'
+);
+
+CREATE FUNCTION public.touch() RETURNS trigger
+    LANGUAGE plpgsql
+    AS $$ BEGIN NEW.updated_at := now(); RETURN NEW; END; $$;
+
+CREATE TABLE public.users (
+    id integer NOT NULL,
+    email character varying(255) NOT NULL,
+    mood public.mood DEFAULT 'happy'::public.mood,
+    created_at timestamp with time zone DEFAULT now()
+);
+
+CREATE TABLE sales.orders (
+    id bigint NOT NULL,
+    "user_id" integer,
+    total numeric(10,2) DEFAULT 0 NOT NULL
+);
+
+ALTER TABLE ONLY public.users ALTER COLUMN id SET DEFAULT nextval('public.users_id_seq'::regclass);
+ALTER TABLE ONLY public.users ADD CONSTRAINT users_pkey PRIMARY KEY (id);
+ALTER TABLE ONLY public.users ADD CONSTRAINT users_email_key UNIQUE (email);
+ALTER TABLE ONLY sales.orders ADD CONSTRAINT orders_pkey PRIMARY KEY (id);
+ALTER TABLE ONLY sales.orders
+    ADD CONSTRAINT orders_user_id_fkey FOREIGN KEY (user_id) REFERENCES public.users(id) ON DELETE CASCADE;
+
+CREATE INDEX orders_user_idx ON sales.orders USING btree (user_id);
+CREATE UNIQUE INDEX users_lower_email ON public.users USING btree (lower((email)::text));
+COMMENT ON TABLE public.users IS 'Registered users';
+COMMENT ON COLUMN sales.orders.total IS 'In cents';
+"#;
+        let d = parse_sql(sql).unwrap();
+
+        assert_eq!(d.enums.len(), 1);
+        assert_eq!(d.enums[0].values.len(), 2);
+
+        let users = &d.tables[0];
+        assert_eq!(users.id, TableId::new("public", "users"));
+        assert_eq!(users.note.as_deref(), Some("Registered users"));
+        let id = &users.columns[0];
+        assert!(id.is_pk && id.is_increment && id.default.is_none());
+        assert_eq!(users.columns[1].type_raw, "character varying(255)");
+        assert!(users.columns[1].is_unique);
+        assert_eq!(users.columns[2].type_raw, "mood");
+        assert_eq!(
+            users.columns[2].default,
+            Some(DefaultValue::String("happy".into()))
+        );
+        assert_eq!(users.columns[3].type_raw, "timestamp with time zone");
+        assert_eq!(
+            users.columns[3].default,
+            Some(DefaultValue::Expression("now()".into()))
+        );
+        assert_eq!(
+            users.indexes[0].columns,
+            vec![IndexColumn::Expression("lower((email)::text)".into())]
+        );
+
+        let orders = &d.tables[1];
+        assert_eq!(orders.id, TableId::new("sales", "orders"));
+        assert_eq!(orders.columns[2].type_raw, "numeric(10,2)");
+        assert!(!orders.columns[2].is_nullable);
+        assert_eq!(orders.columns[2].note.as_deref(), Some("In cents"));
+        assert_eq!(orders.indexes[0].index_type.as_deref(), Some("btree"));
+
+        assert_eq!(d.relationships.len(), 1);
+        let rel = &d.relationships[0];
+        assert_eq!(rel.relation_type, RelationType::ManyToOne);
+        assert_eq!(rel.from.table_id, orders.id);
+        assert_eq!(rel.to.column_names, vec!["id"]);
+        assert_eq!(rel.on_delete, Some(ReferentialAction::Cascade));
+        assert_eq!(rel.name.as_deref(), Some("orders_user_id_fkey"));
+    }
+
+    #[test]
+    fn test_mysql_dump_output() {
+        let sql = r#"
+/*!40101 SET NAMES utf8mb4 */;
+DROP TABLE IF EXISTS `posts`;
+CREATE TABLE `users` (
+  `id` int unsigned NOT NULL AUTO_INCREMENT,
+  `status` enum('active','banned') NOT NULL DEFAULT 'active' COMMENT 'Account state',
+  PRIMARY KEY (`id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COMMENT='People';
+CREATE TABLE `posts` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `user_id` int unsigned REFERENCES users,
+  `slug` varchar(64) CHARACTER SET ascii NOT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `posts_slug` (`slug`(32)),
+  KEY `posts_user` (`user_id`, `id`) USING BTREE,
+  CONSTRAINT `posts_user_fk` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`) ON UPDATE NO ACTION
+);
+"#;
+        let d = parse_sql(sql).unwrap();
+
+        let users = &d.tables[0];
+        assert_eq!(users.note.as_deref(), Some("People"));
+        assert_eq!(users.columns[0].type_raw, "int unsigned");
+        assert!(users.columns[0].is_pk && users.columns[0].is_increment);
+        assert_eq!(users.columns[1].type_raw, "enum('active','banned')");
+        assert_eq!(users.columns[1].note.as_deref(), Some("Account state"));
+
+        let posts = &d.tables[1];
+        assert_eq!(posts.columns[2].type_raw, "varchar(64)");
+        assert!(posts.columns[2].is_unique);
+        assert_eq!(posts.indexes.len(), 1);
+        assert_eq!(posts.indexes[0].name.as_deref(), Some("posts_user"));
+        assert_eq!(posts.indexes[0].index_type.as_deref(), Some("btree"));
+
+        // Inline REFERENCES without columns points at the primary key
+        assert_eq!(d.relationships.len(), 2);
+        assert!(d.relationships[0].is_inline);
+        assert_eq!(d.relationships[0].to.column_names, vec!["id"]);
+        assert_eq!(
+            d.relationships[1].on_update,
+            Some(ReferentialAction::NoAction)
+        );
+    }
+
+    #[test]
+    fn test_reports_line_of_error() {
+        let err = parse_sql("CREATE TABLE t (\n  id int,\n  CONSTRAINT\n);").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to parse SQL: line 4: expected an identifier"
+        );
+    }
+}
@@ -0,0 +1,367 @@
+//! SQLite database front end.
+//!
+//! Reads the schema of a local database file through its pragmas
+//! (`table_list`, `table_xinfo`, `index_list`, `index_info`,
+//! `foreign_key_list`) instead of parsing the stored `CREATE` statements.
+//! The database is opened read-only.
+
+use std::path::Path;
+
+use rusqlite::{Connection, OpenFlags};
+
+use super::sql::{is_increment, parse_default, resolve_references};
+use crate::cardinality;
+use crate::error::AppError;
+use crate::ir::{
+    Column, Diagram, EndPoint, Index, IndexColumn, ReferentialAction, RelationType, Relationship,
+    Table, TableId,
+};
+
+/// First bytes of every SQLite 3 database file.
+pub const MAGIC: &[u8] = b"SQLite format 3\0";
+
+/// Read the schema of the SQLite database at `path` into a Diagram.
+pub fn read_sqlite(path: &Path) -> Result<Diagram, AppError> {
+    let conn = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(sqlite_error)?;
+
+    let mut diagram = Diagram::default();
+    for (name, sql) in table_names(&conn).map_err(sqlite_error)? {
+        let (table, rels) = read_table(&conn, &name, &sql).map_err(sqlite_error)?;
+        diagram.tables.push(table);
+        diagram.relationships.extend(rels);
+    }
+
+    // SQLite looks up the tables and columns foreign keys refer to ignoring
+    // case; use the names they were declared with
+    for rel in &mut diagram.relationships {
+        let parent = diagram
+            .tables
+            .iter()
+            .find(|t| t.id.name.eq_ignore_ascii_case(&rel.to.table_id.name));
+        if let Some(parent) = parent {
+            rel.to.table_id = parent.id.clone();
+            for name in &mut rel.to.column_names {
+                if let Some(c) = parent
+                    .columns
+                    .iter()
+                    .find(|c| c.name.eq_ignore_ascii_case(name))
+                {
+                    name.clone_from(&c.name);
+                }
+            }
+        }
+    }
+
+    resolve_references(&mut diagram);
+    cardinality::infer(&mut diagram);
+    Ok(diagram)
+}
+
+/// Ordinary tables in creation order, with their `CREATE TABLE` text.
+/// Views, virtual tables, their shadow tables and SQLite's own tables are left out.
+fn table_names(conn: &Connection) -> rusqlite::Result<Vec<(String, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT m.name, m.sql FROM sqlite_schema m \
+         JOIN pragma_table_list l ON l.name = m.name AND l.schema = 'main' \
+         WHERE m.type = 'table' AND l.type = 'table' AND m.name NOT LIKE 'sqlite\\_%' ESCAPE '\\' \
+         ORDER BY m.rowid",
+    )?;
+    let rows = stmt.query_map([], |r| {
+        Ok((
+            r.get(0)?,
+            r.get::<_, Option<String>>(1)?.unwrap_or_default(),
+        ))
+    })?;
+    rows.collect()
+}
+
+fn read_table(
+    conn: &Connection,
+    name: &str,
+    sql: &str,
+) -> rusqlite::Result<(Table, Vec<Relationship>)> {
+    let id = TableId::new("public", name);
+
+    // (column, position in the primary key)
+    let mut stmt = conn.prepare(
+        "SELECT name, type, \"notnull\", dflt_value, pk FROM pragma_table_xinfo(?1) \
+         WHERE hidden IN (0, 2, 3) ORDER BY cid",
+    )?;
+    let rows: Vec<(Column, i64)> = stmt
+        .query_map([name], |r| {
+            let type_raw: String = r.get(1)?;
+            let default: Option<String> = r.get(3)?;
+            let pk: i64 = r.get(4)?;
+            Ok((
+                Column {
+                    name: r.get(0)?,
+                    // Columns without a declared type have BLOB affinity
+                    type_raw: if type_raw.is_empty() {
+                        "blob".to_string()
+                    } else {
+                        type_raw
+                    },
+                    is_nullable: !r.get::<_, bool>(2)? && pk == 0,
+                    default: default.as_deref().and_then(parse_default),
+                    ..Default::default()
+                },
+                pk,
+            ))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let mut pk: Vec<(i64, String)> = rows
+        .iter()
+        .filter(|(_, pos)| *pos > 0)
+        .map(|(c, pos)| (*pos, c.name.clone()))
+        .collect();
+    pk.sort();
+    let mut columns: Vec<Column> = rows.into_iter().map(|(c, _)| c).collect();
+    let mut indexes = Vec::new();
+
+    if let [(_, single)] = pk.as_slice() {
+        if let Some(col) = columns.iter_mut().find(|c| c.name == *single) {
+            col.is_pk = true;
+            // Only an INTEGER PRIMARY KEY can be declared AUTOINCREMENT
+            col.is_increment = is_increment(sql, &col.name);
+        }
+    } else if !pk.is_empty() {
+        indexes.push(Index {
+            columns: pk
+                .into_iter()
+                .map(|(_, c)| IndexColumn::Column(c))
+                .collect(),
+            name: None,
+            is_unique: false,
+            is_pk: true,
+            index_type: None,
+            note: None,
+            comments: Vec::new(),
+            trailing_comment: None,
+        });
+    }
+
+    // origin: 'pk' = PRIMARY KEY, 'u' = UNIQUE constraint, 'c' = CREATE INDEX
+    let mut stmt = conn.prepare(
+        "SELECT name, \"unique\", origin FROM pragma_index_list(?1) \
+         WHERE origin != 'pk' ORDER BY seq DESC",
+    )?;
+    let index_list: Vec<(String, bool, String)> = stmt
+        .query_map([name], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    for (index_name, is_unique, origin) in index_list {
+        // Expression columns have no name and are left out
+        let mut stmt = conn.prepare(
+            "SELECT name FROM pragma_index_info(?1) WHERE name IS NOT NULL ORDER BY seqno",
+        )?;
+        let index_columns: Vec<String> = stmt
+            .query_map([&index_name], |r| r.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        if index_columns.is_empty() {
+            continue;
+        }
+
+        if origin == "u" {
+            if let [single] = index_columns.as_slice() {
+                if let Some(col) = columns.iter_mut().find(|c| c.name == *single) {
+                    col.is_unique = true;
+                    continue;
+                }
+            }
+        }
+        indexes.push(Index {
+            columns: index_columns.into_iter().map(IndexColumn::Column).collect(),
+            // Names of constraint indexes are generated, e.g. sqlite_autoindex_users_1
+            name: (origin == "c").then_some(index_name),
+            is_unique,
+            is_pk: false,
+            index_type: None,
+            note: None,
+            comments: Vec::new(),
+            trailing_comment: None,
+        });
+    }
+
+    let relationships = read_foreign_keys(conn, &id)?;
+    let table = Table {
+        id,
+        columns,
+        indexes,
+        ..Default::default()
+    };
+    Ok((table, relationships))
+}
+
+fn read_foreign_keys(conn: &Connection, id: &TableId) -> rusqlite::Result<Vec<Relationship>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, \"table\", \"from\", \"to\", on_update, on_delete \
+         FROM pragma_foreign_key_list(?1) ORDER BY id, seq",
+    )?;
+    let rows = stmt.query_map([&id.name], |r| {
+        Ok((
+            r.get::<_, i64>(0)?,
+            r.get::<_, String>(1)?,
+            r.get::<_, String>(2)?,
+            r.get::<_, Option<String>>(3)?,
+            r.get::<_, String>(4)?,
+            r.get::<_, String>(5)?,
+        ))
+    })?;
+
+    // One row per column; composite keys share an id
+    let mut relationships: Vec<(i64, Relationship)> = Vec::new();
+    for row in rows {
+        let (fk_id, parent, from, to, on_update, on_delete) = row?;
+        if relationships.last().map(|(i, _)| *i) != Some(fk_id) {
+            let mut rel = Relationship::new(
+                RelationType::ManyToOne,
+                EndPoint {
+                    table_id: id.clone(),
+                    column_names: Vec::new(),
+                },
+                EndPoint {
+                    table_id: TableId::new("public", parent),
+                    column_names: Vec::new(),
+                },
+            );
+            rel.on_delete = referential_action(&on_delete);
+            rel.on_update = referential_action(&on_update);
+            relationships.push((fk_id, rel));
+        }
+        if let Some((_, rel)) = relationships.last_mut() {
+            rel.from.column_names.push(from);
+            // NULL when the parent's primary key is referenced implicitly
+            if let Some(to) = to {
+                rel.to.column_names.push(to);
+            }
+        }
+    }
+
+    // pragma_foreign_key_list lists constraints last to first
+    Ok(relationships.into_iter().rev().map(|(_, r)| r).collect())
+}
+
+/// `NO ACTION` is what SQLite reports when no action was declared.
+fn referential_action(action: &str) -> Option<ReferentialAction> {
+    match action {
+        "CASCADE" => Some(ReferentialAction::Cascade),
+        "RESTRICT" => Some(ReferentialAction::Restrict),
+        "SET NULL" => Some(ReferentialAction::SetNull),
+        "SET DEFAULT" => Some(ReferentialAction::SetDefault),
+        _ => None,
+    }
+}
+
+fn sqlite_error(e: rusqlite::Error) -> AppError {
+    AppError::SqliteError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::DefaultValue;
+
+    fn open(schema: &str) -> (tempfile::TempDir, Diagram) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.db");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(schema)
+            .unwrap();
+        let diagram = read_sqlite(&path).unwrap();
+        (dir, diagram)
+    }
+
+    #[test]
+    fn test_reads_tables_keys_and_indexes() {
+        let (_dir, d) = open(
+            "CREATE TABLE users (
+               id INTEGER PRIMARY KEY AUTOINCREMENT,
+               email TEXT NOT NULL UNIQUE,
+               created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+               role TEXT DEFAULT 'member'
+             );
+             CREATE TABLE memberships (
+               user_id INTEGER REFERENCES users ON DELETE CASCADE,
+               team TEXT,
+               note,
+               PRIMARY KEY (user_id, team)
+             );
+             CREATE INDEX memberships_team ON memberships (team);
+             CREATE VIEW admins AS SELECT * FROM users;",
+        );
+
+        assert_eq!(d.tables.len(), 2);
+        let users = &d.tables[0];
+        assert_eq!(users.id, TableId::new("public", "users"));
+        assert!(users.columns[0].is_pk && users.columns[0].is_increment);
+        assert!(!users.columns[0].is_nullable);
+        assert!(users.columns[1].is_unique && !users.columns[1].is_nullable);
+        assert_eq!(
+            users.columns[2].default,
+            Some(DefaultValue::Expression("CURRENT_TIMESTAMP".into()))
+        );
+        assert_eq!(
+            users.columns[3].default,
+            Some(DefaultValue::String("member".into()))
+        );
+        assert!(users.indexes.is_empty());
+
+        let memberships = &d.tables[1];
+        assert_eq!(memberships.columns[2].type_raw, "blob");
+        assert!(memberships.indexes[0].is_pk);
+        assert_eq!(
+            memberships.indexes[1].name.as_deref(),
+            Some("memberships_team")
+        );
+
+        assert_eq!(d.relationships.len(), 1);
+        let rel = &d.relationships[0];
+        assert_eq!(rel.from.column_names, vec!["user_id"]);
+        assert_eq!(rel.to.table_id, users.id);
+        assert_eq!(rel.to.column_names, vec!["id"]);
+        assert_eq!(rel.on_delete, Some(ReferentialAction::Cascade));
+        assert_eq!(rel.on_update, None);
+    }
+
+    #[test]
+    fn test_autoincrement_and_reference_case() {
+        let (_dir, d) = open(
+            "CREATE TABLE users (
+               id INTEGER PRIMARY KEY,
+               -- not AUTOINCREMENT
+               note TEXT DEFAULT 'AUTOINCREMENT'
+             );
+             CREATE TABLE posts (
+               \"autoincrement\" INTEGER PRIMARY KEY AUTOINCREMENT,
+               user_id INTEGER REFERENCES Users(ID)
+             ) STRICT;",
+        );
+        assert!(!d.tables[0].columns[0].is_increment);
+        assert!(d.tables[1].columns[0].is_increment);
+
+        assert_eq!(d.relationships.len(), 1);
+        assert_eq!(d.relationships[0].to.table_id, d.tables[0].id);
+        assert_eq!(d.relationships[0].to.column_names, vec!["id"]);
+    }
+
+    #[test]
+    fn test_composite_foreign_keys_keep_declaration_order() {
+        let (_dir, d) = open(
+            "CREATE TABLE a (x INT, y INT, z INT UNIQUE, PRIMARY KEY (x, y));
+             CREATE TABLE b (
+               ax INT, ay INT, az INT,
+               FOREIGN KEY (ax, ay) REFERENCES a (x, y),
+               FOREIGN KEY (az) REFERENCES a (z)
+             );",
+        );
+        assert_eq!(d.relationships.len(), 2);
+        assert_eq!(d.relationships[0].from.column_names, vec!["ax", "ay"]);
+        assert_eq!(d.relationships[0].to.column_names, vec!["x", "y"]);
+        assert_eq!(d.relationships[1].to.column_names, vec!["z"]);
+    }
+}
@@ -0,0 +1,191 @@
+//! Join paths between two tables: the shortest chains of relationships
+//! connecting them, for writing joins across a schema.
+//!
+//! Relationships are followed in either direction. Mirrors `findPath()`
+//! in `editor.js`, which highlights the same paths.
+
+use std::collections::VecDeque;
+
+use crate::graph::table_index;
+use crate::ir::{Diagram, EndPoint, RelationType, Relationship};
+
+/// One join along a path: relationship `rel` from table `from` to `to`,
+/// all indices into the diagram.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JoinStep {
+    pub rel: usize,
+    pub from: usize,
+    pub to: usize,
+}
+
+/// Up to `limit` shortest paths from `start` to `end`, in the order the
+/// relationships are declared. Empty if the tables are not connected.
+pub fn shortest_paths(
+    diagram: &Diagram,
+    start: usize,
+    end: usize,
+    limit: usize,
+) -> Vec<Vec<JoinStep>> {
+    // Steps out of each table; self-references never shorten a path
+    let mut steps: Vec<Vec<JoinStep>> = vec![Vec::new(); diagram.tables.len()];
+    for (rel, r) in diagram.relationships.iter().enumerate() {
+        let (Some(a), Some(b)) = (
+            table_index(diagram, &r.from.table_id),
+            table_index(diagram, &r.to.table_id),
+        ) else {
+            continue;
+        };
+        if a != b {
+            steps[a].push(JoinStep {
+                rel,
+                from: a,
+                to: b,
+            });
+            steps[b].push(JoinStep {
+                rel,
+                from: b,
+                to: a,
+            });
+        }
+    }
+
+    // Distance of every table from `end`, so paths can be walked forward
+    // from `start` along steps that get one closer each time
+    let mut distance = vec![usize::MAX; diagram.tables.len()];
+    distance[end] = 0;
+    let mut queue = VecDeque::from([end]);
+    while let Some(table) = queue.pop_front() {
+        for step in &steps[table] {
+            if distance[step.to] == usize::MAX {
+                distance[step.to] = distance[table] + 1;
+                queue.push_back(step.to);
+            }
+        }
+    }
+    if distance[start] == usize::MAX {
+        return Vec::new();
+    }
+
+    let mut paths = Vec::new();
+    let mut path = Vec::new();
+    walk(&steps, &distance, start, &mut path, &mut paths, limit);
+    paths
+}
+
+fn walk(
+    steps: &[Vec<JoinStep>],
+    distance: &[usize],
+    table: usize,
+    path: &mut Vec<JoinStep>,
+    paths: &mut Vec<Vec<JoinStep>>,
+    limit: usize,
+) {
+    if paths.len() == limit {
+        return;
+    }
+    if distance[table] == 0 {
+        paths.push(path.clone());
+        return;
+    }
+    for step in &steps[table] {
+        if distance[step.to] + 1 == distance[table] {
+            path.push(*step);
+            walk(steps, distance, step.to, path, paths, limit);
+            path.pop();
+        }
+    }
+}
+
+/// The `FROM`/`JOIN ... ON` clauses for a path from table `start`.
+pub fn join_sql(diagram: &Diagram, start: usize, path: &[JoinStep]) -> String {
+    let mut out = format!("FROM {}\n", diagram.tables[start].id);
+    for step in path {
+        let rel = &diagram.relationships[step.rel];
+        let (near, far) = ends(rel, diagram, step.from);
+        let to = &diagram.tables[step.to].id;
+        let from = &diagram.tables[step.from].id;
+        let conditions: Vec<String> = far
+            .column_names
+            .iter()
+            .zip(&near.column_names)
+            .map(|(f, n)| format!("{}.{} = {}.{}", to, f, from, n))
+            .collect();
+        out.push_str(&format!("JOIN {} ON {}", to, conditions.join(" AND ")));
+        if rel.relation_type == RelationType::ManyToMany {
+            out.push_str(" -- many-to-many, through a junction table");
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// The endpoints of `rel` on the side of table `from` and the other side.
+fn ends<'a>(rel: &'a Relationship, diagram: &Diagram, from: usize) -> (&'a EndPoint, &'a EndPoint) {
+    if rel.from.table_id == diagram.tables[from].id {
+        (&rel.from, &rel.to)
+    } else {
+        (&rel.to, &rel.from)
+    }
+}
+
+/// Text report of the paths from `start` to `end`.
+pub fn report(diagram: &Diagram, start: usize, end: usize, paths: &[Vec<JoinStep>]) -> String {
+    let (from, to) = (&diagram.tables[start].id, &diagram.tables[end].id);
+    if paths.is_empty() {
+        return format!("No path between {} and {}\n", from, to);
+    }
+    let mut out = String::new();
+    for (i, path) in paths.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        let mut names = vec![from.to_string()];
+        names.extend(path.iter().map(|s| diagram.tables[s.to].id.to_string()));
+        let joins = path.len();
+        out.push_str(&format!(
+            "{} ({} join{})\n",
+            names.join(" -> "),
+            joins,
+            if joins == 1 { "" } else { "s" }
+        ));
+        for line in join_sql(diagram, start, path).lines() {
+            out.push_str(&format!("  {}\n", line));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::resolve_table;
+    use crate::parser::parse_dbml;
+
+    #[test]
+    fn test_shortest_paths() {
+        let diagram = parse_dbml(
+            "Project p {\n  database_type: 'PostgreSQL'\n}\n\
+             Table users {\n  id int [pk]\n  manager_id int [ref: > users.id]\n}\n\
+             Table orders {\n  id int [pk]\n  user_id int [ref: > users.id]\n}\n\
+             Table carts {\n  id int [pk]\n  user_id int [ref: > users.id]\n}\n\
+             Table invoices {\n  id int [pk]\n  order_id int [ref: > orders.id]\n\
+               cart_id int [ref: > carts.id]\n}\n\
+             Table audit {\n  id int [pk]\n}\n",
+        )
+        .unwrap();
+        let table = |name| resolve_table(&diagram, name).unwrap();
+        let (invoices, users) = (table("invoices"), table("users"));
+
+        let paths = shortest_paths(&diagram, invoices, users, 10);
+        assert_eq!(paths.len(), 2);
+        assert_eq!(
+            join_sql(&diagram, invoices, &paths[0]),
+            "FROM public.invoices\n\
+             JOIN public.orders ON public.orders.id = public.invoices.order_id\n\
+             JOIN public.users ON public.users.id = public.orders.user_id\n"
+        );
+        assert_eq!(shortest_paths(&diagram, invoices, users, 1).len(), 1);
+        assert!(shortest_paths(&diagram, invoices, table("audit"), 10).is_empty());
+        assert_eq!(shortest_paths(&diagram, users, users, 10), vec![vec![]]);
+    }
+}
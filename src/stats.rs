@@ -0,0 +1,256 @@
+//! Schema statistics: sizes, how connected the tables are, and the shape
+//! of the foreign key graph, for tracking a schema's complexity over time.
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::graph::{self, FkEdge};
+use crate::ir::Diagram;
+
+/// Output formats of `dbml-draw stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum StatsFormat {
+    Text,
+    Json,
+}
+
+/// Most hub tables listed.
+const HUB_COUNT: usize = 5;
+/// Most foreign key cycles listed.
+const MAX_CYCLES: usize = 100;
+
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    pub tables: usize,
+    pub columns: usize,
+    pub relationships: usize,
+    pub schemas: BTreeMap<String, SchemaStats>,
+    /// Every table, most connected first.
+    pub degrees: Vec<TableDegree>,
+    /// The most connected tables.
+    pub hubs: Vec<String>,
+    /// Groups of tables connected by any relationship, largest first.
+    pub components: Vec<Vec<String>>,
+    /// Each cycle's tables, the first one repeated at the end.
+    pub cycles: Vec<Vec<String>>,
+    /// Whether there are more cycles than listed.
+    pub cycles_truncated: bool,
+    /// Strongly connected components of more than one table.
+    pub strongly_connected: Vec<Vec<String>>,
+    /// Tables from the first child to the last parent.
+    pub longest_chain: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SchemaStats {
+    pub tables: usize,
+    pub columns: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TableDegree {
+    pub table: String,
+    /// Foreign keys from this table.
+    pub references: usize,
+    /// Foreign keys to this table from other tables or itself.
+    pub referenced_by: usize,
+    /// All relationships touching the table, many-to-many included.
+    pub total: usize,
+}
+
+pub fn stats(diagram: &Diagram) -> Stats {
+    let name = |i: usize| diagram.tables[i].id.full_name();
+    let names = |tables: &[usize]| tables.iter().map(|&i| name(i)).collect::<Vec<_>>();
+
+    let mut schemas: BTreeMap<String, SchemaStats> = BTreeMap::new();
+    for table in &diagram.tables {
+        let schema = schemas.entry(table.id.schema.clone()).or_default();
+        schema.tables += 1;
+        schema.columns += table.columns.len();
+    }
+
+    let mut degrees: Vec<TableDegree> = diagram
+        .tables
+        .iter()
+        .map(|t| TableDegree {
+            table: t.id.full_name(),
+            references: 0,
+            referenced_by: 0,
+            total: 0,
+        })
+        .collect();
+    for edge in graph::fk_edges(diagram) {
+        degrees[edge.child].references += 1;
+        degrees[edge.parent].referenced_by += 1;
+    }
+    for rel in &diagram.relationships {
+        let from = graph::table_index(diagram, &rel.from.table_id);
+        let to = graph::table_index(diagram, &rel.to.table_id);
+        for i in from.into_iter().chain(to.filter(|&t| Some(t) != from)) {
+            degrees[i].total += 1;
+        }
+    }
+    // Stable, so ties keep declaration order
+    degrees.sort_by_key(|d| Reverse(d.total));
+    let hubs = degrees
+        .iter()
+        .filter(|d| d.total > 0)
+        .take(HUB_COUNT)
+        .map(|d| d.table.clone())
+        .collect();
+
+    let mut components = graph::connected_components(diagram);
+    components.sort_by_key(|c| Reverse(c.len()));
+
+    let cycles = graph::fk_cycles(diagram, MAX_CYCLES + 1);
+    let cycle_names = |cycle: &Vec<FkEdge>| {
+        let mut tables = vec![name(cycle[0].child)];
+        tables.extend(cycle.iter().map(|e| name(e.parent)));
+        tables
+    };
+
+    Stats {
+        tables: diagram.tables.len(),
+        columns: diagram.tables.iter().map(|t| t.columns.len()).sum(),
+        relationships: diagram.relationships.len(),
+        schemas,
+        degrees,
+        hubs,
+        components: components.iter().map(|c| names(c)).collect(),
+        cycles_truncated: cycles.len() > MAX_CYCLES,
+        cycles: cycles.iter().take(MAX_CYCLES).map(cycle_names).collect(),
+        strongly_connected: graph::strongly_connected_components(diagram)
+            .iter()
+            .filter(|c| c.len() > 1)
+            .map(|c| names(c))
+            .collect(),
+        longest_chain: names(&graph::longest_chain(diagram)),
+    }
+}
+
+/// Human-readable report of `stats`.
+pub fn report(stats: &Stats) -> String {
+    let mut out = format!(
+        "{} tables, {} columns, {} relationships\n",
+        stats.tables, stats.columns, stats.relationships
+    );
+
+    out.push_str("\nSchemas\n");
+    let width = stats.schemas.keys().map(String::len).max().unwrap_or(0);
+    for (name, schema) in &stats.schemas {
+        out.push_str(&format!(
+            "  {:width$}  {:>4} tables  {:>5} columns\n",
+            name, schema.tables, schema.columns
+        ));
+    }
+
+    out.push_str("\nDegree (references, referenced by, total)\n");
+    let width = stats
+        .degrees
+        .iter()
+        .map(|d| d.table.len())
+        .max()
+        .unwrap_or(0);
+    for degree in &stats.degrees {
+        out.push_str(&format!(
+            "  {:width$}  {:>3}  {:>3}  {:>3}\n",
+            degree.table, degree.references, degree.referenced_by, degree.total
+        ));
+    }
+
+    out.push_str(&format!("\nHubs: {}\n", list(&stats.hubs)));
+
+    let sizes: Vec<String> = stats
+        .components
+        .iter()
+        .map(|c| c.len().to_string())
+        .collect();
+    out.push_str(&format!(
+        "\nConnected components: {} (sizes {})\n",
+        stats.components.len(),
+        list(&sizes)
+    ));
+
+    out.push_str(&format!(
+        "\nForeign key cycles: {}{}\n",
+        stats.cycles.len(),
+        if stats.cycles_truncated {
+            " (more not listed)"
+        } else {
+            ""
+        }
+    ));
+    for cycle in &stats.cycles {
+        out.push_str(&format!("  {}\n", cycle.join(" -> ")));
+    }
+
+    out.push_str(&format!(
+        "\nStrongly connected components: {}\n",
+        stats.strongly_connected.len()
+    ));
+    for component in &stats.strongly_connected {
+        out.push_str(&format!("  {}\n", component.join(", ")));
+    }
+
+    out.push_str(&format!(
+        "\nLongest dependency chain: {} tables\n",
+        stats.longest_chain.len()
+    ));
+    if stats.longest_chain.len() > 1 {
+        out.push_str(&format!("  {}\n", stats.longest_chain.join(" -> ")));
+    }
+    out
+}
+
+fn list(items: &[String]) -> String {
+    if items.is_empty() {
+        "none".to_string()
+    } else {
+        items.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_dbml;
+
+    #[test]
+    fn test_stats() {
+        let diagram = parse_dbml(
+            "Project p {\n  database_type: 'PostgreSQL'\n}\n\
+             Table users {\n  id int [pk]\n  manager_id int [ref: > users.id]\n}\n\
+             Table orders {\n  id int [pk]\n  user_id int [ref: > users.id]\n}\n\
+             Table billing.invoices {\n  id int [pk]\n  order_id int [ref: > orders.id]\n}\n\
+             Table tags {\n  id int [pk]\n}\n",
+        )
+        .unwrap();
+        let stats = stats(&diagram);
+
+        assert_eq!(
+            (stats.tables, stats.columns, stats.relationships),
+            (4, 7, 3)
+        );
+        assert_eq!(stats.schemas["public"].tables, 3);
+        assert_eq!(stats.schemas["billing"].columns, 2);
+        assert_eq!(stats.degrees[0].table, "public.users");
+        assert_eq!(
+            (stats.degrees[0].references, stats.degrees[0].referenced_by),
+            (1, 2)
+        );
+        assert_eq!(
+            stats.hubs,
+            ["public.users", "public.orders", "billing.invoices"]
+        );
+        assert_eq!(stats.components.len(), 2);
+        assert_eq!(stats.cycles, [["public.users", "public.users"]]);
+        assert!(stats.strongly_connected.is_empty());
+        assert_eq!(
+            stats.longest_chain,
+            ["billing.invoices", "public.orders", "public.users"]
+        );
+        assert!(report(&stats).contains("Foreign key cycles: 1\n  public.users -> public.users\n"));
+    }
+}